@@ -13,18 +13,16 @@ module.exports = function(RED) {
 
             try {
                 const axios = require('axios');
-                const response = await axios.post('http://localhost:8080/api/qkd/encrypt', {
-                    data: String(data),
-                    key_size: keySize
+                const response = await axios.post('http://localhost:8080/api/nodered/qkd-encrypt', {
+                    _msgid: msg._msgid,
+                    topic: msg.topic,
+                    payload: {
+                        data: String(data),
+                        key_size: keySize
+                    }
                 });
 
-                msg.payload = {
-                    original: data,
-                    ciphertext: response.data.ciphertext,
-                    session_id: response.data.session_id,
-                    algorithm: 'QKD-BB84-Hybrid',
-                    timestamp: new Date().toISOString()
-                };
+                msg.payload = response.data.payload;
 
                 node.status({fill: "green", shape: "dot", text: "encrypted"});
                 node.send(msg);
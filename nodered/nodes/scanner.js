@@ -13,20 +13,19 @@ module.exports = function(RED) {
             node.status({fill: "blue", shape: "dot", text: `scanning ${target}`});
 
             try {
-                // Call PyRouterSploit REST API
+                // Call the PyRouterSploit Node-RED bridge
                 const axios = require('axios');
-                const response = await axios.post('http://localhost:8080/api/scan', {
-                    target: target,
-                    scan_type: scanType,
-                    threads: threads
+                const response = await axios.post('http://localhost:8080/api/nodered/scanner', {
+                    _msgid: msg._msgid,
+                    topic: msg.topic,
+                    payload: {
+                        target: target,
+                        scan_type: scanType,
+                        threads: threads
+                    }
                 });
 
-                msg.payload = {
-                    target: target,
-                    scan_id: response.data.scan_id,
-                    status: 'initiated',
-                    timestamp: new Date().toISOString()
-                };
+                msg.payload = response.data.payload;
 
                 node.status({fill: "green", shape: "dot", text: "scan initiated"});
                 node.send(msg);
@@ -0,0 +1,239 @@
+// Authentication context forwarded by the PYRO gateway
+// The gateway validates the bearer token and passes the identity as X-PYRO-* headers.
+// Integrators calling the service directly can use an X-API-Key instead.
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::Response,
+};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::crypto::hashing::password::{hash_password, verify_password};
+use crate::db::models::ApiKeyRecord;
+use crate::db::redb_client::api_keys;
+use super::request_id::RequestId;
+use super::response::{error_codes, PyroErrorResponse};
+
+pub const USER_ID_HEADER: &str = "x-pyro-user-id";
+pub const USERNAME_HEADER: &str = "x-pyro-username";
+pub const ROLES_HEADER: &str = "x-pyro-roles";
+pub const TENANT_ID_HEADER: &str = "x-pyro-tenant-id";
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Keys look like `pyro_<key_id>_<secret>`; the id finds the stored hash
+const API_KEY_PREFIX: &str = "pyro_";
+
+lazy_static! {
+    /// SHA-256 of each key that passed verification, to its key id
+    static ref VERIFIED_KEYS: Mutex<HashMap<[u8; 32], String>> = Mutex::new(HashMap::new());
+}
+
+fn key_digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+/// Key id of `key` if it has already passed verification. A cheap check
+/// for the rate limiter, which runs before the Argon2 one; it doesn't
+/// authenticate the request.
+pub fn verified_key_id(key: &str) -> Option<String> {
+    VERIFIED_KEYS.lock().get(&key_digest(key)).cloned()
+}
+
+/// Authenticated user context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyroUserContext {
+    pub user_id: String,
+    pub username: String,
+    pub roles: Vec<String>,
+    pub tenant_id: Option<String>,
+}
+
+impl PyroUserContext {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+fn header(parts: &Parts, name: &str) -> Option<String> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// 401 response in the PYRO error format
+pub fn unauthorized(parts: &Parts, message: &str) -> Response {
+    let meta = parts
+        .extensions
+        .get::<RequestId>()
+        .map(RequestId::meta)
+        .unwrap_or_default();
+
+    PyroErrorResponse::new(error_codes::UNAUTHORIZED, message, meta)
+        .into_response_with(StatusCode::UNAUTHORIZED)
+}
+
+/// Create and store an API key. The returned plaintext is the only copy.
+pub fn issue_api_key(name: &str, tenant_id: Option<String>, roles: Vec<String>) -> Result<(String, ApiKeyRecord)> {
+    let key_id = uuid::Uuid::new_v4().simple().to_string();
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    let secret = hex::encode(secret);
+
+    let record = ApiKeyRecord {
+        key_id: key_id.clone(),
+        name: name.to_string(),
+        secret_hash: hash_password(&secret)?,
+        tenant_id,
+        roles,
+        created_at: Utc::now(),
+    };
+    api_keys::insert(&record)?;
+    Ok((format!("{}{}_{}", API_KEY_PREFIX, key_id, secret), record))
+}
+
+/// Identity for a presented API key, or `None` if it is malformed, unknown
+/// or has the wrong secret. The Argon2 check runs off the async workers.
+pub async fn authenticate_api_key(key: &str) -> Result<Option<PyroUserContext>> {
+    let Some((key_id, secret)) = key.strip_prefix(API_KEY_PREFIX).and_then(|rest| rest.split_once('_')) else {
+        return Ok(None);
+    };
+    let Some(record) = api_keys::get_by_id(key_id)? else {
+        // Revoked keys stop counting as verified
+        VERIFIED_KEYS.lock().retain(|_, id| id != key_id);
+        return Ok(None);
+    };
+
+    let secret = secret.to_string();
+    let hash = record.secret_hash.clone();
+    let valid = tokio::task::spawn_blocking(move || verify_password(&secret, &hash))
+        .await
+        .map_err(|e| anyhow!("API key verification panicked: {}", e))??;
+    if !valid {
+        return Ok(None);
+    }
+    VERIFIED_KEYS.lock().insert(key_digest(key), record.key_id.clone());
+
+    Ok(Some(PyroUserContext {
+        user_id: format!("api-key:{}", record.key_id),
+        username: record.name,
+        roles: record.roles,
+        tenant_id: record.tenant_id,
+    }))
+}
+
+/// Gateway identity headers take precedence; an API key is only checked without them
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for PyroUserContext {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(user_id) = header(parts, USER_ID_HEADER) else {
+            let Some(key) = header(parts, API_KEY_HEADER) else {
+                return Err(unauthorized(parts, "Missing authenticated user context"));
+            };
+            return match authenticate_api_key(&key).await {
+                Ok(Some(user)) => Ok(user),
+                Ok(None) => Err(unauthorized(parts, "Invalid API key")),
+                Err(e) => {
+                    tracing::error!("API key lookup failed: {}", e);
+                    Err(unauthorized(parts, "Invalid API key"))
+                }
+            };
+        };
+
+        let roles = header(parts, ROLES_HEADER)
+            .map(|r| {
+                r.split(',')
+                    .map(|role| role.trim().to_string())
+                    .filter(|role| !role.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            username: header(parts, USERNAME_HEADER).unwrap_or_else(|| user_id.clone()),
+            user_id,
+            roles,
+            tenant_id: header(parts, TENANT_ID_HEADER),
+        })
+    }
+}
+
+/// A user acting for a tenant, for routes whose data is scoped to one.
+/// Callers without a tenant are refused with 403, since they would
+/// otherwise all share the same unscoped data.
+#[derive(Debug, Clone)]
+pub struct TenantUser {
+    pub user: PyroUserContext,
+    pub tenant_id: String,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for TenantUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = PyroUserContext::from_request_parts(parts, state).await?;
+        let Some(tenant_id) = user.tenant_id.clone() else {
+            let meta = parts.extensions.get::<RequestId>().map(RequestId::meta).unwrap_or_default();
+            return Err(PyroErrorResponse::new(error_codes::FORBIDDEN, "This route requires a tenant", meta)
+                .into_response_with(StatusCode::FORBIDDEN));
+        };
+        Ok(Self { user, tenant_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::Config;
+    use crate::db::redb_client;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn status_with_key(key: &str) -> StatusCode {
+        let app = create_router_with_config(&Config::default());
+        let request = Request::get("/api/v1/vulnscan/exploits")
+            .header(API_KEY_HEADER, key)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_api_key_authentication() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let (key, record) = issue_api_key("fire-marshal", Some("tenant-a".to_string()), vec!["scanner".to_string()]).unwrap();
+        assert!(!record.secret_hash.contains(key.rsplit('_').next().unwrap()));
+
+        let user = authenticate_api_key(&key).await.unwrap().unwrap();
+        assert_eq!(user.tenant_id.as_deref(), Some("tenant-a"));
+        assert!(user.has_role("scanner"));
+        assert_eq!(status_with_key(&key).await, StatusCode::OK);
+
+        let wrong_secret = format!("{}_{}", key.rsplit_once('_').unwrap().0, "0".repeat(64));
+        assert!(authenticate_api_key(&wrong_secret).await.unwrap().is_none());
+        assert_eq!(status_with_key(&wrong_secret).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_with_key("not-a-key").await, StatusCode::UNAUTHORIZED);
+
+        assert_eq!(verified_key_id(&key), Some(record.key_id.clone()));
+        assert!(verified_key_id(&wrong_secret).is_none());
+        api_keys::delete(&record.key_id).unwrap();
+        assert!(authenticate_api_key(&key).await.unwrap().is_none());
+        assert!(verified_key_id(&key).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+// CORS policy for browser dashboards, from the configured allowlists
+
+use std::str::FromStr;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::db::models::Config;
+
+/// Parse a comma-separated config list, skipping entries that don't parse
+fn parse_list<T: FromStr>(list: &str, what: &str) -> Vec<T> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.parse().ok();
+            if parsed.is_none() {
+                tracing::warn!("Ignoring invalid CORS {}: {}", what, entry);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Layer allowing the configured origins, methods and headers. With no
+/// origins configured no CORS headers are sent, so browsers stay same-origin.
+/// Preflight OPTIONS requests are answered by the layer itself.
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    let origins = if config.cors_allowed_origins.split(',').any(|origin| origin.trim() == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(parse_list::<HeaderValue>(&config.cors_allowed_origins, "origin"))
+    };
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(parse_list::<Method>(&config.cors_allowed_methods, "method"))
+        .allow_headers(parse_list::<HeaderName>(&config.cors_allowed_headers, "header"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::api::rest::create_router_with_config;
+
+    #[tokio::test]
+    async fn test_allowed_origin_only() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config {
+            cors_allowed_origins: "https://dashboard.example".to_string(),
+            ..Config::default()
+        });
+        let allow_origin = |app: axum::Router, method: Method, origin: &'static str| async move {
+            let request = Request::builder()
+                .method(method)
+                .uri("/health")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).cloned()
+        };
+
+        assert_eq!(
+            allow_origin(app.clone(), Method::GET, "https://dashboard.example").await.unwrap(),
+            "https://dashboard.example"
+        );
+        assert!(allow_origin(app.clone(), Method::GET, "https://evil.example").await.is_none());
+
+        // Preflight is answered without reaching the routes
+        assert!(allow_origin(app.clone(), Method::OPTIONS, "https://dashboard.example").await.is_some());
+        assert!(allow_origin(app, Method::OPTIONS, "https://evil.example").await.is_none());
+
+        // Same-origin only by default
+        let app = create_router_with_config(&Config::default());
+        assert!(allow_origin(app, Method::GET, "https://dashboard.example").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_allows_api_key_header() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config {
+            cors_allowed_origins: "https://dashboard.example".to_string(),
+            ..Config::default()
+        });
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/v1/vulnscan/scans")
+            .header(header::ORIGIN, "https://dashboard.example")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, crate::api::auth::API_KEY_HEADER)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let allowed = response.headers().get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap();
+        assert!(allowed.to_str().unwrap().contains(crate::api::auth::API_KEY_HEADER));
+    }
+}
@@ -0,0 +1,341 @@
+// Exploit catalogue endpoints in the PYRO format
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::core::exploit::{registry, Exploit};
+use crate::core::options::OptionSpec;
+use crate::db::models::ExploitMetadata;
+use crate::db::redb_client::{cryptex, exploits};
+use super::auth::PyroUserContext;
+use super::request_id::RequestId;
+use super::response::{error_codes, Pagination, PyroErrorResponse, PyroSuccessResponse};
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExploitListParams {
+    /// Only exploits whose references mention this CVE
+    pub cve: Option<String>,
+    /// Only exploits for devices matching this name, e.g. `DIR-850L`
+    pub target_platform: Option<String>,
+    /// Only exploits changed after this RFC 3339 time, for incremental sync
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Only confirmed (`true`) or unconfirmed (`false`) exploits
+    pub verified: Option<bool>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Apply the filters, starting from whichever the database can narrow
+fn find_exploits(params: &ExploitListParams) -> anyhow::Result<Vec<ExploitMetadata>> {
+    let cve = non_empty(&params.cve);
+    let mut exploits = match (non_empty(&params.target_platform), cve, params.updated_after, params.verified) {
+        (Some(device), _, _, _) => exploits::list_by_device(device)?,
+        (None, Some(cve), _, _) => exploits::find_by_reference(cve)?,
+        (None, None, Some(since), _) => exploits::list_updated_after(since)?,
+        (None, None, None, Some(verified)) => return exploits::list_by_verified(verified),
+        (None, None, None, None) => return exploits::list_all(),
+    };
+
+    if let Some(cve) = cve.map(str::to_lowercase) {
+        exploits.retain(|metadata| metadata.references.iter().any(|r| r.to_lowercase().contains(&cve)));
+    }
+    if let Some(since) = params.updated_after {
+        exploits.retain(|metadata| metadata.updated_at > since);
+    }
+    if let Some(verified) = params.verified {
+        exploits.retain(|metadata| metadata.verified == verified);
+    }
+    Ok(exploits)
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExploitList {
+    pub exploits: Vec<ExploitMetadata>,
+    /// Exploits in this page; `meta.pagination.total` counts every match
+    pub count: usize,
+}
+
+/// GET /api/v1/vulnscan/exploits[?cve=CVE-2019-16920][&target_platform=DIR-850L][&updated_after=...][&verified=true]
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/exploits",
+    tag = "exploits",
+    params(ExploitListParams),
+    responses(
+        (status = 200, description = "Matching exploits", body = ExploitListEnvelope),
+        (status = 500, description = "Exploit catalogue unavailable", body = PyroErrorResponse),
+    )
+)]
+pub async fn list_exploits(
+    Query(params): Query<ExploitListParams>,
+    request_id: RequestId,
+    user: PyroUserContext,
+) -> Response {
+    tracing::debug!("User {} listing exploits {:?}", user.user_id, params);
+
+    match find_exploits(&params) {
+        Ok(exploits) => {
+            let (exploits, pagination) = Pagination::page(exploits, params.limit, params.offset);
+            let count = exploits.len();
+            PyroSuccessResponse::new(ExploitList { exploits, count }, request_id.meta().with_pagination(pagination))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to list exploits: {}", e);
+            PyroErrorResponse::new(error_codes::INTERNAL_ERROR, "Failed to list exploits", request_id.meta())
+                .into_response_with(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Rust implementation of an exploit named by function or branding name,
+/// or by the UUID of its exploit record or cryptex entry
+fn implementation(id: &str) -> anyhow::Result<Option<Box<dyn Exploit>>> {
+    if let Some(exploit) = registry::create(id) {
+        return Ok(Some(exploit));
+    }
+    let Ok(uuid) = uuid::Uuid::parse_str(id) else {
+        return Ok(None);
+    };
+    let entry = match exploits::with_cryptex(&uuid)? {
+        Some((_, entry)) => entry,
+        None => match cryptex::get_by_id(&uuid)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        },
+    };
+    Ok(registry::for_entry(&entry).map(|registration| (registration.constructor)()))
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExploitOptionsSchema {
+    pub exploit: String,
+    pub options: Vec<OptionSpec>,
+}
+
+/// GET /api/v1/vulnscan/exploits/:id/options
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/exploits/{id}/options",
+    tag = "exploits",
+    params(("id" = String, Path, description = "Exploit or cryptex UUID, function name or branding name")),
+    responses(
+        (status = 200, description = "Options the exploit accepts", body = ExploitOptionsEnvelope),
+        (status = 404, description = "No implementation for this exploit", body = PyroErrorResponse),
+        (status = 500, description = "Exploit catalogue unavailable", body = PyroErrorResponse),
+    )
+)]
+pub async fn exploit_options(Path(id): Path<String>, request_id: RequestId, user: PyroUserContext) -> Response {
+    tracing::debug!("User {} fetching options of exploit {}", user.user_id, id);
+
+    match implementation(&id) {
+        Ok(Some(exploit)) => {
+            let schema = ExploitOptionsSchema { exploit: exploit.metadata().name, options: exploit.options_schema() };
+            PyroSuccessResponse::new(schema, request_id.meta()).into_response()
+        }
+        Ok(None) => PyroErrorResponse::new(error_codes::NOT_FOUND, format!("No implementation for exploit {}", id), request_id.meta())
+            .into_response_with(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to resolve exploit {}: {}", id, e);
+            PyroErrorResponse::new(error_codes::INTERNAL_ERROR, "Failed to resolve exploit", request_id.meta())
+                .into_response_with(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::USER_ID_HEADER;
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::{Config, Protocol, Severity};
+    use crate::db::redb_client;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn exploit(name: &str, references: &[&str]) -> ExploitMetadata {
+        exploit_for(name, references, &[])
+    }
+
+    fn exploit_for(name: &str, references: &[&str], devices: &[&str]) -> ExploitMetadata {
+        ExploitMetadata {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: String::new(),
+            authors: vec![],
+            references: references.iter().map(|r| r.to_string()).collect(),
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+            category: "exploits/routers".to_string(),
+            protocol: Protocol::HTTP,
+            severity: Severity::High,
+            verified: true,
+            cryptex_id: None,
+            cvss_vector: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_exploits_by_cve() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let dlink = exploit("D-Link DIR-859 RCE", &["https://nvd.nist.gov/vuln/detail/CVE-2019-17621"]);
+        let netgear = exploit("Netgear R7000 RCE", &["CVE-2016-6277", "https://www.kb.cert.org/vuls/id/582384"]);
+        exploits::insert(&dlink).unwrap();
+        exploits::insert(&netgear).unwrap();
+
+        assert_eq!(exploits::find_by_reference("cve-2016-6277").unwrap()[0].id, netgear.id);
+
+        let app = create_router_with_config(&Config::default());
+        let request = Request::get("/api/v1/vulnscan/exploits?cve=cve-2019-17621")
+            .header(USER_ID_HEADER, "analyst")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["data"]["count"], 1);
+        assert_eq!(body["data"]["exploits"][0]["name"], "D-Link DIR-859 RCE");
+
+        let request = Request::get("/api/v1/vulnscan/exploits")
+            .header(USER_ID_HEADER, "analyst")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["data"]["count"], 2);
+    }
+
+    async fn list(query: &str) -> Value {
+        let app = create_router_with_config(&Config::default());
+        let request = Request::get(format!("/api/v1/vulnscan/exploits{}", query))
+            .header(USER_ID_HEADER, "analyst")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_exploits_by_device() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let dlink = exploit_for("D-Link HNAP RCE", &["CVE-2015-2051"], &["DIR-645", "DIR-850L"]);
+        let netgear = exploit_for("Netgear R7000 RCE", &["CVE-2016-6277"], &["R7000", "R6400"]);
+        let camera = exploit_for("Hikvision Auth Bypass", &["CVE-2017-7921"], &["DS-2CD2032"]);
+        for e in [&dlink, &netgear, &camera] {
+            exploits::insert(e).unwrap();
+        }
+
+        let found = exploits::list_by_device("dir-850l").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, dlink.id);
+        assert_eq!(exploits::list_by_device("r").unwrap().len(), 2);
+
+        let body = list("?target_platform=R6400").await;
+        assert_eq!(body["data"]["count"], 1);
+        assert_eq!(body["data"]["exploits"][0]["name"], "Netgear R7000 RCE");
+
+        let body = list("?target_platform=DIR&cve=CVE-2016-6277").await;
+        assert_eq!(body["data"]["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_exploits_paged() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        for i in 0..5 {
+            exploits::insert(&exploit(&format!("Exploit {}", i), &[])).unwrap();
+        }
+
+        let body = list("?limit=2&offset=2").await;
+        assert_eq!(body["data"]["count"], 2);
+        assert_eq!(body["meta"]["pagination"], serde_json::json!({ "total": 5, "limit": 2, "offset": 2, "has_more": true }));
+
+        let body = list("?limit=2&offset=4").await;
+        assert_eq!(body["data"]["count"], 1);
+        assert_eq!(body["meta"]["pagination"]["has_more"], false);
+
+        let body = list("").await;
+        assert_eq!(body["meta"]["pagination"]["limit"], 50);
+        assert_eq!(body["meta"]["pagination"]["has_more"], false);
+    }
+
+    #[tokio::test]
+    async fn test_list_exploits_by_verified() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let batch: Vec<_> = ["Import A", "Import B", "Import C"]
+            .into_iter()
+            .map(|name| ExploitMetadata { verified: false, ..exploit(name, &[]) })
+            .collect();
+        exploits::insert_many(&batch).unwrap();
+        let names = |body: Value| -> Vec<String> {
+            let mut names: Vec<String> = body["data"]["exploits"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        assert!(exploits::list_by_verified(true).unwrap().is_empty());
+
+        assert_eq!(exploits::mark_verified_many(&[batch[0].id, batch[2].id, Uuid::new_v4()], true).unwrap(), 2);
+        assert_eq!(names(list("?verified=true").await), ["Import A", "Import C"]);
+        assert_eq!(names(list("?verified=false").await), ["Import B"]);
+        assert!(exploits::get_by_id(&batch[0].id).unwrap().unwrap().updated_at > batch[0].updated_at);
+
+        assert!(exploits::mark_verified(&batch[2].id, false).unwrap());
+        assert_eq!(exploits::list_by_verified(true).unwrap()[0].name, "Import A");
+        assert_eq!(names(list("?verified=false&cve=").await), ["Import B", "Import C"]);
+        assert!(!exploits::mark_verified(&Uuid::new_v4(), true).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_exploit_options_schema() {
+        use crate::core::exploit::test_support::{echo, BRANDING_NAME, FUNCTION_NAME};
+        use crate::core::options::ExploitOptions;
+
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        registry::register(FUNCTION_NAME, BRANDING_NAME, || echo().boxed());
+        let get = |uri: String| async move {
+            let app = create_router_with_config(&Config::default());
+            let request = Request::builder().uri(uri).header(USER_ID_HEADER, "user-1").body(Body::empty()).unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+            (status, body)
+        };
+
+        let (status, body) = get(format!("/api/v1/vulnscan/exploits/{}/options", BRANDING_NAME)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["exploit"], "Dummy Echo");
+        let schema: Vec<OptionSpec> = serde_json::from_value(body["data"]["options"].clone()).unwrap();
+        assert_eq!(schema, echo().options_schema());
+        assert_eq!(body["data"]["options"][0]["type"], "string");
+        assert_eq!(body["data"]["options"][0]["required"], true);
+
+        // The served schema is what options are checked against
+        let err = ExploitOptions::validate(&Default::default(), &schema).unwrap_err();
+        assert!(err.to_string().contains("missing required option `command`"), "{}", err);
+        let options = serde_json::json!({ "command": "id" }).as_object().cloned().unwrap();
+        assert_eq!(ExploitOptions::validate(&options, &schema).unwrap().rport, Some(80));
+
+        let (status, body) = get("/api/v1/vulnscan/exploits/exploit_missing/options".to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], error_codes::NOT_FOUND);
+    }
+}
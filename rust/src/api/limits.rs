@@ -0,0 +1,62 @@
+// Request body size and request duration limits
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+
+use super::request_id::RequestId;
+use super::response::{error_codes, PyroErrorResponse};
+
+/// Wrap the bare 413 and 408 responses of the body limit and timeout
+/// layers in the PYRO error envelope
+pub async fn limit_errors(request: Request, next: Next) -> Response {
+    let meta = request
+        .extensions()
+        .get::<RequestId>()
+        .map(RequestId::meta)
+        .unwrap_or_default();
+
+    let response = next.run(request).await;
+    let (code, message) = match response.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => (error_codes::PAYLOAD_TOO_LARGE, "Request body is too large"),
+        StatusCode::REQUEST_TIMEOUT => (error_codes::REQUEST_TIMEOUT, "Request took too long to process"),
+        _ => return response,
+    };
+    PyroErrorResponse::new(code, message, meta).into_response_with(response.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use axum::http::header;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_oversized_body_rejected() {
+        let app = create_router_with_config(&Config {
+            max_body_bytes: 64,
+            ..Config::default()
+        });
+        let body = serde_json::json!({ "target": "192.0.2.1", "scan_type": "x".repeat(100) }).to_string();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/vulnscan/scan")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: PyroErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.status, "error");
+        assert_eq!(error.error.code, error_codes::PAYLOAD_TOO_LARGE);
+    }
+}
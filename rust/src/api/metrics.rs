@@ -0,0 +1,61 @@
+// Request instrumentation and the Prometheus scrape endpoint
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Instant;
+
+use crate::telemetry;
+
+/// Count and time every request. Labelled by route template rather than the
+/// raw path so scan ids don't explode label cardinality.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    telemetry::record_http_request(&method, &path, response.status().as_u16(), started.elapsed());
+    response
+}
+
+/// `GET /metrics`, unauthenticated for Prometheus scrapers
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        telemetry::render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_health_check_counted() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config::default());
+
+        let request = Request::builder().uri("/health").body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap();
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(text.contains(crate::telemetry::HTTP_REQUESTS_TOTAL));
+        assert!(text.contains("path=\"/health\""));
+    }
+}
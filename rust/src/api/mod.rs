@@ -2,6 +2,20 @@
 
 pub mod rest;
 pub mod websocket;
+pub mod scans;
+pub mod exploits;
+pub mod response;
+pub mod rate_limit;
+pub mod auth;
+pub mod request_id;
+pub mod metrics;
+pub mod cors;
+pub mod limits;
+pub mod openapi;
+pub mod version;
 
 pub use rest::*;
 pub use websocket::*;
+pub use scans::*;
+pub use exploits::*;
+pub use response::*;
@@ -0,0 +1,120 @@
+// OpenAPI description of the REST API, served at /api/v1/openapi.json
+
+use axum::Json;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::core::options::{OptionSpec, OptionType};
+use crate::core::report::{AggregateReport, ScanDiff, SeverityBreakdown, TargetDiff, TopFinding};
+use crate::core::scanner::ScanPlan;
+use crate::db::models::{ExploitMetadata, Proof, Protocol, ScanCallbacks, ScanRequest, Severity, VulnerabilityFinding};
+use super::auth::API_KEY_HEADER;
+use super::exploits::{ExploitList, ExploitOptionsSchema};
+use super::response::{
+    AggregateReportEnvelope, ExploitListEnvelope, ExploitOptionsEnvelope, Pagination, PyroError, PyroErrorResponse, ResponseMeta,
+    ScanDiffEnvelope, ScanListEnvelope, ScanPlanEnvelope, ScanStatusEnvelope,
+};
+use super::scans::{ScanList, ScanStatusResponse, ScanSummary};
+
+/// The gateway authenticates callers with a JWT bearer token; direct
+/// integrations use an API key instead
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "PyRouterSploit API", description = "Vulnerability scanning in the PYRO envelope format"),
+    paths(
+        super::scans::initiate_scan,
+        super::scans::list_scans,
+        super::scans::scan_results,
+        super::scans::cancel_scan,
+        super::scans::export_scan,
+        super::scans::aggregate_report,
+        super::scans::scan_diff,
+        super::websocket::scan_stream,
+        super::exploits::list_exploits,
+        super::exploits::exploit_options,
+    ),
+    components(schemas(
+        ScanStatusEnvelope,
+        ScanListEnvelope,
+        ScanPlanEnvelope,
+        AggregateReportEnvelope,
+        ScanDiffEnvelope,
+        ExploitListEnvelope,
+        ExploitOptionsEnvelope,
+        PyroErrorResponse,
+        PyroError,
+        ResponseMeta,
+        Pagination,
+        ScanRequest,
+        ScanCallbacks,
+        ScanPlan,
+        ScanStatusResponse,
+        ScanList,
+        ScanSummary,
+        SeverityBreakdown,
+        VulnerabilityFinding,
+        Proof,
+        Severity,
+        AggregateReport,
+        TopFinding,
+        ScanDiff,
+        TargetDiff,
+        ExploitList,
+        ExploitOptionsSchema,
+        OptionSpec,
+        OptionType,
+        ExploitMetadata,
+        Protocol,
+    )),
+    modifiers(&SecuritySchemes),
+    security(("bearer_auth" = []), ("api_key" = [])),
+    tags(
+        (name = "scans", description = "Start scans and read their results"),
+        (name = "exploits", description = "Exploit catalogue"),
+    )
+)]
+pub struct ApiDoc;
+
+/// GET /api/v1/openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_openapi_spec_served() {
+        let app = create_router_with_config(&Config::default());
+        let request = Request::builder().uri("/api/v1/openapi.json").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spec: Value = serde_json::from_slice(&body).unwrap();
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+        assert!(spec["paths"]["/api/v1/vulnscan/scan"]["post"].is_object());
+        assert!(spec["paths"]["/api/v1/vulnscan/exploits"]["get"].is_object());
+        assert!(spec["components"]["schemas"]["PyroErrorResponse"].is_object());
+        assert_eq!(spec["components"]["securitySchemes"]["bearer_auth"]["bearerFormat"], "JWT");
+    }
+}
@@ -0,0 +1,257 @@
+// Sliding-window rate limiter keyed on the caller's identity (or source IP)
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use parking_lot::Mutex;
+use serde_json::json;
+
+use super::auth::{verified_key_id, API_KEY_HEADER, USER_ID_HEADER};
+use super::request_id::RequestId;
+use super::response::{error_codes, PyroErrorResponse};
+
+/// Per-key sliding window request counter
+pub struct RateLimiter {
+    max_requests: usize,
+    window: Duration,
+    windows: Mutex<Windows>,
+}
+
+struct Windows {
+    hits: HashMap<String, VecDeque<Instant>>,
+    last_sweep: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: usize, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            windows: Mutex::new(Windows {
+                hits: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Record a hit for `key`. Returns the time until a slot frees up when the limit is exceeded.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock();
+
+        // Once per window, drop keys that have gone quiet so idle clients don't accumulate
+        if now.duration_since(windows.last_sweep) >= self.window {
+            let period = self.window;
+            windows
+                .hits
+                .retain(|_, hits| hits.back().is_some_and(|last| now.duration_since(*last) < period));
+            windows.last_sweep = now;
+        }
+
+        let window = windows.hits.entry(key.to_string()).or_default();
+
+        while let Some(oldest) = window.front() {
+            if now.duration_since(*oldest) >= self.window {
+                window.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if window.len() >= self.max_requests {
+            let oldest = window.front().copied().unwrap_or(now);
+            return Err(self.window.saturating_sub(now.duration_since(oldest)));
+        }
+
+        window.push_back(now);
+        Ok(())
+    }
+}
+
+/// Key requests on the token subject the gateway forwards, or on an API key
+/// that has already passed verification, falling back to the peer address.
+/// This runs before authentication, so unverified keys never get their own
+/// window and can't be used to guess secrets faster.
+fn rate_limit_key(request: &Request) -> String {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    if let Some(user_id) = header(USER_ID_HEADER) {
+        return format!("user:{}", user_id);
+    }
+    if let Some(key_id) = header(API_KEY_HEADER).and_then(verified_key_id) {
+        return format!("api-key:{}", key_id);
+    }
+
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Middleware rejecting requests over the configured limit with 429
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = rate_limit_key(&request);
+
+    match limiter.check(&key) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let retry_secs = retry_after.as_secs().max(1);
+            tracing::warn!("Rate limit exceeded for {}", key);
+
+            let meta = request
+                .extensions()
+                .get::<RequestId>()
+                .map(RequestId::meta)
+                .unwrap_or_default();
+
+            let mut response = PyroErrorResponse::new(
+                error_codes::RATE_LIMIT_EXCEEDED,
+                "Too many requests, slow down",
+                meta,
+            )
+            .with_details(json!({ "retry_after_seconds": retry_secs }))
+            .into_response_with(StatusCode::TOO_MANY_REQUESTS);
+
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_secs));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::create_router_with_config;
+    use crate::api::auth::{TENANT_ID_HEADER, USER_ID_HEADER};
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use tower::ServiceExt;
+
+    fn user_request(user: &str) -> Request {
+        Request::builder()
+            .uri("/health")
+            .header(USER_ID_HEADER, user)
+            .header(TENANT_ID_HEADER, "tenant-a")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn key_request(key: &str) -> Request {
+        Request::builder()
+            .uri("/api/v1/vulnscan/exploits")
+            .header(API_KEY_HEADER, key)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_exceeded() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let config = Config {
+            rate_limit_requests: 2,
+            rate_limit_window_secs: 60,
+            ..Config::default()
+        };
+        let app = create_router_with_config(&config);
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(user_request("user-1")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app.clone().oneshot(user_request("user-1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: PyroErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.status, "error");
+        assert_eq!(error.error.code, error_codes::RATE_LIMIT_EXCEEDED);
+        assert_eq!(error.meta.service, "pyroutersploit");
+
+        // Other users have their own window, even in the same tenant
+        let response = app.oneshot(user_request("user-2")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_only_verified_api_keys_get_their_own_window() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let (key, _) = crate::api::auth::issue_api_key("limited", Some("tenant-a".to_string()), Vec::new()).unwrap();
+        let config = Config {
+            rate_limit_requests: 1,
+            rate_limit_window_secs: 60,
+            ..Config::default()
+        };
+        let app = create_router_with_config(&config);
+
+        // Unverified keys share the peer's window, so guesses are limited before Argon2 runs
+        let response = app.clone().oneshot(key_request("pyro_guess_0000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(key_request(&key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Once the key has been verified it is limited on its own
+        crate::api::auth::authenticate_api_key(&key).await.unwrap().unwrap();
+        let response = app.clone().oneshot(key_request(&key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(key_request(&key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_tenant_header_is_ignored() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let config = Config {
+            rate_limit_requests: 1,
+            rate_limit_window_secs: 60,
+            ..Config::default()
+        };
+        let app = create_router_with_config(&config);
+
+        // Without an authenticated identity every request shares the peer's window
+        for (tenant, status) in [("tenant-a", StatusCode::OK), ("tenant-b", StatusCode::TOO_MANY_REQUESTS)] {
+            let request = Request::builder()
+                .uri("/health")
+                .header(TENANT_ID_HEADER, tenant)
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), status);
+        }
+    }
+
+    #[test]
+    fn test_idle_windows_are_evicted() {
+        let limiter = RateLimiter::new(5, Duration::from_millis(20));
+        limiter.check("tenant:a").unwrap();
+        limiter.check("tenant:b").unwrap();
+        assert_eq!(limiter.windows.lock().hits.len(), 2);
+
+        std::thread::sleep(Duration::from_millis(30));
+        limiter.check("tenant:c").unwrap();
+
+        let windows = limiter.windows.lock();
+        assert_eq!(windows.hits.keys().collect::<Vec<_>>(), vec!["tenant:c"]);
+    }
+}
@@ -0,0 +1,128 @@
+// Request ID propagation from the X-Request-ID header
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+use super::response::ResponseMeta;
+use super::version::ApiVersion;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Request ID for the current request and the API version negotiated for
+/// it, stored in request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId {
+    pub id: String,
+    pub version: ApiVersion,
+}
+
+impl RequestId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            version: ApiVersion::default(),
+        }
+    }
+
+    /// Response metadata carrying this request ID and version
+    pub fn meta(&self) -> ResponseMeta {
+        ResponseMeta::new()
+            .with_request_id(self.id.clone())
+            .with_version(self.version)
+    }
+}
+
+/// Accept caller-supplied IDs only if they are short printable ASCII
+fn sanitize(value: &HeaderValue) -> Option<String> {
+    let value = value.to_str().ok()?.trim();
+    if value.is_empty()
+        || value.len() > MAX_REQUEST_ID_LEN
+        || !value.chars().all(|c| c.is_ascii_graphic())
+    {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/// Middleware reading (or generating) the request ID and echoing it back
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(sanitize)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestId::new(request_id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| RequestId::new(Uuid::new_v4().to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::{create_router_with_config, create_router_without_rate_limit};
+    use crate::api::response::PyroErrorResponse;
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use axum::http::StatusCode;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_id_echoed_in_meta() {
+        let app = create_router_without_rate_limit(&Config::default());
+
+        // Unauthenticated, so the handler answers with an error body
+        let request = Request::builder()
+            .uri("/api/v1/vulnscan/scans")
+            .header(REQUEST_ID_HEADER, "trace-abc-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-abc-123");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: PyroErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.meta.request_id, "trace-abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_when_absent() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config::default());
+
+        let request = Request::builder().uri("/health").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        let request_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(Uuid::parse_str(request_id).is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+// PYRO-compatible response envelopes
+// See docs/PYRO_API_MAPPING.md for the wire format
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::version::ApiVersion;
+
+/// PYRO error codes
+pub mod error_codes {
+    pub const VALIDATION_ERROR: &str = "VALIDATION_ERROR";
+    pub const INVALID_TARGET: &str = "INVALID_TARGET";
+    pub const NOT_FOUND: &str = "NOT_FOUND";
+    pub const CONFLICT: &str = "CONFLICT";
+    pub const UNAUTHORIZED: &str = "UNAUTHORIZED";
+    pub const FORBIDDEN: &str = "FORBIDDEN";
+    pub const RATE_LIMIT_EXCEEDED: &str = "RATE_LIMIT_EXCEEDED";
+    pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
+    pub const SERVICE_UNAVAILABLE: &str = "SERVICE_UNAVAILABLE";
+    pub const PAYLOAD_TOO_LARGE: &str = "PAYLOAD_TOO_LARGE";
+    pub const REQUEST_TIMEOUT: &str = "REQUEST_TIMEOUT";
+    pub const UNSUPPORTED_VERSION: &str = "UNSUPPORTED_VERSION";
+}
+
+/// Page size used when a list request gives no `limit`
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+/// Largest `limit` a list request may ask for
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Where a list response's page sits in the full result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Pagination {
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+    /// Whether records remain after this page
+    pub has_more: bool,
+}
+
+impl Pagination {
+    /// Cut one page out of `items`. `limit` defaults to [`DEFAULT_PAGE_SIZE`]
+    /// and is capped at [`MAX_PAGE_SIZE`].
+    pub fn page<T>(items: Vec<T>, limit: Option<usize>, offset: Option<usize>) -> (Vec<T>, Self) {
+        let total = items.len();
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        let offset = offset.unwrap_or(0);
+        let page: Vec<T> = items.into_iter().skip(offset).take(limit).collect();
+        let has_more = offset.saturating_add(page.len()) < total;
+        (page, Self { total, limit, offset, has_more })
+    }
+}
+
+/// Response metadata attached to every envelope
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResponseMeta {
+    pub request_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub service: String,
+    pub version: String,
+    /// Set on list responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+impl ResponseMeta {
+    pub fn new() -> Self {
+        Self {
+            request_id: format!("req_{}", Uuid::new_v4().simple()),
+            timestamp: Utc::now(),
+            service: "pyroutersploit".to_string(),
+            version: ApiVersion::default().to_string(),
+            pagination: None,
+        }
+    }
+
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = request_id.into();
+        self
+    }
+
+    /// API version the response was shaped for
+    pub fn with_version(mut self, version: ApiVersion) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    pub fn with_pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = Some(pagination);
+        self
+    }
+}
+
+impl Default for ResponseMeta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Successful response envelope
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[aliases(
+    ScanStatusEnvelope = PyroSuccessResponse<super::scans::ScanStatusResponse>,
+    ScanListEnvelope = PyroSuccessResponse<super::scans::ScanList>,
+    ScanPlanEnvelope = PyroSuccessResponse<crate::core::scanner::ScanPlan>,
+    AggregateReportEnvelope = PyroSuccessResponse<crate::core::report::AggregateReport>,
+    ScanDiffEnvelope = PyroSuccessResponse<crate::core::report::ScanDiff>,
+    ExploitListEnvelope = PyroSuccessResponse<super::exploits::ExploitList>,
+    ExploitOptionsEnvelope = PyroSuccessResponse<super::exploits::ExploitOptionsSchema>,
+)]
+pub struct PyroSuccessResponse<T> {
+    pub status: String,
+    pub data: T,
+    pub meta: ResponseMeta,
+}
+
+impl<T> PyroSuccessResponse<T> {
+    pub fn new(data: T, meta: ResponseMeta) -> Self {
+        Self {
+            status: "success".to_string(),
+            data,
+            meta,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for PyroSuccessResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// Error details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PyroError {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// Error response envelope
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PyroErrorResponse {
+    pub status: String,
+    pub error: PyroError,
+    pub meta: ResponseMeta,
+}
+
+impl PyroErrorResponse {
+    pub fn new(code: &str, message: impl Into<String>, meta: ResponseMeta) -> Self {
+        Self {
+            status: "error".to_string(),
+            error: PyroError {
+                code: code.to_string(),
+                message: message.into(),
+                details: None,
+            },
+            meta,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.error.details = Some(details);
+        self
+    }
+
+    /// Pair the envelope with an HTTP status code
+    pub fn into_response_with(self, status: StatusCode) -> Response {
+        (status, Json(self)).into_response()
+    }
+}
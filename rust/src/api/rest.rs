@@ -4,18 +4,207 @@ use axum::{
     routing::{get, post},
     Router,
     Json,
-    extract::{Path, State},
+    middleware,
 };
-use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
 
-// API routes will be added here
+use crate::db::models::Config;
+use super::cors::cors_layer;
+use super::limits::limit_errors;
+use super::exploits::{exploit_options, list_exploits};
+use super::metrics::{metrics, track_requests};
+use super::openapi::openapi_json;
+use super::rate_limit::{rate_limit, RateLimiter};
+use super::request_id::propagate_request_id;
+use super::version::negotiate_version;
+use super::scans::{aggregate_report, cancel_scan, export_scan, initiate_scan, list_scans, scan_diff, scan_results};
+use super::websocket::scan_stream;
+
+lazy_static! {
+    static ref STARTED_AT: Instant = Instant::now();
+}
 
 pub async fn create_router() -> Router {
-    Router::new()
+    let config = crate::db::redb_client::config::load().unwrap_or_default();
+    create_router_with_config(&config)
+}
+
+/// Build the router using limits from the given config
+pub fn create_router_with_config(config: &Config) -> Router {
+    let limiter = Arc::new(RateLimiter::new(
+        config.rate_limit_requests,
+        Duration::from_secs(config.rate_limit_window_secs),
+    ));
+    build_router(config, Some(limiter))
+}
+
+/// Like `create_router_with_config` without the per-client rate limiter,
+/// for deployments that limit requests at the gateway
+pub fn create_router_without_rate_limit(config: &Config) -> Router {
+    build_router(config, None)
+}
+
+fn build_router(config: &Config, limiter: Option<Arc<RateLimiter>>) -> Router {
+    lazy_static::initialize(&STARTED_AT);
+
+    let router = Router::new()
         .route("/health", get(health_check))
+        .route("/metrics", get(metrics))
+        .route("/api/v1/openapi.json", get(openapi_json))
+        .route("/api/v1/vulnscan/exploits", get(list_exploits))
+        .route("/api/v1/vulnscan/exploits/:id/options", get(exploit_options))
+        .route("/api/v1/vulnscan/scan", post(initiate_scan))
+        .route("/api/v1/vulnscan/scans", get(list_scans))
+        .route("/api/v1/vulnscan/scans/:scan_id/results", get(scan_results))
+        .route("/api/v1/vulnscan/scans/:scan_id/cancel", post(cancel_scan))
+        .route("/api/v1/vulnscan/scans/:scan_id/export", get(export_scan))
+        .route("/api/v1/vulnscan/scans/:scan_id/stream", get(scan_stream))
+        .route("/api/v1/vulnscan/scans/:scan_id/diff", get(scan_diff))
+        .route("/api/v1/vulnscan/reports", get(aggregate_report))
+        .merge(crate::nodered::router())
+        .layer(RequestBodyLimitLayer::new(config.max_body_bytes))
+        .layer(TimeoutLayer::new(Duration::from_secs(config.request_timeout_secs.max(1))))
+        .layer(middleware::from_fn(limit_errors));
+    let router = match limiter {
+        Some(limiter) => router.layer(middleware::from_fn_with_state(limiter, rate_limit)),
+        None => router,
+    };
+
+    router
+        .layer(middleware::from_fn(track_requests))
+        .layer(middleware::from_fn(negotiate_version))
+        .layer(middleware::from_fn(propagate_request_id))
+        .layer(cors_layer(config))
+}
+
+fn component_status(check: Result<()>) -> String {
+    match check {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+/// Unauthenticated liveness and readiness report. Always 200 so the body
+/// can be read; `status` is "degraded" when a component check fails.
+async fn health_check() -> Json<Value> {
+    let database = component_status(crate::db::redb_client::ping());
+    let crypto = component_status(crate::crypto::self_test());
+    let healthy = database == "ok" && crypto == "ok";
+
+    Json(json!({
+        "status": if healthy { "ok" } else { "degraded" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "database": database,
+        "crypto": crypto,
+        "uptime_seconds": STARTED_AT.elapsed().as_secs(),
+    }))
+}
+
+/// Serve until `signal` resolves, let in-flight requests finish, then run library shutdown
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    signal: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(signal)
+        .await?;
+
+    crate::shutdown().await
 }
 
-async fn health_check() -> &'static str {
-    "OK"
+/// Resolves on SIGINT (Ctrl-C) or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received, draining connections...");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::redb_client;
+
+    #[tokio::test]
+    async fn test_health_reports_database() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let app = create_router_with_config(&Config::default());
+        let health = |app: Router| async move {
+            let request = Request::builder().uri("/health").body(Body::empty()).unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let body = health(app.clone()).await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["database"], "ok");
+        assert_eq!(body["crypto"], "ok");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body["uptime_seconds"].is_u64());
+
+        redb_client::close_database().await.unwrap();
+        let body = health(app).await;
+        assert_eq!(body["status"], "degraded");
+        assert!(body["database"].as_str().unwrap().starts_with("error"));
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_closes_database() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        assert!(redb_client::is_initialized());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+
+        let app = create_router_with_config(&Config::default());
+        let server = tokio::spawn(serve(listener, app, async {
+            let _ = rx.await;
+        }));
+
+        let response = reqwest::get(format!("http://{}/health", addr)).await.unwrap();
+        assert!(response.status().is_success());
+
+        tx.send(()).unwrap();
+        server.await.unwrap().unwrap();
+
+        assert!(!redb_client::is_initialized());
+    }
 }
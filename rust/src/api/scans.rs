@@ -0,0 +1,844 @@
+// Scan result retrieval in the PYRO format
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::core::report::{self, exploit_metadata, AggregateReport, ScanDiff};
+use crate::core::jobs::{self, JobQueue};
+use crate::core::scanner::{self, ScannerConfig};
+use crate::db::models::{ScanRequest, ScanResult, ScanStatus, VulnerabilityFinding};
+use crate::db::redb_client::scans;
+use super::auth::TenantUser;
+use super::request_id::RequestId;
+use super::response::{error_codes, Pagination, PyroErrorResponse, PyroSuccessResponse, ResponseMeta};
+
+pub use crate::core::report::{severity_breakdown, SeverityBreakdown};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanSummary {
+    pub targets_scanned: usize,
+    pub vulnerabilities_found: usize,
+    pub by_severity: SeverityBreakdown,
+    pub risk_score: f64,
+}
+
+/// `data` of GET /api/v1/vulnscan/scans/:scan_id/results
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanStatusResponse {
+    pub scan_id: Uuid,
+    pub scan_status: String,
+    pub target: String,
+    pub scan_type: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_seconds: Option<i64>,
+    pub summary: ScanSummary,
+    pub vulnerabilities: Vec<VulnerabilityFinding>,
+}
+
+impl From<&ScanResult> for ScanStatusResponse {
+    fn from(scan: &ScanResult) -> Self {
+        let mut vulnerabilities: Vec<_> = scan
+            .vulnerabilities
+            .iter()
+            .filter(|v| v.vulnerable)
+            .cloned()
+            .collect();
+        // Most severe first
+        vulnerabilities.sort_by(|a, b| a.severity.cmp(&b.severity));
+        let by_severity = severity_breakdown(&vulnerabilities);
+        let targets_scanned = match scan.status {
+            ScanStatus::Completed => 1,
+            _ => 0,
+        };
+
+        Self {
+            scan_id: scan.id,
+            scan_status: format!("{:?}", scan.status).to_lowercase(),
+            target: scan.target.clone(),
+            scan_type: scan.scan_type.clone(),
+            started_at: scan.started_at,
+            completed_at: scan.completed_at,
+            duration_seconds: scan.completed_at.map(|end| (end - scan.started_at).num_seconds()),
+            summary: ScanSummary {
+                targets_scanned,
+                vulnerabilities_found: by_severity.total(),
+                by_severity,
+                risk_score: scan.risk_score,
+            },
+            vulnerabilities,
+        }
+    }
+}
+
+pub(crate) enum LoadError {
+    InvalidId(String),
+    NotFound(Uuid),
+    Internal,
+}
+
+impl LoadError {
+    pub(crate) fn into_response(self, meta: ResponseMeta) -> Response {
+        let (code, message, status) = match self {
+            Self::InvalidId(id) => (error_codes::VALIDATION_ERROR, format!("Invalid scan_id: {}", id), StatusCode::BAD_REQUEST),
+            Self::NotFound(id) => (error_codes::NOT_FOUND, format!("Scan not found: {}", id), StatusCode::NOT_FOUND),
+            Self::Internal => (error_codes::INTERNAL_ERROR, "Failed to load scan".to_string(), StatusCode::INTERNAL_SERVER_ERROR),
+        };
+        PyroErrorResponse::new(code, message, meta).into_response_with(status)
+    }
+}
+
+/// Load a scan belonging to `tenant_id`. Scans of another tenant, or of
+/// none, are reported as not found.
+pub(crate) fn load_scan(scan_id: &str, tenant_id: &str) -> Result<ScanResult, LoadError> {
+    let id = Uuid::parse_str(scan_id).map_err(|_| LoadError::InvalidId(scan_id.to_string()))?;
+
+    match scans::get_by_id(&id) {
+        Ok(Some(scan)) if scan.tenant_id.as_deref() == Some(tenant_id) => Ok(scan),
+        Ok(_) => Err(LoadError::NotFound(id)),
+        Err(e) => {
+            tracing::error!("Failed to load scan {}: {}", id, e);
+            Err(LoadError::Internal)
+        }
+    }
+}
+
+/// The queue given to the router as an extension, otherwise the shared pool
+pub(crate) fn job_queue(extension: Option<Extension<JobQueue>>) -> Option<JobQueue> {
+    match extension {
+        Some(Extension(queue)) => Some(queue),
+        None => jobs::shared(),
+    }
+}
+
+/// POST /api/v1/vulnscan/scan
+///
+/// Queues the scan and returns 202 with the queued record; poll the results
+/// endpoint for progress. Dry runs return the plan instead.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vulnscan/scan",
+    tag = "scans",
+    request_body = ScanRequest,
+    responses(
+        (status = 202, description = "Scan queued", body = ScanStatusEnvelope),
+        (status = 200, description = "Dry run plan", body = ScanPlanEnvelope),
+        (status = 400, description = "Invalid scan request", body = PyroErrorResponse),
+        (status = 503, description = "Scan queue is full or not running", body = PyroErrorResponse),
+        (status = 500, description = "Scan settings are invalid or the scan couldn't be stored", body = PyroErrorResponse),
+    )
+)]
+pub async fn initiate_scan(
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+    queue: Option<Extension<JobQueue>>,
+    Json(request): Json<ScanRequest>,
+) -> Response {
+    let settings = crate::db::redb_client::config::load().unwrap_or_default();
+    // Never fall back to a direct connection or the system resolver when
+    // something else was asked for
+    let (proxy, dns, rate_limit) = match settings
+        .proxy()
+        .and_then(|proxy| Ok((proxy, settings.dns()?, settings.scan_rate_limit()?)))
+    {
+        Ok(route) => route,
+        Err(e) => {
+            tracing::error!("Not starting scan: {}", e);
+            return PyroErrorResponse::new(
+                error_codes::INTERNAL_ERROR,
+                "Configured proxy, DNS resolver or scan rate limit is invalid",
+                request_id.meta(),
+            )
+            .into_response_with(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let config = ScannerConfig {
+        threads: settings.max_threads,
+        timeout: settings.timeout_seconds,
+        rate_limit,
+        precheck_timeout: settings.precheck_timeout_secs,
+        proxy,
+        tls: settings.tls(),
+        dns,
+    };
+    let request = ScanRequest { tenant_id: Some(tenant_id), ..request };
+    let invalid = |e: anyhow::Error| {
+        PyroErrorResponse::new(error_codes::VALIDATION_ERROR, e.to_string(), request_id.meta())
+            .into_response_with(StatusCode::BAD_REQUEST)
+    };
+
+    if request.dry_run {
+        return match scanner::plan(&request, &config).await {
+            Ok(plan) => PyroSuccessResponse::new(plan, request_id.meta()).into_response(),
+            Err(e) => invalid(e),
+        };
+    }
+    let Some(queue) = job_queue(queue).filter(JobQueue::has_capacity) else {
+        return PyroErrorResponse::new(error_codes::SERVICE_UNAVAILABLE, "Scan queue is full or not running", request_id.meta())
+            .into_response_with(StatusCode::SERVICE_UNAVAILABLE);
+    };
+
+    match queue.enqueue(request, config).await {
+        Ok(scan) => {
+            tracing::info!("User {} queued scan {} of {}", user.user_id, scan.id, scan.target);
+            (StatusCode::ACCEPTED, PyroSuccessResponse::new(ScanStatusResponse::from(&scan), request_id.meta())).into_response()
+        }
+        Err(jobs::EnqueueError::Rejected(e)) => invalid(e),
+        Err(e @ jobs::EnqueueError::Unavailable(_)) => {
+            PyroErrorResponse::new(error_codes::SERVICE_UNAVAILABLE, e.to_string(), request_id.meta())
+                .into_response_with(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(e @ jobs::EnqueueError::Storage(_)) => {
+            tracing::error!("Not starting scan: {}", e);
+            PyroErrorResponse::new(error_codes::INTERNAL_ERROR, "Failed to store scan", request_id.meta())
+                .into_response_with(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScanListParams {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanList {
+    pub scans: Vec<ScanStatusResponse>,
+    /// Scans in this page; `meta.pagination.total` counts all of them
+    pub count: usize,
+}
+
+/// GET /api/v1/vulnscan/scans[?limit=50][&offset=0]
+///
+/// The caller's tenant's scans, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/scans",
+    tag = "scans",
+    params(ScanListParams),
+    responses(
+        (status = 200, description = "One page of scans", body = ScanListEnvelope),
+        (status = 500, description = "Scan store unavailable", body = PyroErrorResponse),
+    )
+)]
+pub async fn list_scans(
+    Query(params): Query<ScanListParams>,
+    request_id: RequestId,
+    TenantUser { tenant_id, .. }: TenantUser,
+) -> Response {
+    let mut owned: Vec<ScanResult> = match scans::list_all() {
+        Ok(all) => all.into_iter().filter(|scan| scan.tenant_id.as_deref() == Some(tenant_id.as_str())).collect(),
+        Err(e) => {
+            tracing::error!("Failed to list scans: {}", e);
+            return PyroErrorResponse::new(error_codes::INTERNAL_ERROR, "Failed to list scans", request_id.meta())
+                .into_response_with(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    owned.sort_by_key(|scan| std::cmp::Reverse(scan.started_at));
+
+    let (page, pagination) = Pagination::page(owned, params.limit, params.offset);
+    let scans: Vec<ScanStatusResponse> = page.iter().map(ScanStatusResponse::from).collect();
+    let count = scans.len();
+    PyroSuccessResponse::new(ScanList { scans, count }, request_id.meta().with_pagination(pagination)).into_response()
+}
+
+/// GET /api/v1/vulnscan/scans/:scan_id/results
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/scans/{scan_id}/results",
+    tag = "scans",
+    params(("scan_id" = Uuid, Path, description = "Scan id")),
+    responses(
+        (status = 200, description = "Scan status and findings", body = ScanStatusEnvelope),
+        (status = 400, description = "Invalid scan id", body = PyroErrorResponse),
+        (status = 404, description = "Scan not found", body = PyroErrorResponse),
+    )
+)]
+pub async fn scan_results(
+    Path(scan_id): Path<String>,
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+) -> Response {
+    tracing::debug!("User {} fetching results of scan {}", user.user_id, scan_id);
+
+    match load_scan(&scan_id, &tenant_id) {
+        Ok(scan) => PyroSuccessResponse::new(ScanStatusResponse::from(&scan), request_id.meta()).into_response(),
+        Err(e) => e.into_response(request_id.meta()),
+    }
+}
+
+/// POST /api/v1/vulnscan/scans/:scan_id/cancel
+///
+/// Stops a queued or running scan. A running scan is stored as cancelled
+/// once its in-flight checks stop; poll the results endpoint.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vulnscan/scans/{scan_id}/cancel",
+    tag = "scans",
+    params(("scan_id" = Uuid, Path, description = "Scan id")),
+    responses(
+        (status = 202, description = "Scan cancelled or stopping", body = ScanStatusEnvelope),
+        (status = 400, description = "Invalid scan id", body = PyroErrorResponse),
+        (status = 404, description = "Scan not found", body = PyroErrorResponse),
+        (status = 409, description = "Scan already finished", body = PyroErrorResponse),
+    )
+)]
+pub async fn cancel_scan(
+    Path(scan_id): Path<String>,
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+) -> Response {
+    let scan = match load_scan(&scan_id, &tenant_id) {
+        Ok(scan) => scan,
+        Err(e) => return e.into_response(request_id.meta()),
+    };
+
+    match scanner::cancel(&scan.id) {
+        Ok(true) => {
+            tracing::info!("User {} cancelled scan {}", user.user_id, scan.id);
+            let scan = scans::get_by_id(&scan.id).ok().flatten().unwrap_or(scan);
+            (StatusCode::ACCEPTED, PyroSuccessResponse::new(ScanStatusResponse::from(&scan), request_id.meta())).into_response()
+        }
+        Ok(false) => PyroErrorResponse::new(error_codes::CONFLICT, format!("Scan {} already finished", scan.id), request_id.meta())
+            .into_response_with(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!("Failed to cancel scan {}: {}", scan.id, e);
+            PyroErrorResponse::new(error_codes::INTERNAL_ERROR, "Failed to cancel scan", request_id.meta())
+                .into_response_with(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// json (default), csv or sarif
+    pub format: Option<String>,
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per vulnerable finding: target, vulnerability, severity, cvss
+pub fn to_csv(scan: &ScanResult) -> String {
+    let mut csv = String::from("target,vulnerability,severity,cvss\n");
+    for finding in ScanStatusResponse::from(scan).vulnerabilities {
+        let vulnerability = exploit_metadata(&finding.exploit_id)
+            .map(|metadata| metadata.name)
+            .unwrap_or_else(|| finding.exploit_id.to_string());
+        let cvss = finding.cvss_score.map(|score| format!("{:.1}", score)).unwrap_or_default();
+
+        let row = [
+            csv_field(&scan.target),
+            csv_field(&vulnerability),
+            format!("{:?}", finding.severity).to_lowercase(),
+            cvss,
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// GET /api/v1/vulnscan/scans/:scan_id/export?format=json|csv|sarif
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/scans/{scan_id}/export",
+    tag = "scans",
+    params(("scan_id" = Uuid, Path, description = "Scan id"), ExportParams),
+    responses(
+        (status = 200, description = "Export file in the requested format", body = String),
+        (status = 400, description = "Invalid scan id or format", body = PyroErrorResponse),
+        (status = 404, description = "Scan not found", body = PyroErrorResponse),
+    )
+)]
+pub async fn export_scan(
+    Path(scan_id): Path<String>,
+    Query(params): Query<ExportParams>,
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+) -> Response {
+    let format = params.format.as_deref().unwrap_or("json").to_lowercase();
+    if !["json", "csv", "sarif"].contains(&format.as_str()) {
+        return PyroErrorResponse::new(
+            error_codes::VALIDATION_ERROR,
+            format!("Unsupported export format: {} (expected json, csv or sarif)", format),
+            request_id.meta(),
+        )
+        .into_response_with(StatusCode::BAD_REQUEST);
+    }
+
+    let scan = match load_scan(&scan_id, &tenant_id) {
+        Ok(scan) => scan,
+        Err(e) => return e.into_response(request_id.meta()),
+    };
+    tracing::debug!("User {} exporting scan {} as {}", user.user_id, scan.id, format);
+
+    let (content_type, body) = match format.as_str() {
+        "csv" => ("text/csv; charset=utf-8", Ok(to_csv(&scan))),
+        "sarif" => ("application/sarif+json", report::to_sarif(&scan)),
+        _ => (
+            "application/json",
+            serde_json::to_string_pretty(&ScanStatusResponse::from(&scan)).map_err(Into::into),
+        ),
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to export scan {}: {}", scan.id, e);
+            return PyroErrorResponse::new(error_codes::INTERNAL_ERROR, "Failed to export scan", request_id.meta())
+                .into_response_with(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let disposition = format!("attachment; filename=\"scan-{}.{}\"", scan.id, format);
+
+    (
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        body,
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportParams {
+    /// Comma-separated scan ids
+    pub scan_ids: Option<String>,
+}
+
+/// GET /api/v1/vulnscan/reports?scan_ids=id1,id2
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/reports",
+    tag = "scans",
+    params(ReportParams),
+    responses(
+        (status = 200, description = "Report across the scans", body = AggregateReportEnvelope),
+        (status = 400, description = "Missing or invalid scan ids", body = PyroErrorResponse),
+        (status = 404, description = "A scan was not found", body = PyroErrorResponse),
+    )
+)]
+pub async fn aggregate_report(
+    Query(params): Query<ReportParams>,
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+) -> Response {
+    let ids: Vec<&str> = params
+        .scan_ids
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect();
+    if ids.is_empty() {
+        return PyroErrorResponse::new(error_codes::VALIDATION_ERROR, "scan_ids must list at least one scan", request_id.meta())
+            .into_response_with(StatusCode::BAD_REQUEST);
+    }
+
+    let mut loaded = Vec::with_capacity(ids.len());
+    for id in ids {
+        match load_scan(id, &tenant_id) {
+            Ok(scan) => loaded.push(scan),
+            Err(e) => return e.into_response(request_id.meta()),
+        }
+    }
+    tracing::debug!("User {} aggregating {} scans", user.user_id, loaded.len());
+
+    let report: AggregateReport = report::aggregate_scans(&loaded);
+    PyroSuccessResponse::new(report, request_id.meta()).into_response()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffParams {
+    /// Earlier scan to compare against
+    pub against: Option<String>,
+}
+
+/// GET /api/v1/vulnscan/scans/:scan_id/diff?against=:other_id
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/scans/{scan_id}/diff",
+    tag = "scans",
+    params(("scan_id" = Uuid, Path, description = "Current scan id"), DiffParams),
+    responses(
+        (status = 200, description = "Per-target changes between the scans", body = ScanDiffEnvelope),
+        (status = 400, description = "Missing or invalid scan id", body = PyroErrorResponse),
+        (status = 404, description = "A scan was not found", body = PyroErrorResponse),
+    )
+)]
+pub async fn scan_diff(
+    Path(scan_id): Path<String>,
+    Query(params): Query<DiffParams>,
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+) -> Response {
+    let Some(against) = params.against else {
+        return PyroErrorResponse::new(error_codes::VALIDATION_ERROR, "against is required", request_id.meta())
+            .into_response_with(StatusCode::BAD_REQUEST);
+    };
+    let (current, previous) = match (load_scan(&scan_id, &tenant_id), load_scan(&against, &tenant_id)) {
+        (Ok(current), Ok(previous)) => (current, previous),
+        (Err(e), _) | (_, Err(e)) => return e.into_response(request_id.meta()),
+    };
+    tracing::debug!("User {} diffing scan {} against {}", user.user_id, current.id, previous.id);
+
+    let diff: ScanDiff = report::diff(&previous, &current);
+    PyroSuccessResponse::new(diff, request_id.meta()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::{TENANT_ID_HEADER, USER_ID_HEADER};
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::test_support::{finding, scan};
+    use crate::db::models::{Config, ExploitMetadata, Severity};
+    use crate::db::redb_client::exploits;
+    use crate::db::redb_client;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn request(uri: &str, tenant: Option<&str>) -> (StatusCode, String, String) {
+        let app = create_router_with_config(&Config::default());
+        let mut request = Request::get(uri).header(USER_ID_HEADER, "analyst");
+        if let Some(tenant) = tenant {
+            request = request.header(TENANT_ID_HEADER, tenant);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn get(uri: &str) -> (StatusCode, Value) {
+        let (status, _, body) = request(uri, Some("tenant-a")).await;
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    /// A completed tenant-a scan with one named and one unknown finding
+    fn tenant_scan() -> ScanResult {
+        let named = ExploitMetadata {
+            id: Uuid::new_v4(),
+            name: "D-Link HNAP Auth Bypass, \"unauth\"".to_string(),
+            description: String::new(),
+            authors: vec![],
+            references: vec![],
+            devices: vec!["DIR-850L".to_string()],
+            category: "exploits/routers/dlink".to_string(),
+            protocol: crate::db::models::Protocol::HTTP,
+            severity: Severity::Critical,
+            verified: true,
+            cryptex_id: None,
+            cvss_vector: Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H".to_string()),
+            updated_at: Utc::now(),
+        };
+        exploits::insert(&named).unwrap();
+
+        ScanResult {
+            tenant_id: Some("tenant-a".to_string()),
+            ..scan("192.168.0.1", vec![
+                finding(named.id, true, Severity::Critical, Some(9.8)),
+                finding(Uuid::nil(), true, Severity::Medium, None),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_csv() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let scan = tenant_scan();
+        scans::insert(&scan).unwrap();
+
+        let uri = format!("/api/v1/vulnscan/scans/{}/export?format=csv", scan.id);
+        let (status, content_type, body) = request(&uri, Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(content_type.starts_with("text/csv"));
+
+        let rows: Vec<_> = body.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], "target,vulnerability,severity,cvss");
+        assert_eq!(rows[1], "192.168.0.1,\"D-Link HNAP Auth Bypass, \"\"unauth\"\"\",critical,9.8");
+        assert_eq!(rows[2], format!("192.168.0.1,{},medium,", Uuid::nil()));
+
+        // Other tenants can't see the scan at all
+        let (status, _, _) = request(&uri, Some("tenant-b")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Nor can callers without a tenant, who never see tenantless scans either
+        let (status, _, body) = request(&uri, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(body.contains(error_codes::FORBIDDEN));
+        let untenanted = ScanResult { id: Uuid::new_v4(), tenant_id: None, ..tenant_scan() };
+        scans::insert(&untenanted).unwrap();
+        let (status, _, _) = request(&format!("/api/v1/vulnscan/scans/{}/results", untenanted.id), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _, _) = request(&format!("/api/v1/vulnscan/scans/{}/results", untenanted.id), Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _, _) = request("/api/v1/vulnscan/scans", None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_export_json() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let scan = tenant_scan();
+        scans::insert(&scan).unwrap();
+
+        let uri = format!("/api/v1/vulnscan/scans/{}/export", scan.id);
+        let (status, content_type, body) = request(&uri, Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/json");
+
+        let export: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(export["scan_id"], scan.id.to_string());
+        assert_eq!(export["summary"]["vulnerabilities_found"], 2);
+        assert_eq!(export["vulnerabilities"][0]["cvss_score"], 9.8);
+        assert_eq!(export["vulnerabilities"][1]["severity"], "Medium");
+
+        let (status, content_type, body) = request(&format!("{}?format=sarif", uri), Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/sarif+json");
+        let sarif: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(sarif["runs"][0]["results"].as_array().unwrap().len(), 2);
+
+        let (status, _, body) = request(&format!("{}?format=xml", uri), Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(error["error"]["code"], error_codes::VALIDATION_ERROR);
+    }
+
+    #[test]
+    fn test_severity_breakdown_counts_findings() {
+        let finding = |severity, vulnerable| finding(Uuid::new_v4(), vulnerable, severity, None);
+        let scan = scan("10.0.0.1", vec![
+            finding(Severity::Low, true),
+            finding(Severity::Critical, true),
+            finding(Severity::High, true),
+            finding(Severity::Critical, true),
+            finding(Severity::Medium, true),
+            finding(Severity::Info, true),
+            finding(Severity::Critical, false),
+        ]);
+
+        let response = ScanStatusResponse::from(&scan);
+        let expected = SeverityBreakdown { critical: 2, high: 1, medium: 1, low: 1, info: 1 };
+        assert_eq!(response.summary.by_severity, expected);
+        assert_eq!(response.summary.vulnerabilities_found, 6);
+        assert_eq!(response.summary.vulnerabilities_found, response.vulnerabilities.len());
+
+        let order: Vec<_> = response.vulnerabilities.iter().map(|v| v.severity.clone()).collect();
+        assert!(order.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(order[0], Severity::Critical);
+    }
+
+    #[tokio::test]
+    async fn test_scan_results_include_risk_score() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+
+        let vulnerabilities = vec![
+            finding(Uuid::new_v4(), true, Severity::Critical, Some(9.8)),
+            finding(Uuid::new_v4(), true, Severity::Low, None),
+        ];
+        let started_at = Utc::now();
+        let scan = ScanResult {
+            started_at,
+            completed_at: Some(started_at + chrono::Duration::seconds(42)),
+            risk_score: crate::core::cvss::risk_score(&vulnerabilities),
+            tenant_id: Some("tenant-a".to_string()),
+            ..scan("192.168.1.1", vulnerabilities)
+        };
+        scans::insert(&scan).unwrap();
+
+        let (status, body) = get(&format!("/api/v1/vulnscan/scans/{}/results", scan.id)).await;
+        assert_eq!(status, StatusCode::OK);
+        let data = &body["data"];
+        assert_eq!(data["scan_status"], "completed");
+        assert_eq!(data["duration_seconds"], 42);
+        assert_eq!(data["summary"]["vulnerabilities_found"], 2);
+        assert_eq!(data["summary"]["by_severity"]["critical"], 1);
+        assert_eq!(data["summary"]["by_severity"]["low"], 1);
+        assert_eq!(data["summary"]["risk_score"], 98.5);
+        assert_eq!(data["vulnerabilities"][0]["cvss_score"], 9.8);
+
+        let (status, body) = get(&format!("/api/v1/vulnscan/scans/{}/results", Uuid::new_v4())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], error_codes::NOT_FOUND);
+
+        let (status, _) = get("/api/v1/vulnscan/scans/not-a-uuid/results").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_report_endpoint() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let first = tenant_scan();
+        let second = ScanResult { id: Uuid::new_v4(), target: "192.168.0.2".to_string(), ..tenant_scan() };
+        scans::insert(&first).unwrap();
+        scans::insert(&second).unwrap();
+
+        let uri = format!("/api/v1/vulnscan/reports?scan_ids={},{}", first.id, second.id);
+        let (status, _, body) = request(&uri, Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::OK);
+        let data = &serde_json::from_str::<Value>(&body).unwrap()["data"];
+        assert_eq!(data["targets_scanned"], 2);
+        assert_eq!(data["vulnerabilities_found"], 4);
+        assert_eq!(data["by_severity"]["critical"], 2);
+
+        // Another tenant's scan is not found, even alongside visible ones
+        let (status, _, _) = request(&uri, Some("tenant-b")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _, _) = request("/api/v1/vulnscan/reports", Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_initiate_scan_queues_job() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config::default()).layer(Extension(JobQueue::start(1, 4)));
+        let request = Request::post("/api/v1/vulnscan/scan")
+            .header(USER_ID_HEADER, "analyst")
+            .header(TENANT_ID_HEADER, "tenant-a")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"target":"192.0.2.60","scan_type":"http"}"#))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["data"]["scan_status"], "queued");
+
+        let scan_id = Uuid::parse_str(body["data"]["scan_id"].as_str().unwrap()).unwrap();
+        assert_eq!(scans::get_by_id(&scan_id).unwrap().unwrap().tenant_id.as_deref(), Some("tenant-a"));
+
+        // Bad requests are the caller's fault, not the queue's
+        let request = Request::post("/api/v1/vulnscan/scan")
+            .header(USER_ID_HEADER, "analyst")
+            .header(TENANT_ID_HEADER, "tenant-a")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"target":"192.0.2.60","scan_type":"nmap"}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_initiate_scan_uses_scan_rate_limit() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        redb_client::config::set("scan_rate_limit", "4").unwrap();
+
+        let app = create_router_with_config(&Config::default());
+        let request = Request::post("/api/v1/vulnscan/scan")
+            .header(USER_ID_HEADER, "analyst")
+            .header(TENANT_ID_HEADER, "tenant-a")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"target":"192.0.2.61","scan_type":"http","dry_run":true}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        let actions = body["data"]["actions"].as_array().unwrap();
+        assert!(actions.iter().any(|action| action.as_str().unwrap().ends_with("at most 4 per second")));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_scan_endpoint() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let cancel = |scan_id: Uuid, tenant: &'static str| async move {
+            let request = Request::post(format!("/api/v1/vulnscan/scans/{}/cancel", scan_id))
+                .header(USER_ID_HEADER, "analyst")
+                .header(TENANT_ID_HEADER, tenant)
+                .body(Body::empty())
+                .unwrap();
+            let response = create_router_with_config(&Config::default()).oneshot(request).await.unwrap();
+            let status = response.status();
+            let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+            (status, body)
+        };
+
+        // A queued scan is cancelled on the spot, once
+        let queued = ScanResult { status: ScanStatus::Queued, completed_at: None, ..tenant_scan() };
+        scans::insert(&queued).unwrap();
+        assert_eq!(cancel(queued.id, "tenant-b").await.0, StatusCode::NOT_FOUND);
+        let (status, body) = cancel(queued.id, "tenant-a").await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["data"]["scan_status"], "cancelled");
+        let (status, body) = cancel(queued.id, "tenant-a").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"]["code"], "CONFLICT");
+
+        // A running one has its checks stopped
+        let running = ScanResult { id: Uuid::new_v4(), status: ScanStatus::Running, completed_at: None, ..tenant_scan() };
+        scans::insert(&running).unwrap();
+        let (_guard, token) = scanner::active::track_cancellable(running.id);
+        assert_eq!(cancel(running.id, "tenant-a").await.0, StatusCode::ACCEPTED);
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_scan_diff_endpoint() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let previous = tenant_scan();
+        let mut current = ScanResult { id: Uuid::new_v4(), ..previous.clone() };
+        let fixed = current.vulnerabilities.remove(0);
+        scans::insert(&previous).unwrap();
+        scans::insert(&current).unwrap();
+
+        let uri = format!("/api/v1/vulnscan/scans/{}/diff?against={}", current.id, previous.id);
+        let (status, _, body) = request(&uri, Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::OK);
+        let target = &serde_json::from_str::<Value>(&body).unwrap()["data"]["targets"][0];
+        assert_eq!(target["resolved"][0]["exploit_id"], fixed.exploit_id.to_string());
+        assert_eq!(target["persisting"].as_array().unwrap().len(), 1);
+        assert!(target["new"].as_array().unwrap().is_empty());
+
+        let (status, _, _) = request(&format!("/api/v1/vulnscan/scans/{}/diff", current.id), Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_scans_paged() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        for _ in 0..3 {
+            scans::insert(&tenant_scan()).unwrap();
+        }
+        let mut other = tenant_scan();
+        other.tenant_id = Some("tenant-b".to_string());
+        scans::insert(&other).unwrap();
+
+        let (status, _, body) = request("/api/v1/vulnscan/scans?limit=2", Some("tenant-a")).await;
+        assert_eq!(status, StatusCode::OK);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["data"]["count"], 2);
+        assert_eq!(body["meta"]["pagination"]["total"], 3);
+        assert_eq!(body["meta"]["pagination"]["has_more"], true);
+
+        let (_, _, body) = request("/api/v1/vulnscan/scans?limit=2&offset=2", Some("tenant-a")).await;
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["data"]["count"], 1);
+        assert_eq!(body["meta"]["pagination"]["has_more"], false);
+    }
+
+}
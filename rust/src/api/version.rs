@@ -0,0 +1,172 @@
+// API version negotiation from the /api/vN/ path prefix or the Accept header
+
+use std::convert::Infallible;
+use std::fmt;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::json;
+
+use super::request_id::RequestId;
+use super::response::{error_codes, PyroErrorResponse};
+
+/// Vendor media type naming a version, e.g. `application/vnd.pyro.v1+json`
+const MEDIA_TYPE_PREFIX: &str = "application/vnd.pyro.v";
+const MEDIA_TYPE_SUFFIX: &str = "+json";
+
+/// Response shape served for a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApiVersion {
+    #[default]
+    V1,
+}
+
+impl ApiVersion {
+    pub const SUPPORTED: &'static [ApiVersion] = &[ApiVersion::V1];
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        match number {
+            1 => Some(ApiVersion::V1),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> u32 {
+        match self {
+            ApiVersion::V1 => 1,
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.number())
+    }
+}
+
+/// `N` from a path starting `/api/vN/`
+fn path_version(path: &str) -> Option<u32> {
+    let rest = path.strip_prefix("/api/v")?;
+    let digits = rest.split('/').next()?;
+    digits.parse().ok()
+}
+
+/// First vendor media type in `Accept` that names a version
+fn accept_version(headers: &HeaderMap) -> Option<u32> {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_type| {
+            let media_type = media_type.split(';').next()?.trim().to_ascii_lowercase();
+            media_type
+                .strip_prefix(MEDIA_TYPE_PREFIX)?
+                .strip_suffix(MEDIA_TYPE_SUFFIX)?
+                .parse()
+                .ok()
+        })
+        .next()
+}
+
+fn not_acceptable(request: &Request, message: String) -> Response {
+    let meta = request
+        .extensions()
+        .get::<RequestId>()
+        .map(RequestId::meta)
+        .unwrap_or_default();
+    let supported: Vec<String> = ApiVersion::SUPPORTED.iter().map(ApiVersion::to_string).collect();
+
+    PyroErrorResponse::new(error_codes::UNSUPPORTED_VERSION, message, meta)
+        .with_details(json!({ "supported_versions": supported }))
+        .into_response_with(StatusCode::NOT_ACCEPTABLE)
+}
+
+/// Pick the version named by the path prefix, else by `Accept`, else the
+/// default. Unsupported or conflicting requests get 406. The version is
+/// stored on the request so `RequestId::meta()` reports it.
+pub async fn negotiate_version(mut request: Request, next: Next) -> Response {
+    let from_path = path_version(request.uri().path());
+    let from_accept = accept_version(request.headers());
+
+    let version = match (from_path, from_accept) {
+        // The path picks the routes, so an Accept naming another version can't be met
+        (Some(path), Some(accept)) if path != accept => {
+            Err(format!("Path serves API v{} but Accept asks for v{}", path, accept))
+        }
+        (None, None) => Ok(ApiVersion::default()),
+        (path, accept) => {
+            let number = path.or(accept).unwrap_or_default();
+            ApiVersion::from_number(number).ok_or_else(|| format!("Unsupported API version: v{}", number))
+        }
+    };
+    let version = match version {
+        Ok(version) => version,
+        Err(message) => return not_acceptable(&request, message),
+    };
+
+    if let Some(request_id) = request.extensions_mut().get_mut::<RequestId>() {
+        request_id.version = version;
+    }
+    request.extensions_mut().insert(version);
+    next.run(request).await
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<ApiVersion>().copied().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::USER_ID_HEADER;
+    use crate::api::response::PyroSuccessResponse;
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn get(uri: &str, accept: Option<&str>) -> (StatusCode, Value) {
+        let app = create_router_with_config(&Config::default());
+        let mut request = Request::builder().uri(uri).header(USER_ID_HEADER, "user-1");
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_meta_reports_negotiated_version() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let (status, body) = get("/api/v1/vulnscan/exploits", Some("application/vnd.pyro.v1+json")).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: PyroSuccessResponse<Value> = serde_json::from_value(body).unwrap();
+        assert_eq!(response.meta.version, "v1");
+
+        let (status, body) = get("/api/v1/vulnscan/exploits", Some("application/vnd.pyro.v2+json")).await;
+        assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(body["error"]["code"], error_codes::UNSUPPORTED_VERSION);
+        assert_eq!(body["error"]["details"]["supported_versions"][0], "v1");
+
+        let (status, _) = get("/api/v2/vulnscan/exploits", None).await;
+        assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+
+        assert_eq!(accept_version(&HeaderMap::new()), None);
+        assert_eq!(path_version("/api/vulnscan"), None);
+    }
+}
@@ -1,5 +1,226 @@
 // WebSocket API for real-time updates
 
 use anyhow::Result;
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path,
+    },
+    response::Response,
+};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
 
-// WebSocket implementation will be added here
+use crate::core::scanner::{events, ScanEvent};
+use crate::db::models::ScanStatus;
+use super::auth::TenantUser;
+use super::request_id::RequestId;
+use super::scans::load_scan;
+
+/// GET /api/v1/vulnscan/scans/:scan_id/stream
+#[utoipa::path(
+    get,
+    path = "/api/v1/vulnscan/scans/{scan_id}/stream",
+    tag = "scans",
+    params(("scan_id" = Uuid, Path, description = "Scan id")),
+    responses(
+        (status = 101, description = "WebSocket of scan progress events, one JSON message each"),
+        (status = 401, description = "Missing authenticated user context", body = PyroErrorResponse),
+        (status = 404, description = "Scan not found", body = PyroErrorResponse),
+    )
+)]
+pub async fn scan_stream(
+    ws: WebSocketUpgrade,
+    Path(scan_id): Path<String>,
+    request_id: RequestId,
+    TenantUser { user, tenant_id }: TenantUser,
+) -> Response {
+    let scan = match load_scan(&scan_id, &tenant_id) {
+        Ok(scan) => scan,
+        Err(e) => return e.into_response(request_id.meta()),
+    };
+    tracing::debug!("User {} streaming scan {}", user.user_id, scan.id);
+
+    // Subscribe before the upgrade so no events are lost during the handshake
+    let subscription = events::subscribe(&scan);
+    ws.on_upgrade(move |socket| stream_scan_events(socket, scan.id, subscription))
+}
+
+/// Event for a scan that already finished before the client connected
+fn finished_event(scan_id: Uuid) -> Option<ScanEvent> {
+    let scan = crate::db::redb_client::scans::get_by_id(&scan_id).ok()??;
+
+    match scan.status {
+        ScanStatus::Queued | ScanStatus::Running => None,
+        ScanStatus::Completed => Some(ScanEvent::Completed {
+            scan_id,
+            vulnerabilities_found: scan.vulnerabilities.iter().filter(|v| v.vulnerable).count(),
+        }),
+        status => Some(ScanEvent::Error {
+            scan_id,
+            message: format!("Scan {:?}", status),
+        }),
+    }
+}
+
+async fn send_event(socket: &mut WebSocket, event: &ScanEvent) -> Result<()> {
+    let frame = serde_json::to_string(event)?;
+    socket.send(Message::Text(frame)).await?;
+    Ok(())
+}
+
+async fn stream_scan_events(mut socket: WebSocket, scan_id: Uuid, subscription: Option<events::Subscription>) {
+    // Also catches a scan that finished while the client was subscribing
+    if let Some(event) = finished_event(scan_id) {
+        let _ = send_event(&mut socket, &event).await;
+        close(socket).await;
+        return;
+    }
+    let Some(mut subscription) = subscription else {
+        close(socket).await;
+        return;
+    };
+
+    loop {
+        tokio::select! {
+            event = subscription.recv() => match event {
+                Ok(event) => {
+                    if send_event(&mut socket, &event).await.is_err() {
+                        return;
+                    }
+                    if event.is_terminal() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Scan {} stream lagged, {} events dropped", scan_id, skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                // Client frames carry no meaning on this stream
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    close(socket).await;
+}
+
+async fn close(mut socket: WebSocket) {
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: close_code::NORMAL,
+            reason: "scan finished".into(),
+        })))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::{TENANT_ID_HEADER, USER_ID_HEADER};
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::{test_support, Config};
+    use crate::db::redb_client;
+    use futures::StreamExt;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message as WsMessage};
+
+    async fn serve() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = create_router_with_config(&Config::default());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    fn insert_scan(status: ScanStatus, tenant: &str) -> Uuid {
+        let mut scan = test_support::scan("192.0.2.1", vec![]);
+        scan.status = status;
+        scan.tenant_id = Some(tenant.to_string());
+        redb_client::scans::insert(&scan).unwrap();
+        scan.id
+    }
+
+    async fn connect(
+        addr: SocketAddr,
+        scan_id: Uuid,
+        tenant: &str,
+    ) -> Result<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>> {
+        let mut request = format!("ws://{}/api/v1/vulnscan/scans/{}/stream", addr, scan_id)
+            .into_client_request()?;
+        request.headers_mut().insert(USER_ID_HEADER, "analyst".parse()?);
+        request.headers_mut().insert(TENANT_ID_HEADER, tenant.parse()?);
+        let (ws, _) = tokio_tungstenite::connect_async(request).await?;
+        Ok(ws)
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_receives_progress() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let addr = serve().await;
+        let scan_id = insert_scan(ScanStatus::Running, "tenant-a");
+        let mut ws = connect(addr, scan_id, "tenant-a").await.unwrap();
+
+        events::publish(ScanEvent::TargetScanned {
+            scan_id,
+            target: "192.0.2.1".to_string(),
+            targets_scanned: 1,
+        });
+
+        let frame = tokio::time::timeout(Duration::from_secs(5), ws.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let event: serde_json::Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+        assert_eq!(event["type"], "scan.target_scanned");
+        assert_eq!(event["data"]["targets_scanned"], 1);
+
+        events::publish(ScanEvent::Completed { scan_id, vulnerabilities_found: 0 });
+        let frame = ws.next().await.unwrap().unwrap();
+        assert!(frame.to_text().unwrap().contains("scan.completed"));
+        let frame = ws.next().await.unwrap().unwrap();
+        assert!(matches!(frame, WsMessage::Close(_)));
+        assert!(!events::is_open(scan_id));
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_of_finished_scan_closes() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let addr = serve().await;
+        let scan_id = insert_scan(ScanStatus::Completed, "tenant-a");
+        let mut ws = connect(addr, scan_id, "tenant-a").await.unwrap();
+
+        let frame = tokio::time::timeout(Duration::from_secs(5), ws.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(frame.to_text().unwrap().contains("scan.completed"));
+        let frame = ws.next().await.unwrap().unwrap();
+        assert!(matches!(frame, WsMessage::Close(_)));
+        assert!(!events::is_open(scan_id));
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_hides_other_tenants_and_unknown_scans() {
+        let _db = redb_client::test_support::init_database().await.unwrap();
+        let addr = serve().await;
+        let scan_id = insert_scan(ScanStatus::Running, "tenant-a");
+
+        assert!(connect(addr, scan_id, "tenant-b").await.is_err());
+        assert!(connect(addr, Uuid::new_v4(), "tenant-a").await.is_err());
+        assert!(!events::is_open(scan_id));
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_requires_auth() {
+        let addr = serve().await;
+        let url = format!("ws://{}/api/v1/vulnscan/scans/{}/stream", addr, Uuid::new_v4());
+        assert!(tokio_tungstenite::connect_async(url).await.is_err());
+    }
+}
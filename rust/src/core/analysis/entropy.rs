@@ -0,0 +1,113 @@
+// Shannon entropy for locating compressed or encrypted blobs in firmware
+
+use serde::{Deserialize, Serialize};
+
+/// Windows at or above this many bits per byte are likely compressed or encrypted
+pub const HIGH_ENTROPY_THRESHOLD: f64 = 7.2;
+
+/// Entropy of one window, starting at `offset` bytes into the input
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowEntropy {
+    pub offset: usize,
+    pub length: usize,
+    /// Bits per byte, 0.0 to 8.0
+    pub entropy: f64,
+}
+
+impl WindowEntropy {
+    pub fn is_high(&self) -> bool {
+        self.entropy >= HIGH_ENTROPY_THRESHOLD
+    }
+}
+
+/// Bits per byte, from 0.0 (one repeated byte) to 8.0 (uniformly random)
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Entropy of consecutive non-overlapping `window`-byte chunks. The last
+/// window may be shorter.
+pub fn window_entropy(data: &[u8], window: usize) -> Vec<WindowEntropy> {
+    if window == 0 {
+        return Vec::new();
+    }
+
+    data.chunks(window)
+        .enumerate()
+        .map(|(i, chunk)| WindowEntropy {
+            offset: i * window,
+            length: chunk.len(),
+            entropy: shannon_entropy(chunk),
+        })
+        .collect()
+}
+
+/// Windows at or above `threshold`, with adjacent ones merged into a single
+/// region whose `entropy` is the highest of its windows
+pub fn high_entropy_regions(data: &[u8], window: usize, threshold: f64) -> Vec<WindowEntropy> {
+    let mut regions: Vec<WindowEntropy> = Vec::new();
+
+    for w in window_entropy(data, window).into_iter().filter(|w| w.entropy >= threshold) {
+        match regions.last_mut() {
+            Some(last) if last.offset + last.length == w.offset => {
+                last.length += w.length;
+                last.entropy = last.entropy.max(w.entropy);
+            }
+            _ => regions.push(w),
+        }
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn test_zeros_have_no_entropy() {
+        let zeros = vec![0u8; 4096];
+        assert!(shannon_entropy(&zeros) < 0.01);
+        assert_eq!(shannon_entropy(&[]), 0.0);
+        assert!((shannon_entropy(&[0, 1]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_random_data_near_eight_bits() {
+        let mut random = vec![0u8; 64 * 1024];
+        rand::thread_rng().fill_bytes(&mut random);
+        assert!(shannon_entropy(&random) > 7.9);
+
+        // A random blob embedded between zero padding is located by offset
+        let mut image = vec![0u8; 8192];
+        image.extend_from_slice(&random[..8192]);
+        image.extend(vec![0u8; 4096]);
+
+        let windows = window_entropy(&image, 1024);
+        assert_eq!(windows.len(), 20);
+        assert!(!windows[0].is_high());
+        assert!(windows[8].is_high());
+
+        let regions = high_entropy_regions(&image, 1024, HIGH_ENTROPY_THRESHOLD);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].offset, 8192);
+        assert_eq!(regions[0].length, 8192);
+    }
+}
@@ -0,0 +1,5 @@
+// Firmware analysis helpers
+
+pub mod entropy;
+
+pub use entropy::*;
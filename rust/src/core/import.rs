@@ -0,0 +1,216 @@
+// Exploit catalog import from YAML module manifests
+
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use uuid::Uuid;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::db::models::{ExploitMetadata, Protocol, Severity};
+use crate::db::redb_client::exploits;
+
+/// Protocol by name, case-insensitive. Anything else is kept as `Custom`.
+pub fn parse_protocol(name: &str) -> Protocol {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "http" => Protocol::HTTP,
+        "https" => Protocol::HTTPS,
+        "ssh" => Protocol::SSH,
+        "ftp" => Protocol::FTP,
+        "ftps" => Protocol::FTPS,
+        "telnet" => Protocol::Telnet,
+        "snmp" => Protocol::SNMP,
+        "tcp" => Protocol::TCP,
+        "udp" => Protocol::UDP,
+        _ => Protocol::Custom(name.trim().to_string()),
+    }
+}
+
+pub fn parse_severity(name: &str) -> Result<Severity> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "critical" => Ok(Severity::Critical),
+        "high" => Ok(Severity::High),
+        "medium" => Ok(Severity::Medium),
+        "low" => Ok(Severity::Low),
+        "info" => Ok(Severity::Info),
+        _ => Err(anyhow!("Unknown severity: {} (expected critical, high, medium, low or info)", name)),
+    }
+}
+
+/// A list of strings, or a single string as a one-item list
+fn string_list(value: &Yaml) -> Vec<String> {
+    match value {
+        Yaml::Array(items) => items.iter().filter_map(Yaml::as_str).map(str::to_string).collect(),
+        Yaml::String(s) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Stable id for a module path, so re-importing a manifest updates rows in place
+pub fn module_id(path: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, format!("pyrsf://modules/{}", path).as_bytes())
+}
+
+/// The module's metadata, and its `verified` flag if the manifest sets one
+fn to_metadata(module: &Yaml) -> Result<(ExploitMetadata, Option<bool>)> {
+    let text = |key: &str| module[key].as_str().map(str::to_string);
+    let name = text("name").ok_or_else(|| anyhow!("missing name"))?;
+    let category = text("category").unwrap_or_else(|| "exploits".to_string());
+    let path = text("path").unwrap_or_else(|| format!("{}/{}", category, name));
+
+    let verified = module["verified"].as_bool();
+    let metadata = ExploitMetadata {
+        id: module_id(&path),
+        description: text("description").unwrap_or_default(),
+        authors: string_list(&module["authors"]),
+        references: string_list(&module["references"]),
+        devices: string_list(&module["targets"]),
+        category,
+        protocol: text("protocol").map_or(Protocol::TCP, |p| parse_protocol(&p)),
+        severity: text("severity").map_or(Ok(Severity::Medium), |s| parse_severity(&s))?,
+        verified: verified.unwrap_or(false),
+        cryptex_id: None,
+        cvss_vector: text("cvss_vector"),
+        updated_at: chrono::Utc::now(),
+        name,
+    };
+    Ok((metadata, verified))
+}
+
+fn parse_modules(contents: &str) -> Result<Vec<(ExploitMetadata, Option<bool>)>> {
+    let docs = YamlLoader::load_from_str(contents).context("Invalid YAML manifest")?;
+    let Some(doc) = docs.first() else {
+        return Ok(Vec::new());
+    };
+    let modules = match doc {
+        Yaml::Array(modules) => modules,
+        _ => doc["modules"]
+            .as_vec()
+            .ok_or_else(|| anyhow!("Manifest must be a list of modules or have a `modules` list"))?,
+    };
+
+    modules
+        .iter()
+        .enumerate()
+        .map(|(i, module)| to_metadata(module).with_context(|| format!("Module {} in manifest", i + 1)))
+        .collect()
+}
+
+/// Parse a manifest: either a list of modules or a `modules:` key holding
+/// one. Each module needs a `name`; `targets` become the device list,
+/// protocol defaults to TCP and severity to medium. The id derives from
+/// `path`, or `category/name` when the module has none.
+pub fn parse_manifest(contents: &str) -> Result<Vec<ExploitMetadata>> {
+    Ok(parse_modules(contents)?.into_iter().map(|(metadata, _)| metadata).collect())
+}
+
+/// Parse the manifest at `path` and store every module in the exploits
+/// table. Modules already there keep their cryptex link, and their verified
+/// flag unless the manifest sets one. Returns the stored rows.
+pub fn from_yaml(path: impl AsRef<Path>) -> Result<Vec<ExploitMetadata>> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    exploits::merge_many(&parse_modules(&contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+modules:
+  - name: D-Link DIR-300 Command Injection
+    description: Unauthenticated RCE via the ping diagnostic
+    authors: [Alice, Bob]
+    references:
+      - https://example.com/advisory/1
+    targets: ["D-Link DIR-300", "D-Link DIR-600"]
+    severity: Critical
+    protocol: http
+    category: exploits/routers/dlink
+  - name: Acme Camera RTSP Auth Bypass
+    authors: Carol
+    targets: Acme IPC-100
+    severity: high
+    protocol: rtsp
+  - name: Generic Telnet Backdoor
+    protocol: TELNET
+"#;
+
+    #[tokio::test]
+    async fn test_from_yaml_imports_modules() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let path = std::env::temp_dir().join(format!("pyrsf_manifest_{}.yaml", Uuid::new_v4().simple()));
+        std::fs::write(&path, MANIFEST).unwrap();
+
+        let modules = from_yaml(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(modules.len(), 3);
+
+        let dlink = &modules[0];
+        assert_eq!(dlink.authors, vec!["Alice", "Bob"]);
+        assert_eq!(dlink.devices, vec!["D-Link DIR-300", "D-Link DIR-600"]);
+        assert_eq!(dlink.severity, Severity::Critical);
+        assert!(matches!(dlink.protocol, Protocol::HTTP));
+        assert_eq!(dlink.category, "exploits/routers/dlink");
+
+        assert_eq!(modules[1].devices, vec!["Acme IPC-100"]);
+        assert!(matches!(&modules[1].protocol, Protocol::Custom(p) if p == "rtsp"));
+        assert!(matches!(modules[2].protocol, Protocol::Telnet));
+        assert_eq!(modules[2].severity, Severity::Medium);
+
+        assert_eq!(exploits::list_all().unwrap().len(), 3);
+        assert!(exploits::get_by_id(&dlink.id).unwrap().is_some());
+        assert_eq!(dlink.id, module_id("exploits/routers/dlink/D-Link DIR-300 Command Injection"));
+
+        // Re-importing updates the same rows instead of duplicating them
+        let path = std::env::temp_dir().join(format!("pyrsf_manifest_{}.yaml", Uuid::new_v4().simple()));
+        std::fs::write(&path, MANIFEST.replace("Unauthenticated RCE", "Pre-auth RCE")).unwrap();
+        let again = from_yaml(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(again[0].id, dlink.id);
+        assert_eq!(exploits::list_all().unwrap().len(), 3);
+        assert!(exploits::get_by_id(&dlink.id).unwrap().unwrap().description.starts_with("Pre-auth"));
+
+        let err = parse_manifest("- description: no name\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("missing name"));
+        assert!(parse_manifest("- name: x\n  severity: severe\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reimport_keeps_cryptex_link_and_verified() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let import = |manifest: &str| {
+            let path = std::env::temp_dir().join(format!("pyrsf_manifest_{}.yaml", Uuid::new_v4().simple()));
+            std::fs::write(&path, manifest).unwrap();
+            let modules = from_yaml(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            modules
+        };
+        let id = import(MANIFEST)[0].id;
+
+        let entry = crate::db::CryptexEntry::new(
+            "dlink_ping_injection".to_string(),
+            "Ping of Doom".to_string(),
+            "inject(ping)".to_string(),
+            crate::db::models::CryptexCategory::Exploit,
+        );
+        crate::db::redb_client::cryptex::insert(&entry).unwrap();
+        let mut linked = exploits::get_by_id(&id).unwrap().unwrap();
+        linked.cryptex_id = Some(entry.id);
+        exploits::insert(&linked).unwrap();
+        assert!(exploits::mark_verified(&id, true).unwrap());
+
+        // Re-importing refreshes the manifest fields only
+        let stored = import(&MANIFEST.replace("Unauthenticated RCE", "Pre-auth RCE"));
+        assert!(stored[0].description.starts_with("Pre-auth"));
+        let row = exploits::get_by_id(&id).unwrap().unwrap();
+        assert!(row.description.starts_with("Pre-auth"));
+        assert_eq!(row.cryptex_id, Some(entry.id));
+        assert!(row.verified);
+
+        // An explicit flag in the manifest still wins
+        let row = &import(&MANIFEST.replace("severity: Critical", "severity: Critical\n    verified: false"))[0];
+        assert!(!row.verified);
+        assert_eq!(row.cryptex_id, Some(entry.id));
+        assert!(!exploits::get_by_id(&id).unwrap().unwrap().verified);
+    }
+}
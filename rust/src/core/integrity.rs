@@ -0,0 +1,135 @@
+// File integrity manifests, e.g. for detecting tampering in extracted firmware
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::crypto::hashing::{HashAlgorithm, MultiHasher};
+
+/// Hex digest of every regular file under a directory, keyed by relative path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub algorithm: HashAlgorithm,
+    pub created_at: DateTime<Utc>,
+    /// `/`-separated path relative to the manifest root
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityDiff {
+    /// Present on disk but not in the manifest
+    Added(String),
+    /// In the manifest but missing on disk
+    Removed(String),
+    Modified {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl IntegrityDiff {
+    pub fn path(&self) -> &str {
+        match self {
+            IntegrityDiff::Added(path) | IntegrityDiff::Removed(path) => path,
+            IntegrityDiff::Modified { path, .. } => path,
+        }
+    }
+}
+
+/// Symlinks are skipped so a manifest never follows links out of `root`
+fn walk(root: &Path, dir: &Path, algorithm: HashAlgorithm, files: &mut BTreeMap<String, String>) -> Result<()> {
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Cannot read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let file_type = std::fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_dir() {
+            walk(root, &path, algorithm, files)?;
+        } else if file_type.is_file() {
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let digest = MultiHasher::hash_file(algorithm, &path)
+                .with_context(|| format!("Cannot hash {}", path.display()))?;
+            files.insert(relative, digest.hex);
+        }
+    }
+    Ok(())
+}
+
+fn hash_tree(dir: &Path, algorithm: HashAlgorithm) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    walk(dir, dir, algorithm, &mut files)?;
+    Ok(files)
+}
+
+/// Hash every file under `dir`
+pub fn generate_manifest(dir: &Path, algorithm: HashAlgorithm) -> Result<Manifest> {
+    Ok(Manifest {
+        algorithm,
+        created_at: Utc::now(),
+        files: hash_tree(dir, algorithm)?,
+    })
+}
+
+/// Compare `dir` against `manifest`; an empty list means nothing changed.
+/// Diffs are sorted by path.
+pub fn verify_manifest(dir: &Path, manifest: &Manifest) -> Result<Vec<IntegrityDiff>> {
+    let current = hash_tree(dir, manifest.algorithm)?;
+    let mut diffs = Vec::new();
+
+    for (path, expected) in &manifest.files {
+        match current.get(path) {
+            None => diffs.push(IntegrityDiff::Removed(path.clone())),
+            Some(actual) if actual != expected => diffs.push(IntegrityDiff::Modified {
+                path: path.clone(),
+                expected: expected.clone(),
+                actual: actual.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for path in current.keys().filter(|path| !manifest.files.contains_key(*path)) {
+        diffs.push(IntegrityDiff::Added(path.clone()));
+    }
+
+    diffs.sort_by(|a, b| a.path().cmp(b.path()));
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modified_file_detected() {
+        let dir = std::env::temp_dir().join(format!("pyroutersploit-integrity-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("etc")).unwrap();
+        std::fs::write(dir.join("etc/passwd"), b"root:x:0:0::/root:/bin/sh\n").unwrap();
+        std::fs::write(dir.join("etc/shadow"), b"root:*:0:0:99999:7:::\n").unwrap();
+        std::fs::write(dir.join("busybox"), b"\x7fELF").unwrap();
+
+        let manifest = generate_manifest(&dir, HashAlgorithm::SHA256).unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert!(manifest.files.contains_key("etc/passwd"));
+        assert!(verify_manifest(&dir, &manifest).unwrap().is_empty());
+
+        std::fs::write(dir.join("etc/passwd"), b"root::0:0::/root:/bin/sh\n").unwrap();
+        std::fs::remove_file(dir.join("etc/shadow")).unwrap();
+        std::fs::write(dir.join("backdoor.sh"), b"telnetd -l /bin/sh\n").unwrap();
+
+        let diffs = verify_manifest(&dir, &manifest).unwrap();
+        assert_eq!(diffs.len(), 3);
+        assert_eq!(diffs[0], IntegrityDiff::Added("backdoor.sh".to_string()));
+        assert!(matches!(&diffs[1], IntegrityDiff::Modified { path, expected, .. }
+            if path == "etc/passwd" && expected == &manifest.files["etc/passwd"]));
+        assert_eq!(diffs[2], IntegrityDiff::Removed("etc/shadow".to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+// Scan job queue: a bounded queue drained by a pool of worker tasks
+
+use anyhow::Result;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::core::scanner::{self, active, ScannerConfig};
+use crate::db::models::{Config, ScanCallbacks, ScanRequest, ScanResult, ScanStatus};
+use crate::db::redb_client::scans;
+
+struct Job {
+    record: ScanResult,
+    callbacks: ScanCallbacks,
+    config: ScannerConfig,
+}
+
+/// Why a scan couldn't be queued
+#[derive(Debug)]
+pub enum EnqueueError {
+    /// The request is invalid or out of scope
+    Rejected(anyhow::Error),
+    /// The queue is full or not running; worth retrying later
+    Unavailable(&'static str),
+    /// The scan record couldn't be stored
+    Storage(anyhow::Error),
+}
+
+impl fmt::Display for EnqueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnqueueError::Rejected(e) => write!(f, "{}", e),
+            EnqueueError::Unavailable(reason) => f.write_str(reason),
+            EnqueueError::Storage(e) => write!(f, "Failed to store scan: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EnqueueError {}
+
+/// Handle to a running worker pool. Dropping every handle lets the
+/// workers finish the queued jobs and exit.
+#[derive(Clone)]
+pub struct JobQueue {
+    sender: mpsc::Sender<Job>,
+}
+
+impl JobQueue {
+    /// Spawn `workers` tasks sharing a queue of at most `capacity` jobs
+    pub fn start(workers: usize, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        for worker in 0..workers.max(1) {
+            tokio::spawn(run_worker(worker, receiver.clone()));
+        }
+        Self { sender }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    /// Whether another scan can be queued right now
+    pub fn has_capacity(&self) -> bool {
+        self.is_running() && self.sender.capacity() > 0
+    }
+
+    /// Store `request` as a queued scan and hand it to the workers. Fails
+    /// without storing anything if the queue is full.
+    pub async fn enqueue(&self, request: ScanRequest, config: ScannerConfig) -> Result<ScanResult, EnqueueError> {
+        let record = scanner::new_record(&request, ScanStatus::Queued).await.map_err(EnqueueError::Rejected)?;
+        let permit = self.sender.try_reserve().map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => EnqueueError::Unavailable("Scan queue is full, try again later"),
+            mpsc::error::TrySendError::Closed(_) => EnqueueError::Unavailable(NOT_RUNNING),
+        })?;
+
+        scans::insert(&record).map_err(EnqueueError::Storage)?;
+        permit.send(Job {
+            record: record.clone(),
+            config: config.for_request(&request),
+            callbacks: request.callbacks,
+        });
+        Ok(record)
+    }
+}
+
+async fn run_worker(worker: usize, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) {
+    loop {
+        // Only hold the lock while waiting, so other workers can run jobs
+        let Some(job) = receiver.lock().await.recv().await else {
+            break;
+        };
+        let scan_id = job.record.id;
+        // Tracked before it is marked running, so a cancel always finds it one way or the other
+        let (_guard, cancel) = active::track_cancellable(scan_id);
+        let started = scans::transition(&scan_id, ScanStatus::Queued, |scan| {
+            scan.status = ScanStatus::Running;
+            scan.started_at = Utc::now();
+        });
+        match started {
+            Ok(Some(record)) => {
+                tracing::debug!("Worker {} starting scan {}", worker, scan_id);
+                scanner::execute(record, job.callbacks, job.config, cancel).await;
+            }
+            Ok(None) => tracing::debug!("Worker {} skipping scan {}, cancelled while queued", worker, scan_id),
+            Err(e) => tracing::error!("Worker {} couldn't start scan {}: {}", worker, scan_id, e),
+        }
+    }
+}
+
+const NOT_RUNNING: &str = "Scan queue is not running";
+
+lazy_static! {
+    static ref QUEUE: RwLock<Option<JobQueue>> = RwLock::new(None);
+}
+
+/// Start the shared worker pool unless it has been started. The first
+/// start also fails the scans a previous process left behind.
+pub fn start(workers: usize, capacity: usize) {
+    let mut queue = QUEUE.write();
+    if queue.is_some() {
+        return;
+    }
+    match fail_interrupted() {
+        Ok(0) => {}
+        Ok(interrupted) => tracing::info!("Marked {} scan(s) interrupted by the last shutdown as failed", interrupted),
+        Err(e) => tracing::error!("Failed to clean up interrupted scans: {}", e),
+    }
+    *queue = Some(JobQueue::start(workers, capacity));
+}
+
+/// Start the shared worker pool sized by `config`
+pub fn start_from_config(config: &Config) {
+    start(config.scan_workers, config.scan_queue_size);
+}
+
+/// The shared pool, if it has been started and is still running
+pub fn shared() -> Option<JobQueue> {
+    QUEUE.read().clone().filter(JobQueue::is_running)
+}
+
+/// Queue a scan on the shared pool
+pub async fn enqueue(request: ScanRequest, config: ScannerConfig) -> Result<ScanResult, EnqueueError> {
+    shared().ok_or(EnqueueError::Unavailable(NOT_RUNNING))?.enqueue(request, config).await
+}
+
+/// Mark scans a previous process left queued or running as failed. Their
+/// workers are gone, so nothing else would ever finish them. Run by the
+/// first [`start`], before this process has queued anything.
+pub fn fail_interrupted() -> Result<usize> {
+    let mut failed = 0;
+    for mut scan in scans::list_all()? {
+        if matches!(scan.status, ScanStatus::Queued | ScanStatus::Running) {
+            tracing::warn!("Scan {} of {} was interrupted by a restart, marking it failed", scan.id, scan.target);
+            scan.status = ScanStatus::Failed;
+            scan.completed_at = Some(Utc::now());
+            scans::update(&scan)?;
+            failed += 1;
+        }
+    }
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_job_runs_from_queued_to_completed() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        // A private pool, so workers run on this test's runtime and database
+        let queue = JobQueue::start(2, 8);
+        assert!(queue.has_capacity());
+
+        let config = ScannerConfig { threads: 2, timeout: 1, rate_limit: None, precheck_timeout: 0, proxy: None, tls: Default::default(), dns: Default::default() };
+        let queued = queue.enqueue(ScanRequest::new("192.0.2.55", "http"), config).await.unwrap();
+        assert!(matches!(queued.status, ScanStatus::Queued));
+
+        let mut seen = vec![];
+        let finished = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let scan = scans::get_by_id(&queued.id).unwrap().unwrap();
+                let status = format!("{:?}", scan.status);
+                if seen.last() != Some(&status) {
+                    seen.push(status);
+                }
+                if matches!(scan.status, ScanStatus::Completed) {
+                    return scan;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(finished.completed_at.is_some());
+        assert_eq!(seen.last().map(String::as_str), Some("Completed"));
+        assert!(seen.iter().all(|s| ["Queued", "Running", "Completed"].contains(&s.as_str())));
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_errors() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let config = ScannerConfig { threads: 1, timeout: 1, rate_limit: None, precheck_timeout: 0, proxy: None, tls: Default::default(), dns: Default::default() };
+
+        // No workers, so the one slot stays taken
+        let (sender, receiver) = mpsc::channel(1);
+        let queue = JobQueue { sender };
+        queue.enqueue(ScanRequest::new("192.0.2.56", "http"), config.clone()).await.unwrap();
+        let full = queue.enqueue(ScanRequest::new("192.0.2.57", "http"), config.clone()).await.unwrap_err();
+        assert!(matches!(full, EnqueueError::Unavailable(_)));
+        let invalid = queue.enqueue(ScanRequest::new("192.0.2.57", "nmap"), config.clone()).await.unwrap_err();
+        assert!(matches!(invalid, EnqueueError::Rejected(_)));
+
+        drop(receiver);
+        let closed = queue.enqueue(ScanRequest::new("192.0.2.58", "http"), config).await.unwrap_err();
+        assert_eq!(closed.to_string(), NOT_RUNNING);
+        // Only the accepted scan was stored
+        assert_eq!(scans::list_all().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_cancelled_while_queued_never_starts() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let config = ScannerConfig { threads: 1, timeout: 1, rate_limit: None, precheck_timeout: 0, proxy: None, tls: Default::default(), dns: Default::default() };
+        let (sender, receiver) = mpsc::channel(1);
+        let queue = JobQueue { sender };
+        let queued = queue.enqueue(ScanRequest::new("192.0.2.62", "http"), config).await.unwrap();
+
+        assert!(scanner::cancel(&queued.id).unwrap());
+        assert!(!scanner::cancel(&queued.id).unwrap());
+
+        // A worker reaching it afterwards leaves it alone
+        drop(queue);
+        run_worker(0, Arc::new(Mutex::new(receiver))).await;
+        let stored = scans::get_by_id(&queued.id).unwrap().unwrap();
+        assert_eq!(stored.status, ScanStatus::Cancelled);
+        assert!(stored.completed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fail_interrupted_scans() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let queued = scanner::new_record(&ScanRequest::new("192.0.2.59", "http"), ScanStatus::Queued).await.unwrap();
+        let running = scanner::new_record(&ScanRequest::new("192.0.2.59", "http"), ScanStatus::Running).await.unwrap();
+        let done = scanner::new_record(&ScanRequest::new("192.0.2.59", "http"), ScanStatus::Completed).await.unwrap();
+        for scan in [&queued, &running, &done] {
+            scans::insert(scan).unwrap();
+        }
+
+        assert_eq!(fail_interrupted().unwrap(), 2);
+        for scan in [&queued, &running] {
+            let stored = scans::get_by_id(&scan.id).unwrap().unwrap();
+            assert!(matches!(stored.status, ScanStatus::Failed));
+            assert!(stored.completed_at.is_some());
+        }
+        assert!(matches!(scans::get_by_id(&done.id).unwrap().unwrap().status, ScanStatus::Completed));
+    }
+}
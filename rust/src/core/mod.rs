@@ -3,9 +3,27 @@
 pub mod exploit;
 pub mod scanner;
 pub mod payload;
+pub mod encoder;
 pub mod session;
+pub mod creds;
+pub mod cvss;
+pub mod report;
+pub mod webhook;
+pub mod integrity;
+pub mod analysis;
+pub mod scope;
+pub mod jobs;
+pub mod import;
+pub mod options;
+pub mod net;
 
 pub use exploit::*;
 pub use scanner::*;
 pub use payload::*;
+pub use encoder::*;
 pub use session::*;
+pub use creds::*;
+pub use cvss::*;
+pub use report::*;
+pub use webhook::*;
+pub use integrity::*;
@@ -0,0 +1,197 @@
+// Typed exploit options, validated against the options an exploit declares
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use crate::core::exploit::Options;
+
+/// Kind of value an option takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionType {
+    String,
+    Integer,
+    Boolean,
+    /// Integer from 1 to 65535
+    Port,
+}
+
+impl OptionType {
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            OptionType::String => value.is_string(),
+            OptionType::Integer => value.is_i64() || value.is_u64(),
+            OptionType::Boolean => value.is_boolean(),
+            OptionType::Port => value.as_u64().is_some_and(|port| (1..=65535).contains(&port)),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            OptionType::String => "a string",
+            OptionType::Integer => "an integer",
+            OptionType::Boolean => "true or false",
+            OptionType::Port => "a port number (1-65535)",
+        }
+    }
+}
+
+/// One option an exploit accepts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OptionSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub option_type: OptionType,
+    pub required: bool,
+    /// Used when the caller leaves the option out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    pub description: String,
+}
+
+impl OptionSpec {
+    pub fn new(name: impl Into<String>, option_type: OptionType, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            option_type,
+            required: false,
+            default: None,
+            description: description.into(),
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn with_default(mut self, default: impl Into<Value>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+}
+
+/// Types of the options every exploit shares, checked even when an exploit
+/// declares nothing
+const COMMON_OPTIONS: &[(&str, OptionType)] = &[
+    ("rhost", OptionType::String),
+    ("rport", OptionType::Port),
+    ("ssl", OptionType::Boolean),
+    ("timeout", OptionType::Integer),
+    ("username", OptionType::String),
+    ("password", OptionType::String),
+];
+
+/// Exploit options with the common fields typed. Module-specific keys are
+/// kept in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExploitOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rhost: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rport: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl: Option<bool>,
+    /// Per-call timeout in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ExploitOptions {
+    /// Check `options` against `schema`, filling in defaults. Every problem
+    /// is reported in one error. Options the schema doesn't mention are
+    /// passed through.
+    pub fn validate(options: &Options, schema: &[OptionSpec]) -> Result<Self> {
+        let mut options = options.clone();
+        let mut problems = Vec::new();
+
+        for spec in schema {
+            match options.get(&spec.name) {
+                None | Some(Value::Null) => match &spec.default {
+                    Some(default) => {
+                        options.insert(spec.name.clone(), default.clone());
+                    }
+                    None if spec.required => {
+                        problems.push(format!("missing required option `{}` ({})", spec.name, spec.description));
+                    }
+                    None => {
+                        options.remove(&spec.name);
+                    }
+                },
+                Some(value) if !spec.option_type.accepts(value) => {
+                    problems.push(format!("`{}` must be {}", spec.name, spec.option_type.describe()));
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (name, option_type) in COMMON_OPTIONS {
+            let declared = schema.iter().any(|spec| spec.name == *name);
+            match options.get(*name) {
+                Some(value) if !declared && !value.is_null() && !option_type.accepts(value) => {
+                    problems.push(format!("`{}` must be {}", name, option_type.describe()));
+                }
+                _ => {}
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(anyhow!("Invalid options: {}", problems.join("; ")));
+        }
+        serde_json::from_value(Value::Object(options)).map_err(|e| anyhow!("Invalid options: {}", e))
+    }
+
+    /// Back to the untyped map `Exploit::run` takes
+    pub fn to_options(&self) -> Options {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(options)) => options,
+            _ => Options::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new("username", OptionType::String, "Login name").required(),
+            OptionSpec::new("rport", OptionType::Port, "Web interface port").with_default(80),
+            OptionSpec::new("command", OptionType::String, "Command to run").with_default("id"),
+        ]
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let options = json!({ "username": "admin", "ssl": true, "payload_arch": "mipsle" }).as_object().cloned().unwrap();
+        let validated = ExploitOptions::validate(&options, &schema()).unwrap();
+        assert_eq!(validated.username.as_deref(), Some("admin"));
+        assert_eq!(validated.rport, Some(80));
+        assert_eq!(validated.ssl, Some(true));
+        assert_eq!(validated.extra["command"], "id");
+        assert_eq!(validated.extra["payload_arch"], "mipsle");
+        assert_eq!(validated.to_options()["rport"], 80);
+
+        let err = ExploitOptions::validate(&Options::new(), &schema()).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid options: missing required option `username` (Login name)");
+
+        let options = json!({ "username": "admin", "rport": 70000, "ssl": "yes" }).as_object().cloned().unwrap();
+        let err = ExploitOptions::validate(&options, &schema()).unwrap_err().to_string();
+        assert!(err.contains("`rport` must be a port number (1-65535)"), "{}", err);
+        assert!(err.contains("`ssl` must be true or false"), "{}", err);
+
+        // Without a schema only the common fields are checked
+        let options = json!({ "marker": 1, "timeout": 5 }).as_object().cloned().unwrap();
+        assert_eq!(ExploitOptions::validate(&options, &[]).unwrap().timeout, Some(5));
+    }
+}
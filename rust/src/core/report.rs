@@ -0,0 +1,423 @@
+// Scan reports for external tools
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::core::exploit::registry;
+use crate::db::models::{ExploitMetadata, ScanResult, ScanStatus, Severity, VulnerabilityFinding};
+use crate::db::redb_client::{exploits, scans};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Findings listed in an aggregate report's `top_findings`
+pub const TOP_FINDINGS: usize = 10;
+
+/// Metadata for the exploit behind a finding, from the database or the registry
+pub fn exploit_metadata(id: &Uuid) -> Option<ExploitMetadata> {
+    exploits::get_by_id(id)
+        .ok()
+        .flatten()
+        .or_else(|| registry::metadata(id))
+}
+
+/// Vulnerable findings counted per severity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SeverityBreakdown {
+    pub critical: usize,
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+    pub info: usize,
+}
+
+impl SeverityBreakdown {
+    pub fn total(&self) -> usize {
+        self.critical + self.high + self.medium + self.low + self.info
+    }
+}
+
+/// Count the vulnerable findings of each severity
+pub fn severity_breakdown(vulnerabilities: &[VulnerabilityFinding]) -> SeverityBreakdown {
+    let mut breakdown = SeverityBreakdown::default();
+    for finding in vulnerabilities.iter().filter(|v| v.vulnerable) {
+        let count = match finding.severity {
+            Severity::Critical => &mut breakdown.critical,
+            Severity::High => &mut breakdown.high,
+            Severity::Medium => &mut breakdown.medium,
+            Severity::Low => &mut breakdown.low,
+            Severity::Info => &mut breakdown.info,
+        };
+        *count += 1;
+    }
+    breakdown
+}
+
+/// One vulnerability across every scan it was found in
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TopFinding {
+    pub exploit_id: Uuid,
+    pub name: Option<String>,
+    pub severity: Severity,
+    pub cvss_score: Option<f64>,
+    /// Vulnerable targets, sorted
+    pub targets: Vec<String>,
+}
+
+/// Rolled-up view of several scans
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AggregateReport {
+    pub scan_ids: Vec<Uuid>,
+    /// Distinct targets across the scans
+    pub targets_scanned: usize,
+    /// Distinct targets with at least one vulnerable finding, sorted
+    pub vulnerable_targets: Vec<String>,
+    pub vulnerabilities_found: usize,
+    pub by_severity: SeverityBreakdown,
+    /// Most severe first, then highest CVSS, then most targets affected
+    pub top_findings: Vec<TopFinding>,
+}
+
+/// Aggregate already loaded scans
+pub fn aggregate_scans(scans: &[ScanResult]) -> AggregateReport {
+    let mut targets = BTreeSet::new();
+    let mut vulnerable_targets = BTreeSet::new();
+    let mut vulnerable = Vec::new();
+    let mut by_exploit: HashMap<Uuid, TopFinding> = HashMap::new();
+
+    for scan in scans {
+        targets.insert(scan.target.as_str());
+        for finding in scan.vulnerabilities.iter().filter(|v| v.vulnerable) {
+            vulnerable_targets.insert(scan.target.clone());
+            vulnerable.push(finding.clone());
+
+            let top = by_exploit.entry(finding.exploit_id).or_insert_with(|| TopFinding {
+                exploit_id: finding.exploit_id,
+                name: exploit_metadata(&finding.exploit_id).map(|m| m.name),
+                severity: finding.severity.clone(),
+                cvss_score: finding.cvss_score,
+                targets: Vec::new(),
+            });
+            top.severity = top.severity.clone().min(finding.severity.clone());
+            if finding.cvss_score > top.cvss_score {
+                top.cvss_score = finding.cvss_score;
+            }
+            if !top.targets.contains(&scan.target) {
+                top.targets.push(scan.target.clone());
+            }
+        }
+    }
+
+    let mut top_findings: Vec<TopFinding> = by_exploit.into_values().collect();
+    for top in &mut top_findings {
+        top.targets.sort();
+    }
+    top_findings.sort_by(|a, b| {
+        a.severity
+            .cmp(&b.severity)
+            .then(b.cvss_score.unwrap_or(0.0).total_cmp(&a.cvss_score.unwrap_or(0.0)))
+            .then(b.targets.len().cmp(&a.targets.len()))
+            .then(a.exploit_id.cmp(&b.exploit_id))
+    });
+    top_findings.truncate(TOP_FINDINGS);
+
+    let by_severity = severity_breakdown(&vulnerable);
+    AggregateReport {
+        scan_ids: scans.iter().map(|scan| scan.id).collect(),
+        targets_scanned: targets.len(),
+        vulnerable_targets: vulnerable_targets.into_iter().collect(),
+        vulnerabilities_found: by_severity.total(),
+        by_severity,
+        top_findings,
+    }
+}
+
+/// Load and aggregate the given scans. Fails if any of them doesn't exist.
+pub fn aggregate(scan_ids: &[Uuid]) -> Result<AggregateReport> {
+    let loaded = scan_ids
+        .iter()
+        .map(|id| scans::get_by_id(id)?.ok_or_else(|| anyhow!("Scan not found: {}", id)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(aggregate_scans(&loaded))
+}
+
+/// How one target's vulnerabilities changed between two scans
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TargetDiff {
+    pub target: String,
+    /// Vulnerable now, not before
+    pub new: Vec<VulnerabilityFinding>,
+    /// Vulnerable before, not now
+    pub resolved: Vec<VulnerabilityFinding>,
+    /// Vulnerable in both; the current finding is listed
+    pub persisting: Vec<VulnerabilityFinding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanDiff {
+    pub previous_scan_id: Uuid,
+    pub current_scan_id: Uuid,
+    /// Sorted by target. A target only one scan covered shows all its
+    /// findings as new or resolved.
+    pub targets: Vec<TargetDiff>,
+}
+
+/// Compare vulnerable findings per target, matching them by exploit
+pub fn diff(previous: &ScanResult, current: &ScanResult) -> ScanDiff {
+    let vulnerable = |scan: &ScanResult| -> Vec<VulnerabilityFinding> {
+        scan.vulnerabilities.iter().filter(|v| v.vulnerable).cloned().collect()
+    };
+    let mut targets: Vec<TargetDiff> = Vec::new();
+    let mut entry = |target: &str| -> usize {
+        match targets.iter().position(|t| t.target == target) {
+            Some(index) => index,
+            None => {
+                targets.push(TargetDiff {
+                    target: target.to_string(),
+                    new: Vec::new(),
+                    resolved: Vec::new(),
+                    persisting: Vec::new(),
+                });
+                targets.len() - 1
+            }
+        }
+    };
+
+    let before = vulnerable(previous);
+    let after = vulnerable(current);
+    let same_target = previous.target == current.target;
+    let current_index = entry(&current.target);
+    let previous_index = entry(&previous.target);
+
+    for finding in &after {
+        let persisted = same_target && before.iter().any(|b| b.exploit_id == finding.exploit_id);
+        let list = if persisted { &mut targets[current_index].persisting } else { &mut targets[current_index].new };
+        list.push(finding.clone());
+    }
+    for finding in &before {
+        if !(same_target && after.iter().any(|a| a.exploit_id == finding.exploit_id)) {
+            targets[previous_index].resolved.push(finding.clone());
+        }
+    }
+
+    targets.sort_by(|a, b| a.target.cmp(&b.target));
+    ScanDiff {
+        previous_scan_id: previous.id,
+        current_scan_id: current.id,
+        targets,
+    }
+}
+
+fn sarif_level(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+fn sarif_rule(id: &Uuid, metadata: Option<&ExploitMetadata>) -> Value {
+    let Some(metadata) = metadata else {
+        return json!({
+            "id": id.to_string(),
+            "shortDescription": { "text": "Unknown exploit" },
+        });
+    };
+
+    let mut rule = json!({
+        "id": id.to_string(),
+        "name": metadata.name,
+        "shortDescription": { "text": metadata.name },
+        "fullDescription": { "text": metadata.description },
+        "properties": {
+            "tags": ["security", metadata.category],
+            "devices": metadata.devices,
+            "references": metadata.references,
+        },
+    });
+    if let Some(uri) = metadata.references.iter().find(|r| r.starts_with("http")) {
+        rule["helpUri"] = json!(uri);
+    }
+    if let Some(vector) = &metadata.cvss_vector {
+        rule["properties"]["cvss_vector"] = json!(vector);
+        if let Ok(score) = crate::core::cvss::score(vector) {
+            // Read by code-scanning dashboards to rank security alerts
+            rule["properties"]["security-severity"] = json!(format!("{:.1}", score));
+        }
+    }
+    rule
+}
+
+/// Serialize a scan as a SARIF 2.1.0 log with one result per vulnerable finding
+pub fn to_sarif(scan: &ScanResult) -> Result<String> {
+    let mut rule_ids: Vec<Uuid> = Vec::new();
+    let mut rules = Vec::new();
+    let mut results = Vec::new();
+
+    for finding in scan.vulnerabilities.iter().filter(|v| v.vulnerable) {
+        let metadata = exploit_metadata(&finding.exploit_id);
+        let rule_index = match rule_ids.iter().position(|id| *id == finding.exploit_id) {
+            Some(index) => index,
+            None => {
+                rule_ids.push(finding.exploit_id);
+                rules.push(sarif_rule(&finding.exploit_id, metadata.as_ref()));
+                rules.len() - 1
+            }
+        };
+
+        let name = metadata
+            .as_ref()
+            .map(|m| m.name.clone())
+            .unwrap_or_else(|| finding.exploit_id.to_string());
+        let mut result = json!({
+            "ruleId": finding.exploit_id.to_string(),
+            "ruleIndex": rule_index,
+            "level": sarif_level(&finding.severity),
+            "message": { "text": format!("{} is vulnerable to {}", scan.target, name) },
+            "locations": [{
+                "physicalLocation": { "artifactLocation": { "uri": scan.target } },
+            }],
+            "properties": { "severity": finding.severity },
+        });
+        if let Some(score) = finding.cvss_score {
+            result["properties"]["cvss_score"] = json!(score);
+        }
+        if let Some(proof) = &finding.proof {
+            result["properties"]["proof"] = json!(proof);
+        }
+        results.push(result);
+    }
+
+    let mut invocation = json!({
+        "executionSuccessful": matches!(scan.status, ScanStatus::Completed),
+        "startTimeUtc": scan.started_at,
+    });
+    if let Some(completed_at) = scan.completed_at {
+        invocation["endTimeUtc"] = json!(completed_at);
+    }
+
+    let sarif = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "PyRouterSploit",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "automationDetails": { "id": format!("{}/{}", scan.scan_type, scan.id) },
+            "invocations": [invocation],
+            "results": results,
+        }],
+    });
+
+    Ok(serde_json::to_string_pretty(&sarif)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::test_support::{finding, scan};
+    use crate::db::models::Protocol;
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn test_to_sarif() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let metadata = ExploitMetadata {
+            id: Uuid::new_v4(),
+            name: "Netgear R7000 Command Injection".to_string(),
+            description: "Unauthenticated command injection via the cgi-bin path".to_string(),
+            authors: vec![],
+            references: vec!["https://www.kb.cert.org/vuls/id/582384".to_string()],
+            devices: vec!["R7000".to_string()],
+            category: "exploits/routers/netgear".to_string(),
+            protocol: Protocol::HTTP,
+            severity: Severity::Critical,
+            verified: true,
+            cryptex_id: None,
+            cvss_vector: Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H".to_string()),
+            updated_at: Utc::now(),
+        };
+        exploits::insert(&metadata).unwrap();
+
+        let scan = scan("192.168.1.1", vec![
+            finding(metadata.id, true, Severity::Critical, Some(9.8)),
+            finding(Uuid::nil(), true, Severity::Low, Some(9.8)),
+            finding(Uuid::nil(), false, Severity::Low, Some(9.8)),
+        ]);
+
+        let sarif: Value = serde_json::from_str(&to_sarif(&scan).unwrap()).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], metadata.id.to_string());
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "192.168.1.1");
+        assert_eq!(results[1]["level"], "note");
+
+        let rule = &run["tool"]["driver"]["rules"][0];
+        assert_eq!(rule["name"], "Netgear R7000 Command Injection");
+        assert_eq!(rule["helpUri"], "https://www.kb.cert.org/vuls/id/582384");
+        assert_eq!(rule["properties"]["security-severity"], "9.8");
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], Uuid::nil().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_two_scans() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let (rce, xss, info) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let first = scan("10.0.0.1", vec![
+            finding(rce, true, Severity::Critical, Some(9.8)),
+            finding(xss, true, Severity::Medium, Some(6.1)),
+            finding(info, false, Severity::Info, None),
+        ]);
+        let second = scan("10.0.0.2", vec![
+            finding(rce, true, Severity::Critical, Some(9.8)),
+            finding(info, true, Severity::Info, None),
+        ]);
+        let clean = scan("10.0.0.3", vec![]);
+        for scan in [&first, &second, &clean] {
+            scans::insert(scan).unwrap();
+        }
+
+        let report = aggregate(&[first.id, second.id, clean.id]).unwrap();
+        assert_eq!(report.targets_scanned, 3);
+        assert_eq!(report.vulnerable_targets, vec!["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(report.vulnerabilities_found, 4);
+        assert_eq!(report.by_severity, SeverityBreakdown { critical: 2, high: 0, medium: 1, low: 0, info: 1 });
+
+        let top: Vec<Uuid> = report.top_findings.iter().map(|f| f.exploit_id).collect();
+        assert_eq!(top, vec![rce, xss, info]);
+        assert_eq!(report.top_findings[0].targets, vec!["10.0.0.1", "10.0.0.2"]);
+
+        assert!(aggregate(&[first.id, Uuid::new_v4()]).is_err());
+    }
+
+    #[test]
+    fn test_diff_lists_fixed_vulnerability_as_resolved() {
+        let (fixed, kept, added) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let high = |exploit_id| finding(exploit_id, true, Severity::High, None);
+        let previous = scan("192.168.1.1", vec![high(fixed), high(kept)]);
+        let current = scan("192.168.1.1", vec![high(kept), high(added), finding(fixed, false, Severity::High, None)]);
+
+        let diff = diff(&previous, &current);
+        assert_eq!(diff.previous_scan_id, previous.id);
+        assert_eq!(diff.targets.len(), 1);
+        let target = &diff.targets[0];
+        let ids = |findings: &[VulnerabilityFinding]| findings.iter().map(|f| f.exploit_id).collect::<Vec<_>>();
+        assert_eq!(ids(&target.resolved), vec![fixed]);
+        assert_eq!(ids(&target.persisting), vec![kept]);
+        assert_eq!(ids(&target.new), vec![added]);
+    }
+}
@@ -1,12 +1,1114 @@
 // Scanner engine
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use parking_lot::RwLock;
+use lazy_static::lazy_static;
+use tokio::sync::{broadcast, Semaphore};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::core::{cvss, net, scope, webhook};
+use crate::core::exploit::{bounded, registry, Bounded, Target};
+use crate::core::net::{DnsConfig, ProxyConfig, TlsOptions};
+use crate::db::models::{Protocol, ScanCallbacks, ScanRequest, ScanResult, ScanStatus, VulnerabilityFinding};
+use crate::db::redb_client::scans;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScannerConfig {
     pub threads: usize,
     pub timeout: u64,
+    /// Max requests per second to the target host; `None` is unpaced
+    #[serde(default)]
+    pub rate_limit: Option<f64>,
+    /// Seconds to wait for the target to accept a connection before any
+    /// check runs; 0 skips the precheck
+    #[serde(default)]
+    pub precheck_timeout: u64,
+    /// Pivot the precheck, fingerprinting and exploit checks connect through
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub tls: TlsOptions,
+    #[serde(default)]
+    pub dns: DnsConfig,
+}
+
+impl ScannerConfig {
+    /// These settings with the TLS server name and pace `request` asks for
+    pub(crate) fn for_request(mut self, request: &ScanRequest) -> Self {
+        if request.sni.is_some() {
+            self.tls.sni = request.sni.clone();
+        }
+        if request.rate_limit.is_some() {
+            self.rate_limit = request.rate_limit;
+        }
+        self
+    }
+}
+
+/// Token bucket pacing outbound requests to one host. Holds a single
+/// token, so requests are spaced evenly with no burst.
+pub struct TokenBucket {
+    rate: f64,
+    /// (tokens, last refill). Tokens go negative to reserve future slots.
+    state: parking_lot::Mutex<(f64, std::time::Instant)>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64) -> Result<Self> {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(anyhow!("Rate limit must be a positive number of requests per second, got {}", rate));
+        }
+        Ok(Self {
+            rate,
+            state: parking_lot::Mutex::new((1.0, std::time::Instant::now())),
+        })
+    }
+
+    /// Bucket for `rate_limit`, or `None` when unpaced
+    pub fn for_limit(rate_limit: Option<f64>) -> Result<Option<Arc<Self>>> {
+        rate_limit.map(|rate| Self::new(rate).map(Arc::new)).transpose()
+    }
+
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock();
+            let now = std::time::Instant::now();
+            let (tokens, last) = *state;
+            let tokens = (tokens + now.duration_since(last).as_secs_f64() * self.rate).min(1.0) - 1.0;
+            *state = (tokens, now);
+            if tokens < 0.0 { Duration::from_secs_f64(-tokens / self.rate) } else { Duration::ZERO }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Supported scan types
+pub const SCAN_TYPES: &[&str] = &["autopwn", "http", "ssh", "telnet", "all"];
+
+/// Progress events emitted while a scan runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ScanEvent {
+    #[serde(rename = "scan.started")]
+    Started { scan_id: Uuid, targets: usize },
+    #[serde(rename = "scan.target_scanned")]
+    TargetScanned {
+        scan_id: Uuid,
+        target: String,
+        targets_scanned: usize,
+    },
+    #[serde(rename = "scan.vulnerability_found")]
+    VulnerabilityFound {
+        scan_id: Uuid,
+        target: String,
+        finding: VulnerabilityFinding,
+    },
+    #[serde(rename = "scan.completed")]
+    Completed {
+        scan_id: Uuid,
+        vulnerabilities_found: usize,
+    },
+    #[serde(rename = "scan.error")]
+    Error { scan_id: Uuid, message: String },
+}
+
+impl ScanEvent {
+    pub fn scan_id(&self) -> Uuid {
+        match self {
+            ScanEvent::Started { scan_id, .. }
+            | ScanEvent::TargetScanned { scan_id, .. }
+            | ScanEvent::VulnerabilityFound { scan_id, .. }
+            | ScanEvent::Completed { scan_id, .. }
+            | ScanEvent::Error { scan_id, .. } => *scan_id,
+        }
+    }
+
+    /// Whether no further events follow this one
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ScanEvent::Completed { .. } | ScanEvent::Error { .. })
+    }
+}
+
+/// Per-scan broadcast channels for progress events
+pub mod events {
+    use super::*;
+
+    const CHANNEL_CAPACITY: usize = 256;
+
+    lazy_static! {
+        static ref CHANNELS: RwLock<HashMap<Uuid, broadcast::Sender<ScanEvent>>> =
+            RwLock::new(HashMap::new());
+    }
+
+    /// Events of one scan for one subscriber. The scan's channel closes
+    /// when its last subscription is dropped.
+    pub struct Subscription {
+        scan_id: Uuid,
+        rx: Option<broadcast::Receiver<ScanEvent>>,
+    }
+
+    impl Subscription {
+        pub async fn recv(&mut self) -> Result<ScanEvent, broadcast::error::RecvError> {
+            match self.rx.as_mut() {
+                Some(rx) => rx.recv().await,
+                None => Err(broadcast::error::RecvError::Closed),
+            }
+        }
+    }
+
+    impl Drop for Subscription {
+        fn drop(&mut self) {
+            drop(self.rx.take());
+            let mut channels = CHANNELS.write();
+            if channels.get(&self.scan_id).is_some_and(|tx| tx.receiver_count() == 0) {
+                channels.remove(&self.scan_id);
+            }
+        }
+    }
+
+    /// Subscribe to the events of `scan`, unless it has already finished
+    /// and none will follow. Subscribe before the scan could finish, or
+    /// check its stored status again afterwards.
+    pub fn subscribe(scan: &ScanResult) -> Option<Subscription> {
+        if !matches!(scan.status, ScanStatus::Queued | ScanStatus::Running) {
+            return None;
+        }
+        let rx = CHANNELS
+            .write()
+            .entry(scan.id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe();
+        Some(Subscription { scan_id: scan.id, rx: Some(rx) })
+    }
+
+    /// Publish an event to the scan's subscribers, if it has any. The
+    /// channel is dropped after a terminal event.
+    pub fn publish(event: ScanEvent) {
+        let scan_id = event.scan_id();
+        let sender = if event.is_terminal() {
+            CHANNELS.write().remove(&scan_id)
+        } else {
+            CHANNELS.read().get(&scan_id).cloned()
+        };
+        if let Some(sender) = sender {
+            // Every subscriber may have just left
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Whether scan `scan_id` has an open channel
+    #[cfg(test)]
+    pub(crate) fn is_open(scan_id: Uuid) -> bool {
+        CHANNELS.read().contains_key(&scan_id)
+    }
+}
+
+/// Tracks in-flight scans so shutdown can drain them and callers can cancel them
+pub mod active {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::sync::Notify;
+
+    static ACTIVE_SCANS: AtomicUsize = AtomicUsize::new(0);
+
+    lazy_static! {
+        static ref IDLE: Notify = Notify::new();
+        static ref CANCEL_TOKENS: RwLock<HashMap<Uuid, CancellationToken>> = RwLock::new(HashMap::new());
+    }
+
+    /// Held by a running scan; dropping it marks the scan finished
+    pub struct ActiveScanGuard(Option<Uuid>);
+
+    impl Drop for ActiveScanGuard {
+        fn drop(&mut self) {
+            if let Some(scan_id) = self.0 {
+                CANCEL_TOKENS.write().remove(&scan_id);
+            }
+            if ACTIVE_SCANS.fetch_sub(1, Ordering::SeqCst) == 1 {
+                IDLE.notify_waiters();
+            }
+        }
+    }
+
+    pub fn track() -> ActiveScanGuard {
+        ACTIVE_SCANS.fetch_add(1, Ordering::SeqCst);
+        ActiveScanGuard(None)
+    }
+
+    /// Track `scan_id` along with a token that [`cancel`] fires
+    pub fn track_cancellable(scan_id: Uuid) -> (ActiveScanGuard, CancellationToken) {
+        let token = CancellationToken::new();
+        CANCEL_TOKENS.write().insert(scan_id, token.clone());
+        ACTIVE_SCANS.fetch_add(1, Ordering::SeqCst);
+        (ActiveScanGuard(Some(scan_id)), token)
+    }
+
+    /// Abort a running scan's in-flight checks. False if it isn't running.
+    pub fn cancel(scan_id: &Uuid) -> bool {
+        CANCEL_TOKENS.read().get(scan_id).map(CancellationToken::cancel).is_some()
+    }
+
+    pub fn count() -> usize {
+        ACTIVE_SCANS.load(Ordering::SeqCst)
+    }
+
+    /// Wait for running scans to finish. Returns false if the timeout elapsed first.
+    pub async fn drain(timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = IDLE.notified();
+                if count() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// Async TCP connect port scanning
+pub mod portscan {
+    use super::*;
+    use std::net::{IpAddr, SocketAddr};
+    use tokio::net::TcpStream;
+
+    /// Whether a TCP connection to `addr` completes within `timeout`
+    async fn is_open(addr: SocketAddr, timeout: Duration) -> bool {
+        matches!(tokio::time::timeout(timeout, TcpStream::connect(addr)).await, Ok(Ok(_)))
+    }
+
+    /// Connect-scan `ports` on `ip`, at most `concurrency` connections in flight.
+    /// Refused, filtered and unreachable ports are simply reported closed.
+    /// Returns the open ports in ascending order.
+    pub async fn scan_ports(
+        ip: IpAddr,
+        ports: &[u16],
+        timeout: Duration,
+        concurrency: usize,
+    ) -> Result<Vec<u16>> {
+        scan_ports_paced(ip, ports, timeout, concurrency, None).await
+    }
+
+    /// [`scan_ports`] opening at most `rate_limit` connections per second
+    pub async fn scan_ports_paced(
+        ip: IpAddr,
+        ports: &[u16],
+        timeout: Duration,
+        concurrency: usize,
+        rate_limit: Option<f64>,
+    ) -> Result<Vec<u16>> {
+        let mut ports = ports.to_vec();
+        ports.sort_unstable();
+        ports.dedup();
+
+        let bucket = TokenBucket::for_limit(rate_limit)?;
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut tasks = Vec::with_capacity(ports.len());
+
+        for port in ports {
+            let permit = semaphore.clone().acquire_owned().await?;
+            if let Some(bucket) = &bucket {
+                bucket.acquire().await;
+            }
+            tasks.push(tokio::spawn(async move {
+                let _permit = permit;
+                is_open(SocketAddr::new(ip, port), timeout).await.then_some(port)
+            }));
+        }
+
+        let mut open = Vec::new();
+        for task in tasks {
+            if let Some(port) = task.await? {
+                open.push(port);
+            }
+        }
+        tracing::debug!("{}: {} open port(s)", ip, open.len());
+        Ok(open)
+    }
+}
+
+/// HTTP device fingerprinting from the web management UI
+pub mod fingerprint {
+    use super::*;
+    use crate::db::models::ExploitMetadata;
+    use regex::Regex;
+    use reqwest::header::{HeaderMap, SERVER, WWW_AUTHENTICATE};
+
+    /// Best guess at the device behind a web UI
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    pub struct DeviceFingerprint {
+        pub vendor: Option<String>,
+        pub model: Option<String>,
+        /// 0.0 (no idea) to 1.0 (certain)
+        pub confidence: f32,
+        /// Which signatures matched, e.g. `realm: DIR-645`
+        pub evidence: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Source {
+        Server,
+        Realm,
+        Title,
+        Body,
+    }
+
+    impl Source {
+        fn weight(self) -> f32 {
+            match self {
+                Source::Realm => 0.5,
+                Source::Server => 0.4,
+                Source::Title => 0.35,
+                Source::Body => 0.2,
+            }
+        }
+
+        fn label(self) -> &'static str {
+            match self {
+                Source::Server => "server",
+                Source::Realm => "realm",
+                Source::Title => "title",
+                Source::Body => "body",
+            }
+        }
+    }
+
+    /// (vendor, where to look, pattern). A `model` capture group names the
+    /// model. Text skipped before an optional model sits inside the optional
+    /// group, so a lazy `.*?` can't match nothing and skip the model.
+    const SIGNATURES: &[(&str, Source, &str)] = &[
+        ("D-Link", Source::Server, r"(?i)d-?link"),
+        ("D-Link", Source::Realm, r"(?i)(?P<model>DIR-\d+[A-Z]*)"),
+        ("D-Link", Source::Title, r"(?i)d-?link(?:.*?(?P<model>DIR-\d+[A-Z]*))?"),
+        ("D-Link", Source::Body, r"(?i)(?:d-?link|dlink\.com)"),
+        ("Netgear", Source::Realm, r"(?i)netgear\s+(?P<model>[A-Z]+\d+\w*)"),
+        ("Netgear", Source::Title, r"(?i)netgear\s*(?P<model>[A-Z]+\d+\w*)?"),
+        ("Netgear", Source::Body, r"(?i)routerlogin\.net"),
+        ("TP-Link", Source::Realm, r"(?i)tp-?link(?:.*?(?P<model>(?:TL-)?[A-Z]{2,}-?\d+\w*))?"),
+        ("TP-Link", Source::Title, r"(?i)tp-?link"),
+        ("TP-Link", Source::Body, r"(?i)tplinkwifi\.net"),
+        ("Linksys", Source::Realm, r"(?i)(?:linksys\s*)?(?P<model>WRT\d+\w*)"),
+        ("Linksys", Source::Title, r"(?i)linksys"),
+        ("ASUS", Source::Realm, r"(?i)(?P<model>RT-[A-Z]+\d+\w*)"),
+        ("ASUS", Source::Title, r"(?i)asus\w*\s*(?P<model>RT-[A-Z]+\d+\w*)?"),
+        ("MikroTik", Source::Server, r"(?i)mikrotik"),
+        ("MikroTik", Source::Title, r"(?i)routeros"),
+        ("Cisco", Source::Server, r"(?i)cisco"),
+        ("Cisco", Source::Realm, r"(?i)cisco"),
+        ("ZyXEL", Source::Title, r"(?i)zyxel"),
+        ("Huawei", Source::Server, r"(?i)huawei"),
+        ("Huawei", Source::Title, r"(?i)huawei"),
+    ];
+
+    lazy_static! {
+        static ref COMPILED: Vec<(&'static str, Source, Regex)> = SIGNATURES
+            .iter()
+            .map(|(vendor, source, pattern)| (*vendor, *source, Regex::new(pattern).unwrap()))
+            .collect();
+        static ref TITLE: Regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+        static ref REALM: Regex = Regex::new(r#"(?i)realm="([^"]*)""#).unwrap();
+    }
+
+    /// Score a captured response against the known signatures
+    pub fn fingerprint_response(headers: &HeaderMap, body: &str) -> DeviceFingerprint {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+        let server = header(SERVER);
+        let realm = REALM
+            .captures(header(WWW_AUTHENTICATE))
+            .map(|c| c[1].to_string())
+            .unwrap_or_default();
+        let title = TITLE
+            .captures(body)
+            .map(|c| c[1].trim().to_string())
+            .unwrap_or_default();
+
+        // vendor -> (probability none of the matches are right, model, evidence)
+        let mut scores: HashMap<&str, (f32, Option<String>, Vec<String>)> = HashMap::new();
+        for (vendor, source, regex) in COMPILED.iter() {
+            let haystack = match source {
+                Source::Server => server,
+                Source::Realm => realm.as_str(),
+                Source::Title => title.as_str(),
+                Source::Body => body,
+            };
+            let Some(captures) = regex.captures(haystack).filter(|_| !haystack.is_empty()) else {
+                continue;
+            };
+
+            let entry = scores.entry(vendor).or_insert((1.0, None, Vec::new()));
+            entry.0 *= 1.0 - source.weight();
+            let model = captures.name("model").map(|m| m.as_str().to_uppercase());
+            if entry.1.is_none() {
+                entry.1 = model;
+            }
+            entry.2.push(format!("{}: {}", source.label(), &captures[0]));
+        }
+
+        scores
+            .into_iter()
+            .map(|(vendor, (miss, model, evidence))| DeviceFingerprint {
+                vendor: Some(vendor.to_string()),
+                model,
+                confidence: 1.0 - miss,
+                evidence,
+            })
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .unwrap_or_default()
+    }
+
+    /// Fetch the web UI of `target` and fingerprint the device serving it
+    pub async fn http_fingerprint(target: &Target) -> Result<DeviceFingerprint> {
+        let client = net::http_client(target).await?.timeout(Duration::from_secs(10)).build()?;
+        let url = net::http_url(target, false, "/");
+        let response = client.get(url).send().await?;
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+
+        Ok(fingerprint_response(&headers, &body))
+    }
+
+    impl DeviceFingerprint {
+        /// Whether an exploit is worth trying against this device. Unknown
+        /// devices and exploits without a device list always match.
+        pub fn matches(&self, metadata: &ExploitMetadata) -> bool {
+            let Some(vendor) = &self.vendor else {
+                return true;
+            };
+            let normalize = |s: &str| s.to_lowercase().replace(['-', ' '], "");
+            let vendor = normalize(vendor);
+
+            metadata.devices.is_empty()
+                || metadata.devices.iter().any(|device| {
+                    let device = normalize(device);
+                    device.contains(&vendor) || device == "multi" || device == "any"
+                })
+        }
+    }
+
+    /// Fingerprint a target's web UI, giving up quietly if it has none
+    pub async fn detect(target: &Target, timeout: Duration) -> Option<DeviceFingerprint> {
+        match tokio::time::timeout(timeout, http_fingerprint(target)).await {
+            Ok(Ok(fingerprint)) if fingerprint.vendor.is_some() => {
+                tracing::info!(
+                    "{} looks like {} {} ({:.0}%)",
+                    target,
+                    fingerprint.vendor.as_deref().unwrap_or_default(),
+                    fingerprint.model.as_deref().unwrap_or_default(),
+                    fingerprint.confidence * 100.0
+                );
+                Some(fingerprint)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether an exploit speaking `protocol` belongs in a scan of `scan_type`
+fn matches_scan_type(scan_type: &str, protocol: &Protocol) -> bool {
+    match scan_type {
+        "http" => matches!(protocol, Protocol::HTTP | Protocol::HTTPS),
+        "ssh" => matches!(protocol, Protocol::SSH),
+        "telnet" => matches!(protocol, Protocol::Telnet),
+        _ => true,
+    }
 }
 
-// Scanner implementation will be added here
+/// Fail unless `target` accepts a connection on one of `ports`, so a dead
+/// host isn't put through every check. Passes when there is nothing to probe.
+async fn precheck(target: &Target, ports: &BTreeSet<u16>, timeout: Duration) -> Result<()> {
+    if ports.is_empty() {
+        return Ok(());
+    }
+    let probes = ports.iter().map(|&port| Box::pin(net::precheck(target, port, timeout)));
+    futures::future::select_ok(probes).await.map(|_| ())
+}
+
+/// Told about each vulnerable finding as soon as its check returns
+type OnFinding = Arc<dyn Fn(&VulnerabilityFinding) + Send + Sync>;
+
+/// Run `check()` for every registered exploit applicable to the scan type.
+/// The target must pass the scope, and every connection goes to the
+/// addresses it was vetted at. The target's port, or else the default
+/// ports of those exploits, must answer first. Autopwn scans then
+/// fingerprint the device and skip exploits for other vendors.
+async fn check_exploits(
+    target: &str,
+    scan_type: &str,
+    config: &ScannerConfig,
+    cancel: &CancellationToken,
+    on_finding: OnFinding,
+) -> Result<Vec<VulnerabilityFinding>> {
+    let addresses = scope::enforce(target).await?;
+    let target = Target::parse(target)?
+        .with_proxy(config.proxy.clone())
+        .with_tls(config.tls.clone())
+        .with_dns(config.dns.clone())
+        .with_addresses(addresses);
+    // Every scan targets one host, so one bucket paces that host. Each
+    // exploit check counts as one request.
+    let bucket = TokenBucket::for_limit(config.rate_limit)?;
+    if config.precheck_timeout > 0 {
+        let ports: BTreeSet<u16> = match target.port {
+            Some(port) => BTreeSet::from([port]),
+            None => registry::all()
+                .into_iter()
+                .filter(|registration| matches_scan_type(scan_type, &registration.metadata.protocol))
+                .filter_map(|registration| net::default_port(&registration.metadata.protocol))
+                .collect(),
+        };
+        if let Some(bucket) = &bucket {
+            bucket.acquire().await;
+        }
+        precheck(&target, &ports, Duration::from_secs(config.precheck_timeout)).await?;
+    }
+    let device = match scan_type {
+        "autopwn" | "all" => {
+            if let Some(bucket) = &bucket {
+                bucket.acquire().await;
+            }
+            fingerprint::detect(&target, Duration::from_secs(config.timeout.clamp(1, 10))).await
+        }
+        _ => None,
+    };
+    let semaphore = Arc::new(Semaphore::new(config.threads.max(1)));
+    let timeout = Duration::from_secs(config.timeout.max(1));
+    let mut tasks = Vec::new();
+
+    for registration in registry::all() {
+        let metadata = registration.metadata;
+        if !matches_scan_type(scan_type, &metadata.protocol) {
+            continue;
+        }
+        if device.as_ref().is_some_and(|device| !device.matches(&metadata)) {
+            tracing::debug!("Skipping {}: not applicable to detected device", metadata.name);
+            continue;
+        }
+        let exploit = (registration.constructor)();
+
+        let permit = semaphore.clone().acquire_owned().await?;
+        if let Some(bucket) = &bucket {
+            bucket.acquire().await;
+        }
+        if cancel.is_cancelled() {
+            break;
+        }
+        let target = target.clone();
+        let cancel = cancel.clone();
+        let on_finding = on_finding.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = permit;
+            let (vulnerable, proof) = match bounded(exploit.check_with_proof(&target), timeout, &cancel).await {
+                Ok(Bounded::Completed(outcome)) => (outcome.vulnerable, outcome.proof),
+                Ok(Bounded::Timeout) => {
+                    tracing::debug!("{} check timed out on {}", metadata.name, target);
+                    (false, None)
+                }
+                Ok(Bounded::Cancelled) => (false, None),
+                Err(e) => {
+                    tracing::debug!("{} check failed on {}: {}", metadata.name, target, e);
+                    (false, None)
+                }
+            };
+            let finding = VulnerabilityFinding {
+                exploit_id: metadata.id,
+                vulnerable,
+                proof,
+                cvss_score: metadata.cvss_vector.as_deref().and_then(|vector| {
+                    cvss::score(vector)
+                        .map_err(|e| tracing::warn!("{} has an invalid CVSS vector: {}", metadata.name, e))
+                        .ok()
+                }),
+                severity: metadata.severity,
+            };
+            if finding.vulnerable {
+                on_finding(&finding);
+            }
+            finding
+        }));
+    }
+
+    let mut findings = Vec::new();
+    for task in tasks {
+        let finding = task.await?;
+        if finding.vulnerable {
+            findings.push(finding);
+        }
+    }
+    Ok(findings)
+}
+
+/// Check `target` against every registered exploit and collect the vulnerable findings.
+/// Checks run concurrently, at most `config.threads` at a time, each bounded by `config.timeout` seconds.
+pub async fn autopwn(target: &str, config: &ScannerConfig) -> Result<ScanResult> {
+    let started_at = Utc::now();
+    let vulnerabilities = check_exploits(target, "autopwn", config, &CancellationToken::new(), Arc::new(|_| {})).await?;
+    let risk_score = cvss::risk_score(&vulnerabilities);
+
+    Ok(ScanResult {
+        id: Uuid::new_v4(),
+        target: target.trim().to_string(),
+        scan_type: "autopwn".to_string(),
+        started_at,
+        completed_at: Some(Utc::now()),
+        status: ScanStatus::Completed,
+        vulnerabilities,
+        credentials: Vec::new(),
+        risk_score,
+        tenant_id: None,
+    })
+}
+
+fn callback_urls(callbacks: &ScanCallbacks) -> impl Iterator<Item = &String> {
+    [&callbacks.on_complete, &callbacks.on_vulnerability, &callbacks.on_error]
+        .into_iter()
+        .flatten()
+}
+
+async fn validate_request(request: &ScanRequest) -> Result<()> {
+    if request.target.trim().is_empty() {
+        return Err(anyhow!("Target must not be empty"));
+    }
+    if !SCAN_TYPES.contains(&request.scan_type.as_str()) {
+        return Err(anyhow!("Unsupported scan type: {} (expected one of {:?})", request.scan_type, SCAN_TYPES));
+    }
+    TokenBucket::for_limit(request.rate_limit)?;
+    scope::enforce(&request.target).await?;
+    for url in callback_urls(&request.callbacks) {
+        webhook::validate_url(url).await?;
+    }
+    Ok(())
+}
+
+/// What a scan would do, as returned for dry runs
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ScanPlan {
+    pub target: String,
+    pub scan_type: String,
+    /// Whether the device is fingerprinted first
+    pub fingerprint: bool,
+    /// Exploits whose `check()` would run. Fingerprinting may skip some of them.
+    pub exploits: Vec<String>,
+    pub actions: Vec<String>,
+}
+
+/// Validate `request` like queueing it would and describe the scan,
+/// without touching the network or storing anything
+pub async fn plan(request: &ScanRequest, config: &ScannerConfig) -> Result<ScanPlan> {
+    validate_request(request).await?;
+    let config = &config.clone().for_request(request);
+    let target = Target::parse(request.target.trim())?;
+    let fingerprint = matches!(request.scan_type.as_str(), "autopwn" | "all");
+
+    let mut exploits: Vec<String> = registry::all()
+        .into_iter()
+        .map(|registration| registration.metadata)
+        .filter(|metadata| matches_scan_type(&request.scan_type, &metadata.protocol))
+        .map(|metadata| metadata.name)
+        .collect();
+    exploits.sort();
+
+    let mut actions = Vec::new();
+    if fingerprint {
+        actions.push(format!("Fingerprint the web UI at http://{}/", target.address(80)));
+    }
+    TokenBucket::for_limit(config.rate_limit)?;
+    actions.push(format!(
+        "Run {} exploit checks against {}, {} at a time with a {}s timeout each{}",
+        exploits.len(),
+        target,
+        config.threads.max(1),
+        config.timeout.max(1),
+        config.rate_limit.map(|rate| format!(", at most {} per second", rate)).unwrap_or_default()
+    ));
+    actions.push("Store the scan record and findings".to_string());
+    actions.extend(callback_urls(&request.callbacks).map(|url| format!("POST scan events to {}", url)));
+
+    Ok(ScanPlan {
+        target: target.to_string(),
+        scan_type: request.scan_type.clone(),
+        fingerprint,
+        exploits,
+        actions,
+    })
+}
+
+/// Validate `request` and build its initial, not yet stored, record
+pub(crate) async fn new_record(request: &ScanRequest, status: ScanStatus) -> Result<ScanResult> {
+    if request.dry_run {
+        return Err(anyhow!("Dry-run scans are planned with scanner::plan, not submitted"));
+    }
+    validate_request(request).await?;
+
+    Ok(ScanResult {
+        id: Uuid::new_v4(),
+        target: request.target.trim().to_string(),
+        scan_type: request.scan_type.clone(),
+        started_at: Utc::now(),
+        completed_at: None,
+        status,
+        vulnerabilities: Vec::new(),
+        credentials: Vec::new(),
+        risk_score: 0.0,
+        tenant_id: request.tenant_id.clone(),
+    })
+}
+
+/// Stop a queued or running scan. A running scan keeps its findings so far
+/// and is stored as cancelled once its checks wind down. False if the scan
+/// already finished.
+pub fn cancel(scan_id: &Uuid) -> Result<bool> {
+    // A worker only starts a scan that is still queued, so claiming it here wins
+    let claimed = scans::transition(scan_id, ScanStatus::Queued, |scan| {
+        scan.status = ScanStatus::Cancelled;
+        scan.completed_at = Some(Utc::now());
+    })?;
+    if claimed.is_some() {
+        return Ok(true);
+    }
+    // Workers track a scan before marking it running, so a running one is found
+    Ok(active::cancel(scan_id))
+}
+
+/// Run a stored scan to the end, persisting each status change and
+/// emitting its events. The caller tracks it in [`active`].
+pub(crate) async fn execute(
+    mut record: ScanResult,
+    callbacks: ScanCallbacks,
+    config: ScannerConfig,
+    cancel: CancellationToken,
+) {
+    let scan_id = record.id;
+    if !matches!(record.status, ScanStatus::Running) {
+        record.status = ScanStatus::Running;
+        record.started_at = Utc::now();
+        if let Err(e) = scans::update(&record) {
+            tracing::error!("Failed to mark scan {} running: {}", scan_id, e);
+        }
+    }
+    let started = std::time::Instant::now();
+    emit(&callbacks, ScanEvent::Started { scan_id, targets: 1 });
+
+    let on_finding: OnFinding = {
+        let callbacks = callbacks.clone();
+        let target = record.target.clone();
+        Arc::new(move |finding| {
+            emit(&callbacks, ScanEvent::VulnerabilityFound {
+                scan_id,
+                target: target.clone(),
+                finding: finding.clone(),
+            })
+        })
+    };
+
+    let mut failure = None;
+    match check_exploits(&record.target, &record.scan_type, &config, &cancel, on_finding).await {
+        // Findings made before the cancel are kept
+        Ok(findings) if cancel.is_cancelled() => {
+            tracing::info!("Scan {} cancelled", scan_id);
+            record.risk_score = cvss::risk_score(&findings);
+            record.vulnerabilities = findings;
+            record.status = ScanStatus::Cancelled;
+        }
+        Ok(findings) => {
+            emit(&callbacks, ScanEvent::TargetScanned {
+                scan_id,
+                target: record.target.clone(),
+                targets_scanned: 1,
+            });
+            record.risk_score = cvss::risk_score(&findings);
+            record.vulnerabilities = findings;
+            record.status = ScanStatus::Completed;
+        }
+        Err(e) => {
+            tracing::error!("Scan {} failed: {}", scan_id, e);
+            record.status = ScanStatus::Failed;
+            failure = Some(e.to_string());
+        }
+    }
+
+    record.completed_at = Some(Utc::now());
+    let status = match record.status {
+        ScanStatus::Completed => "completed",
+        ScanStatus::Cancelled => "cancelled",
+        _ => "failed",
+    };
+    crate::telemetry::record_scan(&record.scan_type, status, started.elapsed());
+    if let Err(e) = scans::update(&record) {
+        tracing::error!("Failed to persist scan {}: {}", scan_id, e);
+    }
+
+    match record.status {
+        ScanStatus::Completed => emit(&callbacks, ScanEvent::Completed {
+            scan_id,
+            vulnerabilities_found: record.vulnerabilities.len(),
+        }),
+        ScanStatus::Cancelled => emit(&callbacks, ScanEvent::Error {
+            scan_id,
+            message: "Scan cancelled".to_string(),
+        }),
+        _ => emit(&callbacks, ScanEvent::Error {
+            scan_id,
+            message: match failure {
+                Some(reason) => format!("Scan failed: {}", reason),
+                None => "Scan failed".to_string(),
+            },
+        }),
+    }
+}
+
+/// Publish to stream subscribers and fire any matching webhook
+fn emit(callbacks: &ScanCallbacks, event: ScanEvent) {
+    webhook::notify(callbacks, &event);
+    events::publish(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exploit::test_support::MockExploit;
+    use crate::core::exploit::Exploit;
+    use crate::db::models::{ExploitMetadata, Severity};
+
+    #[tokio::test]
+    async fn test_autopwn_aggregates_vulnerable_findings() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        fn vulnerable() -> MockExploit {
+            MockExploit::new("Mock vulnerable")
+        }
+        fn patched() -> MockExploit {
+            MockExploit::new("Mock patched").vulnerable(false)
+        }
+        // Would report vulnerable, but never answers within the timeout
+        fn hanging() -> MockExploit {
+            MockExploit::new("Mock hanging").delay(Duration::from_secs(3))
+        }
+        registry::register("exploit_mock_vulnerable", "pyroutersploit_mock_vulnerable", || vulnerable().boxed());
+        registry::register("exploit_mock_patched", "pyroutersploit_mock_patched", || patched().boxed());
+        registry::register("exploit_mock_hanging", "pyroutersploit_mock_hanging", || hanging().boxed());
+        let [vulnerable_id, patched_id, hanging_id] = [vulnerable(), patched(), hanging()].map(|mock| mock.metadata().id);
+
+        // Stands in for the device's web UI, which autopwn fingerprints first
+        let app = axum::Router::new().route("/", axum::routing::get(|| async { "<html><title>Router</title></html>" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("127.0.0.1:{}", listener.local_addr().unwrap().port());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = ScannerConfig { threads: 2, timeout: 1, rate_limit: None, precheck_timeout: 0, proxy: None, tls: Default::default(), dns: Default::default() };
+        let result = autopwn(&target, &config).await.unwrap();
+
+        assert!(matches!(result.status, ScanStatus::Completed));
+        assert_eq!(result.scan_type, "autopwn");
+        assert!(result.completed_at.is_some());
+
+        let ids: Vec<Uuid> = result.vulnerabilities.iter().map(|f| f.exploit_id).collect();
+        assert!(ids.contains(&vulnerable_id));
+        assert!(!ids.contains(&patched_id));
+        assert!(!ids.contains(&hanging_id));
+        assert!(result.vulnerabilities.iter().all(|f| f.vulnerable));
+
+        let finding = result.vulnerabilities.iter().find(|f| f.exploit_id == vulnerable_id).unwrap();
+        let proof = finding.proof.as_ref().unwrap();
+        assert_eq!(proof.request, format!("GET / HTTP/1.1\r\nHost: {}", target));
+        assert_eq!(proof.response, "HTTP/1.1 200 OK");
+        assert!(proof.captured_at >= result.started_at);
+
+        // Findings are reported as each check returns, not once all have.
+        // Enough threads that no check waits for another to start.
+        let config = ScannerConfig { threads: 64, ..config };
+        let reported = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let on_finding: OnFinding = {
+            let reported = reported.clone();
+            Arc::new(move |finding| reported.lock().push((finding.exploit_id, std::time::Instant::now())))
+        };
+        check_exploits(&target, "autopwn", &config, &CancellationToken::new(), on_finding).await.unwrap();
+        let finished = std::time::Instant::now();
+        let (_, reported_at) = *reported.lock().iter().find(|(id, _)| *id == vulnerable_id).unwrap();
+        assert!(finished - reported_at >= Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_scan_events_fan_out() {
+        use crate::db::models::test_support::scan;
+
+        let running = ScanResult { status: ScanStatus::Running, ..scan("192.0.2.63", Vec::new()) };
+        let scan_id = running.id;
+        let mut first = events::subscribe(&running).unwrap();
+        let mut second = events::subscribe(&running).unwrap();
+
+        events::publish(ScanEvent::Started { scan_id, targets: 1 });
+        events::publish(ScanEvent::Completed { scan_id, vulnerabilities_found: 0 });
+        assert!(!events::is_open(scan_id));
+
+        for rx in [&mut first, &mut second] {
+            assert!(matches!(rx.recv().await.unwrap(), ScanEvent::Started { .. }));
+            assert!(rx.recv().await.unwrap().is_terminal());
+        }
+
+        // Finished scans get no channel
+        assert!(events::subscribe(&ScanResult { status: ScanStatus::Completed, ..running.clone() }).is_none());
+        events::publish(ScanEvent::Started { scan_id, targets: 1 });
+        assert!(!events::is_open(scan_id));
+
+        // The channel closes when the last subscriber leaves
+        let first = events::subscribe(&running).unwrap();
+        let second = events::subscribe(&running).unwrap();
+        drop(first);
+        assert!(events::is_open(scan_id));
+        drop(second);
+        assert!(!events::is_open(scan_id));
+    }
+
+    #[tokio::test]
+    async fn test_portscan_detects_listener() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = {
+            let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            closed.local_addr().unwrap().port()
+        };
+
+        let open = portscan::scan_ports(
+            "127.0.0.1".parse().unwrap(),
+            &[closed_port, open_port, open_port],
+            Duration::from_millis(500),
+            4,
+        )
+        .await
+        .unwrap();
+        assert_eq!(open, vec![open_port]);
+
+        // Unreachable hosts yield no open ports rather than an error
+        let open = portscan::scan_ports(
+            "192.0.2.1".parse().unwrap(),
+            &[80, 443],
+            Duration::from_millis(200),
+            2,
+        )
+        .await
+        .unwrap();
+        assert!(open.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_paces_connections() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let first = listener.local_addr().unwrap().port();
+        let ports: Vec<u16> = (0..6).map(|i| first.wrapping_add(i)).collect();
+
+        // 6 connections at 20/s: the first is immediate, the rest 50ms apart
+        let started = std::time::Instant::now();
+        let open = portscan::scan_ports_paced(
+            "127.0.0.1".parse().unwrap(),
+            &ports,
+            Duration::from_millis(200),
+            6,
+            Some(20.0),
+        )
+        .await
+        .unwrap();
+        assert!(open.contains(&first));
+        assert!(started.elapsed() >= Duration::from_millis(250), "{:?}", started.elapsed());
+
+        assert!(TokenBucket::new(0.0).is_err());
+        assert!(TokenBucket::new(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_http_fingerprint_dlink() {
+        use reqwest::header::{HeaderMap, HeaderValue, SERVER, WWW_AUTHENTICATE};
+
+        let mut headers = HeaderMap::new();
+        headers.insert(SERVER, HeaderValue::from_static("Linux, HTTP/1.1, DIR-645 Ver 1.03"));
+        headers.insert(WWW_AUTHENTICATE, HeaderValue::from_static(r#"Basic realm="DIR-645""#));
+        let body = r#"<html><head><title>D-LINK SYSTEMS, INC. | WIRELESS ROUTER | HOME</title></head>
+            <body><a href="http://www.dlink.com">D-Link</a></body></html>"#;
+
+        let device = fingerprint::fingerprint_response(&headers, body);
+        assert_eq!(device.vendor.as_deref(), Some("D-Link"));
+        assert_eq!(device.model.as_deref(), Some("DIR-645"));
+        assert!(device.confidence > 0.7);
+        assert!(device.evidence.iter().any(|e| e.starts_with("realm")));
+
+        let dlink_exploit = ExploitMetadata {
+            id: Uuid::new_v4(),
+            name: "D-Link HNAP RCE".to_string(),
+            description: String::new(),
+            authors: vec![],
+            references: vec![],
+            devices: vec!["D-Link DIR-645".to_string()],
+            category: "routers".to_string(),
+            protocol: Protocol::HTTP,
+            severity: Severity::Critical,
+            verified: true,
+            cryptex_id: None,
+            cvss_vector: None,
+            updated_at: Utc::now(),
+        };
+        let netgear_exploit = ExploitMetadata {
+            devices: vec!["Netgear R7000".to_string()],
+            ..dlink_exploit.clone()
+        };
+        assert!(device.matches(&dlink_exploit));
+        assert!(!device.matches(&netgear_exploit));
+
+        // Models named only in the page title or after other realm text
+        let device = fingerprint::fingerprint_response(
+            &HeaderMap::new(),
+            "<html><title>D-LINK SYSTEMS, INC. | WIRELESS ROUTER | DIR-850L</title></html>",
+        );
+        assert_eq!(device.vendor.as_deref(), Some("D-Link"));
+        assert_eq!(device.model.as_deref(), Some("DIR-850L"));
+        let mut headers = HeaderMap::new();
+        headers.insert(WWW_AUTHENTICATE, HeaderValue::from_static(r#"Basic realm="TP-LINK Wireless N Router WR841N""#));
+        let device = fingerprint::fingerprint_response(&headers, "");
+        assert_eq!(device.vendor.as_deref(), Some("TP-Link"));
+        assert_eq!(device.model.as_deref(), Some("WR841N"));
+
+        let unknown = fingerprint::fingerprint_response(&HeaderMap::new(), "<html></html>");
+        assert!(unknown.vendor.is_none());
+        assert_eq!(unknown.confidence, 0.0);
+        assert!(unknown.matches(&netgear_exploit));
+    }
+
+    #[test]
+    fn test_request_sni_overrides_config() {
+        let config = ScannerConfig {
+            threads: 1,
+            timeout: 1,
+            rate_limit: None,
+            precheck_timeout: 0,
+            proxy: None,
+            tls: TlsOptions { accept_invalid_certs: true, ..Default::default() },
+            dns: Default::default(),
+        };
+        let request = ScanRequest { sni: Some("device.local".to_string()), ..ScanRequest::new("192.0.2.1", "http") };
+        let tls = config.clone().for_request(&request).tls;
+        assert_eq!(tls.sni.as_deref(), Some("device.local"));
+        assert!(tls.accept_invalid_certs);
+        assert_eq!(config.for_request(&ScanRequest::new("192.0.2.1", "http")).tls.sni, None);
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_active_scans() {
+        let guard = active::track();
+        let scan = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            drop(guard);
+        });
+
+        assert!(active::drain(std::time::Duration::from_secs(5)).await);
+        scan.await.unwrap();
+    }
+}
@@ -0,0 +1,271 @@
+// Engagement scope: which targets exploits and scans may touch
+
+use anyhow::{Result, anyhow};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::core::exploit::Target;
+use crate::core::net::{self, DnsConfig};
+use crate::db::models::Config;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScopeRule {
+    Network { addr: IpAddr, prefix: u8 },
+    /// Exact hostname, or `*.example.com` for any subdomain
+    Host(String),
+}
+
+fn mask(addr: IpAddr, prefix: u8) -> u128 {
+    let (bits, width) = match addr {
+        IpAddr::V4(v4) => (u32::from(v4) as u128, 32),
+        IpAddr::V6(v6) => (u128::from(v6), 128),
+    };
+    let host_bits = width - prefix as u32;
+    if host_bits >= 128 { 0 } else { bits >> host_bits << host_bits }
+}
+
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    ip.is_ipv4() == network.is_ipv4() && mask(ip, prefix) == mask(network, prefix)
+}
+
+impl ScopeRule {
+    fn parse(entry: &str) -> Result<Self> {
+        let entry = entry.trim().trim_end_matches('.').to_ascii_lowercase();
+        let (addr, prefix) = match entry.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (entry.as_str(), None),
+        };
+
+        match addr.parse::<IpAddr>() {
+            Ok(addr) => {
+                let width = if addr.is_ipv4() { 32 } else { 128 };
+                let prefix = match prefix {
+                    Some(prefix) => prefix
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|prefix| *prefix <= width)
+                        .ok_or_else(|| anyhow!("Invalid prefix length in scope entry: {}", entry))?,
+                    None => width,
+                };
+                Ok(ScopeRule::Network { addr, prefix })
+            }
+            Err(_) if prefix.is_none() && !entry.is_empty() => Ok(ScopeRule::Host(entry)),
+            Err(_) => Err(anyhow!("Invalid scope entry: {}", entry)),
+        }
+    }
+
+    fn matches(&self, host: &str, ip: Option<IpAddr>) -> bool {
+        match (self, ip) {
+            (ScopeRule::Network { addr, prefix }, Some(ip)) => in_network(ip, *addr, *prefix),
+            (ScopeRule::Host(pattern), None) => match pattern.strip_prefix("*.") {
+                Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.')),
+                None => host == pattern,
+            },
+            _ => false,
+        }
+    }
+}
+
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_private(),
+        // Unique local addresses, fc00::/7
+        IpAddr::V6(v6) => in_network(IpAddr::V6(v6), IpAddr::V6(Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 0)), 7),
+    }
+}
+
+fn is_loopback(host: &str, ip: Option<IpAddr>) -> bool {
+    match ip {
+        Some(ip) => ip.is_loopback() || ip == IpAddr::V4(Ipv4Addr::UNSPECIFIED) || ip == IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        None => host == "localhost" || host.ends_with(".localhost"),
+    }
+}
+
+/// Allowlist plus private/loopback guards. Hostnames are resolved, and the
+/// guards and CIDR entries apply to every address they resolve to.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    rules: Vec<ScopeRule>,
+    block_private: bool,
+    block_loopback: bool,
+    dns: DnsConfig,
+}
+
+impl Scope {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let rules = config
+            .scope_allowlist
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(ScopeRule::parse)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            block_private: config.scope_block_private,
+            block_loopback: config.scope_block_loopback,
+            dns: config.dns()?,
+        })
+    }
+
+    fn is_restricted(&self) -> bool {
+        self.block_private || self.block_loopback || !self.rules.is_empty()
+    }
+
+    /// The addresses `target` may be attacked at, otherwise an error naming
+    /// the reason. Connect to these rather than resolving the host again,
+    /// which could answer differently. A hostname that can't be resolved is
+    /// out of scope unless the scope is unrestricted, which vets nothing and
+    /// returns no addresses.
+    pub async fn check(&self, target: &str) -> Result<Vec<IpAddr>> {
+        let parsed = Target::parse(target)?;
+        let host = parsed.host.trim_end_matches('.').to_ascii_lowercase();
+        let out_of_scope = |reason: String| Err(anyhow!("Target out of scope: {} ({})", target.trim(), reason));
+        if !self.is_restricted() {
+            return Ok(Vec::new());
+        }
+        if self.block_loopback && is_loopback(&host, None) {
+            return out_of_scope("loopback targets are blocked".to_string());
+        }
+
+        let addresses: Vec<IpAddr> = match net::resolve(&host, &self.dns).await {
+            Ok(addresses) => addresses.into_iter().map(|ip| ip.to_canonical()).collect(),
+            Err(e) => return out_of_scope(e.to_string()),
+        };
+
+        if self.block_loopback && addresses.iter().any(|&ip| is_loopback(&host, Some(ip))) {
+            return out_of_scope("loopback targets are blocked".to_string());
+        }
+        if self.block_private && addresses.iter().copied().any(is_private) {
+            return out_of_scope("private (RFC 1918) targets are blocked".to_string());
+        }
+        let host_allowed = self.rules.iter().any(|rule| rule.matches(&host, None));
+        let addresses_allowed = addresses.iter().all(|&ip| self.rules.iter().any(|rule| rule.matches(&host, Some(ip))));
+        if !self.rules.is_empty() && !host_allowed && !addresses_allowed {
+            return out_of_scope("not in the scope allowlist".to_string());
+        }
+        Ok(addresses)
+    }
+
+    pub async fn is_in_scope(&self, target: &str) -> bool {
+        self.check(target).await.is_ok()
+    }
+}
+
+/// Scope from the stored config, or the permissive default if there is none
+pub fn current() -> Result<Scope> {
+    Scope::from_config(&crate::db::redb_client::config::load().unwrap_or_default())
+}
+
+/// Whether `target` passes the configured scope. An unreadable scope
+/// counts as out of scope.
+pub async fn is_in_scope(target: &str) -> bool {
+    match current() {
+        Ok(scope) => scope.is_in_scope(target).await,
+        Err(_) => false,
+    }
+}
+
+/// Fail unless `target` passes the configured scope, else the addresses it
+/// was vetted at, as [`Scope::check`]. Called before every exploit run and
+/// scan.
+pub async fn enforce(target: &str) -> Result<Vec<IpAddr>> {
+    current()?.check(target).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::net::test_support::dns_mock;
+
+    /// Scope resolving through a mock nameserver, which knows a few lab
+    /// hosts: router.lab is a loopback alias
+    async fn scope(allowlist: &str, block_private: bool, block_loopback: bool) -> Scope {
+        let nameserver = dns_mock(&[
+            ("router.lab", Ipv4Addr::LOCALHOST),
+            ("cam1.iot.lab", Ipv4Addr::new(192, 168, 10, 20)),
+            ("evil-router.lab", Ipv4Addr::new(198, 51, 100, 7)),
+            ("printer.lab", Ipv4Addr::new(192, 168, 10, 30)),
+        ])
+        .await;
+        Scope::from_config(&Config {
+            scope_allowlist: allowlist.to_string(),
+            scope_block_private: block_private,
+            scope_block_loopback: block_loopback,
+            dns_nameservers: nameserver.to_string(),
+            dns_timeout_secs: 2,
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_allowlist() {
+        let scope = scope("192.168.10.0/24, 2001:db8::/32, router.lab, *.iot.lab", false, false).await;
+
+        assert!(scope.is_in_scope("192.168.10.1").await);
+        assert!(scope.is_in_scope("192.168.10.254:8080").await);
+        assert!(scope.is_in_scope("[2001:db8::1]:22").await);
+        assert!(scope.is_in_scope("Router.Lab").await);
+        assert!(scope.is_in_scope("cam1.iot.lab").await);
+        // Not named in the allowlist, but resolves into an allowed network
+        assert!(scope.is_in_scope("printer.lab").await);
+        // Connections go to the addresses that were vetted
+        assert_eq!(scope.check("printer.lab:80").await.unwrap(), vec![IpAddr::V4(Ipv4Addr::new(192, 168, 10, 30))]);
+
+        assert!(!scope.is_in_scope("192.168.11.1").await);
+        assert!(!scope.is_in_scope("8.8.8.8").await);
+        assert!(!scope.is_in_scope("evil-router.lab").await);
+        let err = scope.check("10.0.0.1").await.unwrap_err();
+        assert!(err.to_string().contains("not in the scope allowlist"));
+        // Names that don't resolve are refused, even if a rule names them
+        let err = scope.check("iot.lab").await.unwrap_err();
+        assert!(err.to_string().contains("Can't resolve iot.lab"));
+
+        // No allowlist means anything the guards allow, with nothing vetted
+        assert!(Scope::default().is_in_scope("8.8.8.8").await);
+        assert!(Scope::default().check("router.invalid").await.unwrap().is_empty());
+        assert!(Scope::from_config(&Config { scope_allowlist: "10.0.0.0/33".to_string(), ..Config::default() }).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_private_and_loopback_guards() {
+        let guarded = scope("", true, true).await;
+        assert!(!guarded.is_in_scope("10.1.2.3").await);
+        assert!(!guarded.is_in_scope("172.16.0.1").await);
+        assert!(!guarded.is_in_scope("127.0.0.1:80").await);
+        assert!(!guarded.is_in_scope("[::1]:80").await);
+        assert!(!guarded.is_in_scope("[::ffff:192.168.1.1]").await);
+        assert!(!guarded.is_in_scope("localhost").await);
+        assert!(!guarded.is_in_scope("cam1.iot.lab").await);
+        assert!(guarded.is_in_scope("203.0.113.5").await);
+        assert!(guarded.is_in_scope("evil-router.lab").await);
+
+        // Guards win over the allowlist
+        assert!(!scope("192.168.0.0/16", true, false).await.is_in_scope("192.168.1.1").await);
+        assert!(scope("192.168.0.0/16", false, true).await.is_in_scope("192.168.1.1").await);
+    }
+
+    #[tokio::test]
+    async fn test_hostname_resolving_to_loopback() {
+        let guarded = scope("router.lab", false, true).await;
+        let err = guarded.check("router.lab:8080").await.unwrap_err();
+        assert!(err.to_string().contains("loopback targets are blocked"));
+        assert!(scope("router.lab", false, false).await.is_in_scope("router.lab:8080").await);
+    }
+
+    #[tokio::test]
+    async fn test_scan_refused_out_of_scope() {
+        use crate::core::scanner::ScannerConfig;
+        use crate::db::models::ScanRequest;
+
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        crate::db::redb_client::config::set("scope_allowlist", "192.0.2.0/24").unwrap();
+        assert!(is_in_scope("192.0.2.10").await);
+        assert!(!is_in_scope("198.51.100.1").await);
+
+        let config = ScannerConfig { threads: 1, timeout: 1, rate_limit: None, precheck_timeout: 0, proxy: None, tls: Default::default(), dns: Default::default() };
+        let queue = crate::core::jobs::JobQueue::start(1, 1);
+        let err = queue.enqueue(ScanRequest::new("198.51.100.1", "http"), config).await.unwrap_err();
+        assert!(err.to_string().starts_with("Target out of scope"));
+    }
+}
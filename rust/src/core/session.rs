@@ -1,10 +1,202 @@
 // Session management
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::core::exploit::Target;
+use crate::core::net;
+use crate::db::models::{SessionRecord, SessionStatus};
+use crate::db::redb_client::sessions;
+
+/// An open shell on a compromised target
 pub struct Session {
     pub id: Uuid,
+    pub target: Target,
+    pub opened_at: DateTime<Utc>,
+    reader: Mutex<OwnedReadHalf>,
+    writer: Mutex<OwnedWriteHalf>,
+}
+
+impl Session {
+    fn new(target: Target, stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self {
+            id: Uuid::new_v4(),
+            target,
+            opened_at: Utc::now(),
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+        }
+    }
+
+    fn record(&self, status: SessionStatus) -> SessionRecord {
+        SessionRecord {
+            id: self.id,
+            target: self.target.to_string(),
+            opened_at: self.opened_at,
+            closed_at: (status != SessionStatus::Active).then(Utc::now),
+            status,
+        }
+    }
+
+    /// Write `command` followed by a newline
+    pub async fn send_command(&self, command: &str) -> Result<()> {
+        let mut writer = self.writer.lock().await;
+        writer.write_all(command.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Read whatever the shell sends until it has been quiet for `idle`
+    pub async fn read_output(&self, idle: Duration) -> Result<String> {
+        let mut reader = self.reader.lock().await;
+        let mut output = Vec::new();
+        let mut buf = [0u8; 4096];
+
+        loop {
+            match tokio::time::timeout(idle, reader.read(&mut buf)).await {
+                Ok(Ok(0)) | Err(_) => break,
+                Ok(Ok(n)) => output.extend_from_slice(&buf[..n]),
+                Ok(Err(e)) => return Err(e.into()),
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+}
+
+/// Tracks live sessions; metadata is persisted to the `sessions` table
+#[derive(Default)]
+pub struct SessionManager {
+    sessions: RwLock<HashMap<Uuid, Arc<Session>>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to a bind shell listening on `target`
+    pub async fn open(&self, target: &Target, timeout: Duration) -> Result<Arc<Session>> {
+        let port = target
+            .port
+            .ok_or_else(|| anyhow!("Target {} has no port to connect to", target))?;
+        let stream = tokio::time::timeout(timeout, net::connect(target, port))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to {}", target))??;
+
+        self.attach(target.clone(), stream)
+    }
+
+    /// Track an already connected stream, e.g. an accepted reverse shell
+    pub fn attach(&self, target: Target, stream: TcpStream) -> Result<Arc<Session>> {
+        let session = Arc::new(Session::new(target, stream));
+        sessions::insert(&session.record(SessionStatus::Active))?;
+        self.sessions.write().insert(session.id, session.clone());
+
+        tracing::info!("Session {} opened on {}", session.id, session.target);
+        Ok(session)
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<Arc<Session>> {
+        self.sessions.read().get(id).cloned()
+    }
+
+    /// All recorded sessions. Sessions recorded as active that this
+    /// manager doesn't hold (e.g. from before a restart) are reported lost.
+    pub fn list(&self) -> Result<Vec<SessionRecord>> {
+        let live = self.sessions.read();
+        let mut records = sessions::list_all()?;
+
+        for record in &mut records {
+            if record.status == SessionStatus::Active && !live.contains_key(&record.id) {
+                record.status = SessionStatus::Lost;
+            }
+        }
+        records.sort_by_key(|r| r.opened_at);
+        Ok(records)
+    }
+
+    pub async fn close(&self, id: &Uuid) -> Result<()> {
+        let session = self
+            .sessions
+            .write()
+            .remove(id)
+            .ok_or_else(|| anyhow!("No active session {}", id))?;
+
+        let _ = session.writer.lock().await.shutdown().await;
+        sessions::update(&session.record(SessionStatus::Closed))?;
+
+        tracing::info!("Session {} closed", id);
+        Ok(())
+    }
+
+    pub async fn send_command(&self, id: &Uuid, command: &str) -> Result<()> {
+        self.get(id)
+            .ok_or_else(|| anyhow!("No active session {}", id))?
+            .send_command(command)
+            .await
+    }
+
+    pub async fn read_output(&self, id: &Uuid, idle: Duration) -> Result<String> {
+        self.get(id)
+            .ok_or_else(|| anyhow!("No active session {}", id))?
+            .read_output(idle)
+            .await
+    }
 }
 
-// Session management will be added here
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn echo_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (mut reader, mut writer) = stream.split();
+            let _ = tokio::io::copy(&mut reader, &mut writer).await;
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_session_round_trip() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let port = echo_server().await;
+        let manager = SessionManager::new();
+
+        let target = Target::new("127.0.0.1").with_port(port);
+        let session = manager.open(&target, Duration::from_secs(5)).await.unwrap();
+
+        manager.send_command(&session.id, "id").await.unwrap();
+        let output = manager.read_output(&session.id, Duration::from_millis(200)).await.unwrap();
+        assert_eq!(output, "id\n");
+
+        let listed = manager.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].status, SessionStatus::Active);
+
+        // A fresh manager (as after a restart) still lists the session
+        let restarted = SessionManager::new();
+        assert_eq!(restarted.list().unwrap()[0].status, SessionStatus::Lost);
+
+        manager.close(&session.id).await.unwrap();
+        let record = sessions::get_by_id(&session.id).unwrap().unwrap();
+        assert_eq!(record.status, SessionStatus::Closed);
+        assert!(record.closed_at.is_some());
+        assert!(manager.send_command(&session.id, "id").await.is_err());
+    }
+}
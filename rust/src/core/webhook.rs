@@ -0,0 +1,341 @@
+// Webhook delivery for scan callbacks
+
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::core::net::{self, DnsConfig};
+use crate::core::scanner::ScanEvent;
+use crate::crypto::hashing::hmac::{hmac_sha256, verify_hmac_sha256};
+use crate::db::models::ScanCallbacks;
+
+/// Environment variable holding the shared signing secret
+pub const WEBHOOK_SECRET_ENV: &str = "PYRO_WEBHOOK_SECRET";
+/// `sha256=<hex HMAC-SHA256 of the request body>`
+pub const SIGNATURE_HEADER: &str = "x-pyro-signature";
+pub const EVENT_HEADER: &str = "x-pyro-event";
+
+lazy_static! {
+    static ref DISPATCHER: WebhookDispatcher = WebhookDispatcher::from_env();
+}
+
+/// Signature header value for `body`
+pub fn sign(secret: &[u8], body: &[u8]) -> Result<String> {
+    Ok(format!("sha256={}", hex::encode(hmac_sha256(secret, body)?)))
+}
+
+/// Check a signature header value produced by [`sign`]
+pub fn verify(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    signature
+        .strip_prefix("sha256=")
+        .and_then(|digest| hex::decode(digest).ok())
+        .is_some_and(|digest| verify_hmac_sha256(secret, body, &digest).unwrap_or(false))
+}
+
+/// Loopback, private (RFC 1918 and fc00::/7), link-local (which holds
+/// the cloud metadata service) and unspecified addresses
+fn is_internal(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified() || v4.is_broadcast(),
+        IpAddr::V6(v6) => {
+            let segment = v6.segments()[0];
+            v6.is_loopback() || v6.is_unspecified() || segment & 0xfe00 == 0xfc00 || segment & 0xffc0 == 0xfe80
+        }
+    }
+}
+
+/// Parse `url` and resolve its host. Unless `allow_internal`, every
+/// address must be public.
+async fn resolve_url(url: &str, allow_internal: bool) -> Result<(reqwest::Url, Vec<SocketAddr>)> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| anyhow!("Invalid callback URL {}: {}", url, e))?;
+    match parsed.scheme() {
+        "http" | "https" => {}
+        scheme => return Err(anyhow!("Unsupported callback URL scheme: {}", scheme)),
+    }
+    let host = match parsed.host_str() {
+        // IPv6 literals come bracketed
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']').to_string(),
+        None => return Err(anyhow!("Callback URL has no host: {}", url)),
+    };
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addresses = net::resolve(&host, &DnsConfig::default()).await?;
+    if !allow_internal {
+        if let Some(ip) = addresses.iter().find(|ip| is_internal(**ip)) {
+            return Err(anyhow!("Callback URL {} resolves to internal address {}", url, ip));
+        }
+    }
+    Ok((parsed, addresses.into_iter().map(|ip| SocketAddr::new(ip, port)).collect()))
+}
+
+/// Callback URLs must be absolute http(s) URLs whose host resolves only
+/// to public addresses
+pub async fn validate_url(url: &str) -> Result<()> {
+    resolve_url(url, false).await.map(|_| ())
+}
+
+/// URL registered for `event`, if any
+pub fn callback_url<'a>(callbacks: &'a ScanCallbacks, event: &ScanEvent) -> Option<&'a str> {
+    match event {
+        ScanEvent::Completed { .. } => callbacks.on_complete.as_deref(),
+        ScanEvent::VulnerabilityFound { .. } => callbacks.on_vulnerability.as_deref(),
+        ScanEvent::Error { .. } => callbacks.on_error.as_deref(),
+        _ => None,
+    }
+}
+
+/// POSTs signed JSON payloads, retrying with exponential backoff
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    secret: Option<Vec<u8>>,
+    allow_internal: bool,
+    max_attempts: u32,
+    initial_backoff: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(secret: Option<Vec<u8>>) -> Self {
+        Self {
+            secret,
+            allow_internal: false,
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+
+    /// Sign with `PYRO_WEBHOOK_SECRET`; payloads go out unsigned without it
+    pub fn from_env() -> Self {
+        let secret = std::env::var(WEBHOOK_SECRET_ENV)
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(String::into_bytes);
+        if secret.is_none() {
+            tracing::warn!("{} is not set; webhook payloads will not be signed", WEBHOOK_SECRET_ENV);
+        }
+        Self::new(secret)
+    }
+
+    pub fn with_retry(mut self, max_attempts: u32, initial_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Also deliver to loopback, private and link-local addresses, for
+    /// receivers on the local machine or network
+    pub fn allow_internal(mut self) -> Self {
+        self.allow_internal = true;
+        self
+    }
+
+    /// Client pinned to the addresses `url` was vetted against, so a second
+    /// DNS answer can't point it elsewhere. Redirects are not followed.
+    async fn client_for(&self, url: &str) -> Result<reqwest::Client> {
+        let (parsed, addresses) = resolve_url(url, self.allow_internal).await?;
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none());
+        if let Some(domain) = parsed.domain() {
+            builder = builder.resolve_to_addrs(domain, &addresses);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// POST `payload` to `url`. Connection errors and non-2xx responses,
+    /// redirects included, are retried, doubling the delay each time. URLs
+    /// resolving to internal addresses fail without a request.
+    pub async fn deliver(&self, url: &str, event: &str, payload: &Value) -> Result<()> {
+        let client = self.client_for(url).await?;
+        let body = serde_json::to_vec(payload)?;
+        let signature = self.secret.as_deref().map(|secret| sign(secret, &body)).transpose()?;
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+
+        loop {
+            let mut request = client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event)
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => anyhow!("{} responded {}", url, response.status()),
+                Err(e) => anyhow!("{} unreachable: {}", url, e),
+            };
+            if attempt >= self.max_attempts {
+                return Err(error.context(format!("Webhook {} failed after {} attempts", event, attempt)));
+            }
+
+            tracing::debug!("Webhook {} attempt {} failed: {}", event, attempt, error);
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Deliver `event` to its callback URL, if one is registered
+    pub async fn dispatch(&self, callbacks: &ScanCallbacks, event: &ScanEvent) -> Result<()> {
+        let Some(url) = callback_url(callbacks, event) else {
+            return Ok(());
+        };
+        let payload = json!({
+            "event": event,
+            "scan_id": event.scan_id(),
+            "timestamp": Utc::now(),
+        });
+        let name = payload["event"]["type"].as_str().unwrap_or("scan.event").to_string();
+        self.deliver(url, &name, &payload).await
+    }
+}
+
+/// Deliver `event` in the background using the global dispatcher
+pub fn notify(callbacks: &ScanCallbacks, event: &ScanEvent) {
+    if callback_url(callbacks, event).is_none() {
+        return;
+    }
+
+    let callbacks = callbacks.clone();
+    let event = event.clone();
+    tokio::spawn(async move {
+        if let Err(e) = DISPATCHER.dispatch(&callbacks, &event).await {
+            tracing::warn!("Scan {} callback not delivered: {:#}", event.scan_id(), e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::{HeaderMap, StatusCode}, routing::post, Router};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct Received {
+        attempts: usize,
+        deliveries: Vec<(HeaderMap, Vec<u8>)>,
+    }
+
+    /// Webhook receiver that fails the first `failures` requests with 503
+    async fn mock_server(failures: usize) -> (String, Arc<Mutex<Received>>) {
+        let received = Arc::new(Mutex::new(Received::default()));
+        let app = Router::new()
+            .route(
+                "/hooks/complete",
+                post(move |State(state): State<Arc<Mutex<Received>>>, headers: HeaderMap, body: axum::body::Bytes| async move {
+                    let mut state = state.lock();
+                    state.attempts += 1;
+                    if state.attempts <= failures {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    state.deliveries.push((headers, body.to_vec()));
+                    StatusCode::OK
+                }),
+            )
+            .with_state(received.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/hooks/complete", addr), received)
+    }
+
+    #[tokio::test]
+    async fn test_on_complete_delivered_with_signature() {
+        let (url, received) = mock_server(2).await;
+        let secret = b"webhook-secret".to_vec();
+        let dispatcher = WebhookDispatcher::new(Some(secret.clone()))
+            .with_retry(3, Duration::from_millis(10))
+            .allow_internal();
+
+        let callbacks = ScanCallbacks {
+            on_complete: Some(url),
+            ..ScanCallbacks::default()
+        };
+        let scan_id = Uuid::new_v4();
+        let event = ScanEvent::Completed { scan_id, vulnerabilities_found: 3 };
+        dispatcher.dispatch(&callbacks, &event).await.unwrap();
+
+        let received = received.lock();
+        assert_eq!(received.attempts, 3);
+        assert_eq!(received.deliveries.len(), 1);
+
+        let (headers, body) = &received.deliveries[0];
+        assert_eq!(headers[EVENT_HEADER], "scan.completed");
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        assert!(verify(&secret, body, signature));
+        assert!(!verify(b"wrong-secret", body, signature));
+
+        let payload: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["scan_id"], scan_id.to_string());
+        assert_eq!(payload["event"]["data"]["vulnerabilities_found"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_gives_up_after_max_attempts() {
+        let (url, received) = mock_server(usize::MAX).await;
+        let dispatcher = WebhookDispatcher::new(None).with_retry(2, Duration::from_millis(10)).allow_internal();
+
+        let result = dispatcher.deliver(&url, "scan.completed", &json!({})).await;
+        assert!(result.is_err());
+        assert_eq!(received.lock().attempts, 2);
+
+        // Events without a registered URL are skipped
+        let event = ScanEvent::Started { scan_id: Uuid::new_v4(), targets: 1 };
+        assert!(callback_url(&ScanCallbacks::default(), &event).is_none());
+        assert!(validate_url("/api/v1/webhooks/scan-complete").await.is_err());
+        assert!(validate_url("ftp://pyro.local/hook").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_internal_callback_urls_rejected() {
+        for url in [
+            "http://127.0.0.1/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.5:8080/hook",
+            "http://[::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[::ffff:192.168.1.1]/hook",
+            "http://localhost/hook",
+        ] {
+            assert!(validate_url(url).await.is_err(), "{}", url);
+        }
+        assert!(validate_url("https://203.0.113.10/hooks/complete").await.is_ok());
+
+        // Delivery checks too, without sending anything
+        let (url, received) = mock_server(0).await;
+        let dispatcher = WebhookDispatcher::new(None).with_retry(1, Duration::from_millis(10));
+        assert!(dispatcher.deliver(&url, "scan.completed", &json!({})).await.is_err());
+        assert_eq!(received.lock().attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_redirects_not_followed() {
+        let followed = Arc::new(Mutex::new(0));
+        let app = Router::new()
+            .route("/hooks/complete", post(|| async { axum::response::Redirect::temporary("/hooks/internal") }))
+            .route(
+                "/hooks/internal",
+                post(move |State(followed): State<Arc<Mutex<usize>>>| async move {
+                    *followed.lock() += 1;
+                    StatusCode::OK
+                }),
+            )
+            .with_state(followed.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks/complete", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dispatcher = WebhookDispatcher::new(None).with_retry(1, Duration::from_millis(10)).allow_internal();
+        let error = dispatcher.deliver(&url, "scan.completed", &json!({})).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("307"));
+        assert_eq!(*followed.lock(), 0);
+    }
+}
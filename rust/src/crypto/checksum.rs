@@ -0,0 +1,120 @@
+// Non-cryptographic checksums (CRC32/CRC16) used in firmware headers.
+// Kept out of HashAlgorithm since they offer no tamper resistance.
+
+use serde::{Deserialize, Serialize};
+
+/// CRC-16 parameters in the usual catalogue form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crc16 {
+    pub name: &'static str,
+    pub poly: u16,
+    pub init: u16,
+    /// Process bits LSB first and reflect the output
+    pub reflect: bool,
+    pub xorout: u16,
+}
+
+impl Crc16 {
+    /// CRC-16/CCITT-FALSE (U-Boot, many bootloaders)
+    pub const CCITT: Crc16 = Crc16 { name: "CRC-16/CCITT-FALSE", poly: 0x1021, init: 0xFFFF, reflect: false, xorout: 0 };
+    pub const XMODEM: Crc16 = Crc16 { name: "CRC-16/XMODEM", poly: 0x1021, init: 0, reflect: false, xorout: 0 };
+    /// CRC-16/ARC, also called CRC-16/IBM
+    pub const ARC: Crc16 = Crc16 { name: "CRC-16/ARC", poly: 0x8005, init: 0, reflect: true, xorout: 0 };
+    pub const MODBUS: Crc16 = Crc16 { name: "CRC-16/MODBUS", poly: 0x8005, init: 0xFFFF, reflect: true, xorout: 0 };
+
+    pub fn checksum(&self, data: &[u8]) -> u16 {
+        let mut crc = self.init;
+        if self.reflect {
+            let poly = self.poly.reverse_bits();
+            for &byte in data {
+                crc ^= byte as u16;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+                }
+            }
+        } else {
+            for &byte in data {
+                crc ^= (byte as u16) << 8;
+                for _ in 0..8 {
+                    crc = if crc & 0x8000 != 0 { (crc << 1) ^ self.poly } else { crc << 1 };
+                }
+            }
+        }
+        crc ^ self.xorout
+    }
+}
+
+/// CRC-32/ISO-HDLC, as used by zlib, gzip and uImage headers
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    Crc16::CCITT.checksum(data)
+}
+
+pub fn crc16(data: &[u8], params: &Crc16) -> u16 {
+    params.checksum(data)
+}
+
+/// Checksum value with its zero-padded hex form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumResult {
+    pub algorithm: String,
+    pub value: u32,
+    pub hex: String,
+}
+
+impl ChecksumResult {
+    pub fn crc32(data: &[u8]) -> Self {
+        let value = crc32(data);
+        Self {
+            algorithm: "CRC-32".to_string(),
+            value,
+            hex: format!("{:08x}", value),
+        }
+    }
+
+    pub fn crc16(data: &[u8], params: &Crc16) -> Self {
+        let value = params.checksum(data);
+        Self {
+            algorithm: params.name.to_string(),
+            value: value as u32,
+            hex: format!("{:04x}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Standard check input from the CRC catalogue
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(CHECK), 0xCBF4_3926);
+        assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+        assert_eq!(crc32(b""), 0);
+
+        let result = ChecksumResult::crc32(CHECK);
+        assert_eq!(result.hex, "cbf43926");
+    }
+
+    #[test]
+    fn test_crc16_variants() {
+        assert_eq!(crc16_ccitt(CHECK), 0x29B1);
+        assert_eq!(crc16(CHECK, &Crc16::XMODEM), 0x31C3);
+        assert_eq!(crc16(CHECK, &Crc16::ARC), 0xBB3D);
+        assert_eq!(crc16(CHECK, &Crc16::MODBUS), 0x4B37);
+        assert_eq!(ChecksumResult::crc16(CHECK, &Crc16::XMODEM).hex, "31c3");
+    }
+}
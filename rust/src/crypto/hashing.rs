@@ -1,9 +1,11 @@
 // Comprehensive Multi-Algorithm Hashing Module
-// Supports SHA-2, SHA-3, BLAKE2, BLAKE3, MD5, RIPEMD, and more
+// Supports SHA-2, SHA-3, BLAKE2, BLAKE3, MD5, SHA-1, RIPEMD, and more
 
 use anyhow::{Result, anyhow};
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine};
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 // Import all hashing algorithms
 use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256, Digest as Sha2Digest};
@@ -12,9 +14,10 @@ use sha3::{
     Shake128, Shake256,
     digest::{ExtendableOutput, Update, XofReader},
 };
-use blake2::{Blake2b512, Blake2s256, Digest as Blake2Digest};
+use blake2::{Blake2b512, Blake2bVar, Blake2s256, Blake2sVar, digest::VariableOutput};
 use blake3::Hasher as Blake3Hasher;
 use md5::Md5;
+use sha1::Sha1;
 use ripemd::Ripemd160;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -44,6 +47,9 @@ pub enum HashAlgorithm {
 
     // Legacy (for compatibility)
     MD5,
+    /// Insecure: practical collisions exist. Only for matching existing
+    /// manifests and git-style object ids, never for integrity guarantees.
+    SHA1,
     RIPEMD160,
 }
 
@@ -53,6 +59,19 @@ impl fmt::Display for HashAlgorithm {
     }
 }
 
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    /// Case-insensitive algorithm name, e.g. `sha256` or `SHA3-256`
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.trim().to_uppercase().replace('-', "_");
+        HashAlgorithm::all()
+            .into_iter()
+            .find(|algorithm| algorithm.to_string().to_uppercase() == name)
+            .ok_or_else(|| anyhow!("Unknown hash algorithm: {}", s))
+    }
+}
+
 impl HashAlgorithm {
     /// Get all available hash algorithms
     pub fn all() -> Vec<HashAlgorithm> {
@@ -73,6 +92,7 @@ impl HashAlgorithm {
             HashAlgorithm::BLAKE2s,
             HashAlgorithm::BLAKE3,
             HashAlgorithm::MD5,
+            HashAlgorithm::SHA1,
             HashAlgorithm::RIPEMD160,
         ]
     }
@@ -96,9 +116,59 @@ impl HashAlgorithm {
             HashAlgorithm::BLAKE2s => Some(32),
             HashAlgorithm::BLAKE3 => Some(32),
             HashAlgorithm::MD5 => Some(16),
+            HashAlgorithm::SHA1 => Some(20),
             HashAlgorithm::RIPEMD160 => Some(20),
         }
     }
+
+    /// Candidate algorithms for a hex or base64 digest, matched on decoded
+    /// length. Hex is assumed whenever the string is valid hex. SHAKE is
+    /// never suggested since its output length is arbitrary.
+    pub fn identify(hash: &str) -> Vec<HashAlgorithm> {
+        let hash = hash.trim();
+        let len = if !hash.is_empty() && hash.len().is_multiple_of(2) && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            hash.len() / 2
+        } else if let Ok(bytes) = STANDARD.decode(hash).or_else(|_| URL_SAFE_NO_PAD.decode(hash)) {
+            bytes.len()
+        } else {
+            return Vec::new();
+        };
+
+        HashAlgorithm::all()
+            .into_iter()
+            .filter(|algorithm| algorithm.output_size() == Some(len))
+            .collect()
+    }
+}
+
+/// Named algorithm sets for cataloguing with a consistent set of digests
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HashProfile {
+    /// SHA256, SHA512 and BLAKE3
+    Forensic,
+    /// MD5, SHA1 and RIPEMD160, for matching older databases
+    Legacy,
+}
+
+impl HashProfile {
+    pub fn algorithms(&self) -> &'static [HashAlgorithm] {
+        match self {
+            HashProfile::Forensic => &[HashAlgorithm::SHA256, HashAlgorithm::SHA512, HashAlgorithm::BLAKE3],
+            HashProfile::Legacy => &[HashAlgorithm::MD5, HashAlgorithm::SHA1, HashAlgorithm::RIPEMD160],
+        }
+    }
+}
+
+impl FromStr for HashProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "forensic" => Ok(HashProfile::Forensic),
+            "legacy" => Ok(HashProfile::Legacy),
+            other => Err(anyhow!("Unknown hash profile: {} (expected forensic or legacy)", other)),
+        }
+    }
 }
 
 /// Hash result
@@ -118,6 +188,55 @@ impl HashResult {
             hex,
         }
     }
+
+    /// Standard padded base64, as used by Subresource Integrity
+    pub fn base64(&self) -> String {
+        STANDARD.encode(&self.hash)
+    }
+
+    /// Algorithm name, with the digest size in bits when it differs from the
+    /// algorithm's fixed size, e.g. `BLAKE2b-256` from `blake2b_var`
+    pub fn label(&self) -> String {
+        match self.algorithm.output_size() {
+            Some(size) if size != self.hash.len() => format!("{}-{}", self.algorithm, self.hash.len() * 8),
+            _ => self.algorithm.to_string(),
+        }
+    }
+
+    /// URL-safe base64 without padding
+    pub fn base64url(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.hash)
+    }
+
+    pub fn encode(&self, encoding: DigestEncoding) -> String {
+        match encoding {
+            DigestEncoding::Hex => self.hex.clone(),
+            DigestEncoding::Base64 => self.base64(),
+            DigestEncoding::Base64Url => self.base64url(),
+        }
+    }
+}
+
+/// Text encoding for printing a digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestEncoding {
+    #[default]
+    Hex,
+    Base64,
+    Base64Url,
+}
+
+impl FromStr for DigestEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hex" => Ok(DigestEncoding::Hex),
+            "base64" => Ok(DigestEncoding::Base64),
+            "base64url" => Ok(DigestEncoding::Base64Url),
+            other => Err(anyhow!("Unknown encoding: {} (expected hex, base64 or base64url)", other)),
+        }
+    }
 }
 
 /// Multi-algorithm hasher
@@ -130,54 +249,54 @@ impl MultiHasher {
             // SHA-2 family
             HashAlgorithm::SHA224 => {
                 let mut hasher = Sha224::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA256 => {
                 let mut hasher = Sha256::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA384 => {
                 let mut hasher = Sha384::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA512 => {
                 let mut hasher = Sha512::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA512_224 => {
                 let mut hasher = Sha512_224::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA512_256 => {
                 let mut hasher = Sha512_256::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
 
             // SHA-3 family
             HashAlgorithm::SHA3_224 => {
                 let mut hasher = Sha3_224::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA3_256 => {
                 let mut hasher = Sha3_256::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA3_384 => {
                 let mut hasher = Sha3_384::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::SHA3_512 => {
                 let mut hasher = Sha3_512::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
 
@@ -200,12 +319,12 @@ impl MultiHasher {
             // BLAKE family
             HashAlgorithm::BLAKE2b => {
                 let mut hasher = Blake2b512::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::BLAKE2s => {
                 let mut hasher = Blake2s256::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::BLAKE3 => {
@@ -217,12 +336,17 @@ impl MultiHasher {
             // Legacy
             HashAlgorithm::MD5 => {
                 let mut hasher = Md5::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
+                hasher.finalize().to_vec()
+            }
+            HashAlgorithm::SHA1 => {
+                let mut hasher = Sha1::new();
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
             HashAlgorithm::RIPEMD160 => {
                 let mut hasher = Ripemd160::new();
-                hasher.update(data);
+                Sha2Digest::update(&mut hasher, data);
                 hasher.finalize().to_vec()
             }
         };
@@ -242,6 +366,46 @@ impl MultiHasher {
         Ok(results)
     }
 
+    /// BLAKE2b with an `out_len`-byte digest (1 to 64). Not a truncation:
+    /// the length is part of the parameter block, so outputs differ per length.
+    pub fn blake2b_var(data: &[u8], out_len: usize) -> Result<HashResult> {
+        let max = HashAlgorithm::BLAKE2b.output_size().unwrap_or(64);
+        if !(1..=max).contains(&out_len) {
+            return Err(anyhow!("BLAKE2b output length must be 1-{} bytes, got {}", max, out_len));
+        }
+        let mut hasher = Blake2bVar::new(out_len).map_err(|e| anyhow!("BLAKE2b: {}", e))?;
+        blake2::digest::Update::update(&mut hasher, data);
+        let mut output = vec![0u8; out_len];
+        hasher
+            .finalize_variable(&mut output)
+            .map_err(|e| anyhow!("BLAKE2b finalize failed: {}", e))?;
+        Ok(HashResult::new(HashAlgorithm::BLAKE2b, output))
+    }
+
+    /// BLAKE2s with an `out_len`-byte digest (1 to 32)
+    pub fn blake2s_var(data: &[u8], out_len: usize) -> Result<HashResult> {
+        let max = HashAlgorithm::BLAKE2s.output_size().unwrap_or(32);
+        if !(1..=max).contains(&out_len) {
+            return Err(anyhow!("BLAKE2s output length must be 1-{} bytes, got {}", max, out_len));
+        }
+        let mut hasher = Blake2sVar::new(out_len).map_err(|e| anyhow!("BLAKE2s: {}", e))?;
+        blake2::digest::Update::update(&mut hasher, data);
+        let mut output = vec![0u8; out_len];
+        hasher
+            .finalize_variable(&mut output)
+            .map_err(|e| anyhow!("BLAKE2s finalize failed: {}", e))?;
+        Ok(HashResult::new(HashAlgorithm::BLAKE2s, output))
+    }
+
+    /// Hash data with each algorithm in `profile`, in profile order
+    pub fn hash_profile(profile: HashProfile, data: &[u8]) -> Result<Vec<HashResult>> {
+        profile
+            .algorithms()
+            .iter()
+            .map(|&algorithm| Self::hash(algorithm, data))
+            .collect()
+    }
+
     /// Hash string with specified algorithm
     pub fn hash_string(algorithm: HashAlgorithm, s: &str) -> Result<HashResult> {
         Self::hash(algorithm, s.as_bytes())
@@ -253,6 +417,12 @@ impl MultiHasher {
         Self::hash(algorithm, &data)
     }
 
+    /// Hash a file with all algorithms, reading it once
+    pub fn hash_file_all(path: &std::path::Path) -> Result<Vec<HashResult>> {
+        let data = std::fs::read(path)?;
+        Self::hash_all(&data)
+    }
+
     /// Verify hash
     pub fn verify(algorithm: HashAlgorithm, data: &[u8], expected_hash: &str) -> Result<bool> {
         let result = Self::hash(algorithm, data)?;
@@ -263,7 +433,7 @@ impl MultiHasher {
 /// HMAC (Hash-based Message Authentication Code) support
 pub mod hmac {
     use super::*;
-    use hmac::{Hmac, Mac};
+    use ::hmac::{Hmac, Mac};
     use sha2::{Sha256, Sha512};
 
     type HmacSha256 = Hmac<Sha256>;
@@ -272,21 +442,21 @@ pub mod hmac {
     pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
         let mut mac = HmacSha256::new_from_slice(key)
             .map_err(|e| anyhow!("Invalid key length: {}", e))?;
-        mac.update(data);
+        Mac::update(&mut mac, data);
         Ok(mac.finalize().into_bytes().to_vec())
     }
 
     pub fn hmac_sha512(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
         let mut mac = HmacSha512::new_from_slice(key)
             .map_err(|e| anyhow!("Invalid key length: {}", e))?;
-        mac.update(data);
+        Mac::update(&mut mac, data);
         Ok(mac.finalize().into_bytes().to_vec())
     }
 
     pub fn verify_hmac_sha256(key: &[u8], data: &[u8], expected: &[u8]) -> Result<bool> {
         let mut mac = HmacSha256::new_from_slice(key)
             .map_err(|e| anyhow!("Invalid key length: {}", e))?;
-        mac.update(data);
+        Mac::update(&mut mac, data);
         Ok(mac.verify_slice(expected).is_ok())
     }
 }
@@ -349,6 +519,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_base64_encodings() {
+        let result = MultiHasher::hash(HashAlgorithm::SHA256, b"test").unwrap();
+        assert_eq!(result.base64(), "n4bQgYhMfWWaL+qgxVrQFaO/TxsrC4Is0V1sFbDwCgg=");
+        assert_eq!(result.base64url(), "n4bQgYhMfWWaL-qgxVrQFaO_TxsrC4Is0V1sFbDwCgg");
+        assert_eq!(result.encode("hex".parse().unwrap()), result.hex);
+        assert!("base32".parse::<DigestEncoding>().is_err());
+    }
+
+    #[test]
+    fn test_forensic_profile() {
+        let results = MultiHasher::hash_profile(HashProfile::Forensic, b"firmware").unwrap();
+        let algorithms: Vec<_> = results.iter().map(|r| r.algorithm).collect();
+        assert_eq!(algorithms, vec![HashAlgorithm::SHA256, HashAlgorithm::SHA512, HashAlgorithm::BLAKE3]);
+        assert_eq!(results[0].hex, MultiHasher::hash(HashAlgorithm::SHA256, b"firmware").unwrap().hex);
+        assert_eq!("Legacy".parse::<HashProfile>().unwrap(), HashProfile::Legacy);
+    }
+
+    #[test]
+    fn test_sha1() {
+        let result = MultiHasher::hash(HashAlgorithm::SHA1, b"test").unwrap();
+        assert_eq!(result.hex, "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3");
+        assert_eq!(result.hash.len(), HashAlgorithm::SHA1.output_size().unwrap());
+        assert_eq!("sha1".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::SHA1);
+    }
+
+    #[test]
+    fn test_identify() {
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert_eq!(
+            HashAlgorithm::identify(sha256),
+            vec![
+                HashAlgorithm::SHA256,
+                HashAlgorithm::SHA512_256,
+                HashAlgorithm::SHA3_256,
+                HashAlgorithm::BLAKE2s,
+                HashAlgorithm::BLAKE3,
+            ]
+        );
+        assert_eq!(HashAlgorithm::identify("098f6bcd4621d373cade4e832627b4f6"), vec![HashAlgorithm::MD5]);
+        assert_eq!(
+            HashAlgorithm::identify("n4bQgYhMfWWaL+qgxVrQFaO/TxsrC4Is0V1sFbDwCgg=").len(),
+            HashAlgorithm::identify(sha256).len()
+        );
+        assert!(HashAlgorithm::identify("not a hash!").is_empty());
+    }
+
+    #[test]
+    fn test_blake2_variable_output() {
+        let short = MultiHasher::blake2b_var(b"abc", 32).unwrap();
+        assert_eq!(short.hex, "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319");
+
+        let full = MultiHasher::blake2b_var(b"abc", 64).unwrap();
+        assert_eq!(full.hash.len(), 64);
+        assert_eq!(full.hex, MultiHasher::hash(HashAlgorithm::BLAKE2b, b"abc").unwrap().hex);
+        assert_eq!(short.label(), "BLAKE2b-256");
+        assert_eq!(full.label(), "BLAKE2b");
+
+        let blake2s = MultiHasher::blake2s_var(b"abc", 16).unwrap();
+        assert_eq!(blake2s.hex, "aa4938119b1dc7b87cbad0ffd200d0ae");
+        assert_eq!(blake2s.label(), "BLAKE2s-128");
+        assert!(MultiHasher::blake2b_var(b"abc", 65).is_err());
+        assert!(MultiHasher::blake2s_var(b"abc", 33).is_err());
+        assert!(MultiHasher::blake2b_var(b"abc", 0).is_err());
+    }
+
     #[test]
     fn test_blake3() {
         let data = b"test";
@@ -356,6 +592,24 @@ mod tests {
         assert_eq!(result.hash.len(), 32);
     }
 
+    #[test]
+    fn test_hash_file() {
+        let path = std::env::temp_dir().join(format!("pyroutersploit-hash-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"test").unwrap();
+
+        let result = MultiHasher::hash_file(HashAlgorithm::SHA256, &path).unwrap();
+        assert_eq!(
+            result.hex,
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        );
+        let all = MultiHasher::hash_file_all(&path).unwrap();
+        assert_eq!(all.len(), HashAlgorithm::all().len());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!("sha3-256".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::SHA3_256);
+        assert!("whirlpool".parse::<HashAlgorithm>().is_err());
+    }
+
     #[test]
     fn test_verify() {
         let data = b"test";
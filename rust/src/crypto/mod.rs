@@ -1,12 +1,24 @@
 pub mod hashing;
 pub mod qkd;
 pub mod pqc;
+pub mod checksum;
 
-pub use hashing::{HashAlgorithm, HashResult, MultiHasher};
+pub use hashing::{DigestEncoding, HashAlgorithm, HashProfile, HashResult, MultiHasher};
+pub use checksum::{crc16, crc16_ccitt, crc32, ChecksumResult, Crc16};
 pub use qkd::{QKDProtocol, QKDKeyGenerator, QKDEncryption};
 
 use anyhow::Result;
 
+/// Known-answer check that the hash backend works, cheap enough for health checks
+pub fn self_test() -> Result<()> {
+    let result = MultiHasher::hash(HashAlgorithm::SHA256, b"test")?;
+    anyhow::ensure!(
+        result.hex == "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+        "SHA256 known-answer test failed"
+    );
+    Ok(())
+}
+
 /// Initialize the crypto subsystem
 pub fn init() -> Result<()> {
     tracing::info!("Crypto subsystem initialized");
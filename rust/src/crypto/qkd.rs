@@ -4,7 +4,10 @@ use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::Utc;
-use rand::Rng;
+use rand::{CryptoRng, Rng, RngCore};
+use std::io::{Read, Write};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+use chacha20poly1305::Nonce;
 
 use crate::db::models::QKDSession;
 use crate::crypto::hashing::{HashAlgorithm, MultiHasher};
@@ -47,7 +50,11 @@ impl BB84Simulator {
 
     /// Alice prepares quantum states
     pub fn alice_prepare(&self) -> (Vec<QuantumBit>, Vec<QuantumBasis>) {
-        let mut rng = rand::thread_rng();
+        self.alice_prepare_with_rng(&mut rand::thread_rng())
+    }
+
+    /// `alice_prepare` with a caller-supplied RNG, e.g. a seeded one in tests
+    pub fn alice_prepare_with_rng<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> (Vec<QuantumBit>, Vec<QuantumBasis>) {
         let mut bits = Vec::new();
         let mut bases = Vec::new();
 
@@ -68,7 +75,15 @@ impl BB84Simulator {
 
     /// Bob measures quantum states
     pub fn bob_measure(&self, alice_bits: &[QuantumBit]) -> (Vec<bool>, Vec<QuantumBasis>) {
-        let mut rng = rand::thread_rng();
+        self.bob_measure_with_rng(alice_bits, &mut rand::thread_rng())
+    }
+
+    /// `bob_measure` with a caller-supplied RNG
+    pub fn bob_measure_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        alice_bits: &[QuantumBit],
+        rng: &mut R,
+    ) -> (Vec<bool>, Vec<QuantumBasis>) {
         let mut measured_values = Vec::new();
         let mut bob_bases = Vec::new();
 
@@ -150,14 +165,19 @@ pub struct QKDKeyGenerator;
 impl QKDKeyGenerator {
     /// Generate a quantum-safe key using BB84 protocol simulation
     pub fn generate_bb84_key(key_size: usize) -> Result<Vec<u8>> {
+        Self::generate_bb84_key_with_rng(key_size, &mut rand::thread_rng())
+    }
+
+    /// `generate_bb84_key` with a caller-supplied RNG
+    pub fn generate_bb84_key_with_rng<R: RngCore + CryptoRng + ?Sized>(key_size: usize, rng: &mut R) -> Result<Vec<u8>> {
         // Generate extra bits to account for basis mismatch
         let simulator = BB84Simulator::new(key_size * 16);
 
         // Alice prepares
-        let (alice_bits, alice_bases) = simulator.alice_prepare();
+        let (alice_bits, alice_bases) = simulator.alice_prepare_with_rng(rng);
 
         // Bob measures
-        let (bob_values, bob_bases) = simulator.bob_measure(&alice_bits);
+        let (bob_values, bob_bases) = simulator.bob_measure_with_rng(&alice_bits, rng);
 
         // Sift key
         let sifted_key = BB84Simulator::sift_key(&alice_bases, &bob_bases, &bob_values);
@@ -168,10 +188,14 @@ impl QKDKeyGenerator {
 
     /// Generate hybrid classical-quantum key
     pub fn generate_hybrid_key(key_size: usize) -> Result<Vec<u8>> {
-        let qkd_key = Self::generate_bb84_key(key_size)?;
+        Self::generate_hybrid_key_with_rng(key_size, &mut rand::thread_rng())
+    }
+
+    /// `generate_hybrid_key` with a caller-supplied RNG
+    pub fn generate_hybrid_key_with_rng<R: RngCore + CryptoRng + ?Sized>(key_size: usize, rng: &mut R) -> Result<Vec<u8>> {
+        let qkd_key = Self::generate_bb84_key_with_rng(key_size, rng)?;
 
         // Mix with classical entropy
-        let mut rng = rand::thread_rng();
         let classical_entropy: Vec<u8> = (0..key_size).map(|_| rng.gen()).collect();
 
         // XOR combine
@@ -187,59 +211,129 @@ impl QKDKeyGenerator {
     }
 }
 
-/// QKD Encryption Engine
+/// Plaintext bytes per segment in `encrypt_stream`
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// Random per-stream nonce prefix; the rest of the nonce is a 32-bit
+/// segment counter and a last-segment flag
+const STREAM_NONCE_PREFIX_SIZE: usize = 7;
+const STREAM_TAG_SIZE: usize = 16;
+
+fn stream_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..STREAM_NONCE_PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_SIZE..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+/// Fill `buf` unless the reader hits EOF first; returns the bytes read
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// QKD Encryption Engine. Key material is wiped on drop.
+#[derive(Zeroize, ZeroizeOnDrop)]
 pub struct QKDEncryption {
+    #[zeroize(skip)]
     session_id: Uuid,
     key_material: Vec<u8>,
+    #[zeroize(skip)]
+    previous_session_id: Option<Uuid>,
 }
 
 impl QKDEncryption {
     /// Create new QKD encryption session
     pub fn new_session(key_size: usize) -> Result<Self> {
-        let key_material = QKDKeyGenerator::generate_hybrid_key(key_size)?;
+        Self::new_session_with_rng(key_size, &mut rand::thread_rng())
+    }
+
+    /// `new_session` with a caller-supplied RNG for the key material
+    pub fn new_session_with_rng<R: RngCore + CryptoRng + ?Sized>(key_size: usize, rng: &mut R) -> Result<Self> {
+        let key_material = QKDKeyGenerator::generate_hybrid_key_with_rng(key_size, rng)?;
 
         Ok(Self {
             session_id: Uuid::new_v4(),
             key_material,
+            previous_session_id: None,
         })
     }
 
+    /// Session identifier used to persist and reload the key
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Session this one replaced through `rotate_key`
+    pub fn previous_session_id(&self) -> Option<Uuid> {
+        self.previous_session_id
+    }
+
+    /// Re-key before expiry: save a new session with fresh key material,
+    /// linked back to this one. This session is left untouched, so data
+    /// encrypted under it still decrypts with it.
+    pub fn rotate_key(&self) -> Result<QKDEncryption> {
+        let rotated = Self {
+            session_id: Uuid::new_v4(),
+            key_material: QKDKeyGenerator::generate_hybrid_key(self.key_material.len())?,
+            previous_session_id: Some(self.session_id),
+        };
+        rotated.save_session()?;
+        Ok(rotated)
+    }
+
     /// Encrypt data with quantum-derived key
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
-        // Use ChaCha20-Poly1305 (quantum-resistant symmetric cipher)
-        use chacha20poly1305::{
-            aead::{Aead, KeyInit, OsRng},
-            ChaCha20Poly1305, Nonce,
-        };
+        self.encrypt_with_aad(plaintext, &[])
+    }
 
-        // Derive key from QKD material
-        let key_bytes = if self.key_material.len() >= 32 {
-            &self.key_material[..32]
-        } else {
-            // Hash to get 32 bytes
-            let hash = MultiHasher::hash(HashAlgorithm::SHA256, &self.key_material)?;
-            return self.encrypt_with_key(&hash.hash, plaintext);
-        };
+    /// Encrypt and bind `aad` (e.g. target and session id) into the tag.
+    /// The AAD is not stored; `decrypt_with_aad` must be given the same bytes.
+    pub fn encrypt_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        self.encrypt_with_rng(plaintext, aad, &mut rand::thread_rng())
+    }
+
+    /// `encrypt_with_aad` drawing the nonce from a caller-supplied RNG.
+    /// Never reuse a seeded RNG across sessions outside of tests.
+    pub fn encrypt_with_rng<R: RngCore + CryptoRng + ?Sized>(&self, plaintext: &[u8], aad: &[u8], rng: &mut R) -> Result<Vec<u8>> {
+        // Use ChaCha20-Poly1305 (quantum-resistant symmetric cipher)
+        self.encrypt_with_key(&self.cipher_key()?, plaintext, aad, rng)
+    }
 
-        self.encrypt_with_key(key_bytes, plaintext)
+    /// Cipher key from the QKD material: its first 32 bytes, or its SHA-256
+    /// when shorter. Wiped when dropped, like the material itself.
+    fn cipher_key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        if self.key_material.len() >= 32 {
+            return Ok(Zeroizing::new(self.key_material[..32].to_vec()));
+        }
+        let mut hash = MultiHasher::hash(HashAlgorithm::SHA256, &self.key_material)?;
+        hash.hex.zeroize();
+        Ok(Zeroizing::new(std::mem::take(&mut hash.hash)))
     }
 
-    fn encrypt_with_key(&self, key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    fn encrypt_with_key<R: RngCore + CryptoRng + ?Sized>(&self, key: &[u8], plaintext: &[u8], aad: &[u8], rng: &mut R) -> Result<Vec<u8>> {
         use chacha20poly1305::{
-            aead::{Aead, KeyInit},
-            ChaCha20Poly1305, Nonce,
+            aead::{Aead, KeyInit, Payload},
+            ChaCha20Poly1305,
         };
 
         let cipher = ChaCha20Poly1305::new_from_slice(key)
             .map_err(|e| anyhow!("Key error: {}", e))?;
 
         // Generate random nonce
-        let mut rng = rand::thread_rng();
         let nonce_bytes: [u8; 12] = rng.gen();
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         let ciphertext = cipher
-            .encrypt(nonce, plaintext)
+            .encrypt(nonce, Payload { msg: plaintext, aad })
             .map_err(|e| anyhow!("Encryption failed: {}", e))?;
 
         // Prepend nonce to ciphertext
@@ -251,11 +345,11 @@ impl QKDEncryption {
 
     /// Decrypt data with quantum-derived key
     pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
-        use chacha20poly1305::{
-            aead::{Aead, KeyInit},
-            ChaCha20Poly1305, Nonce,
-        };
+        self.decrypt_with_aad(ciphertext, &[])
+    }
 
+    /// Decrypt data from `encrypt_with_aad`; fails if `aad` differs
+    pub fn decrypt_with_aad(&self, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
         if ciphertext.len() < 12 {
             return Err(anyhow!("Ciphertext too short"));
         }
@@ -264,20 +358,12 @@ impl QKDEncryption {
         let (nonce_bytes, encrypted_data) = ciphertext.split_at(12);
         let nonce = Nonce::from_slice(nonce_bytes);
 
-        // Derive key
-        let key_bytes = if self.key_material.len() >= 32 {
-            &self.key_material[..32]
-        } else {
-            let hash = MultiHasher::hash(HashAlgorithm::SHA256, &self.key_material)?;
-            return self.decrypt_with_key(&hash.hash, encrypted_data, nonce);
-        };
-
-        self.decrypt_with_key(key_bytes, encrypted_data, nonce)
+        self.decrypt_with_key(&self.cipher_key()?, encrypted_data, aad, nonce)
     }
 
-    fn decrypt_with_key(&self, key: &[u8], ciphertext: &[u8], nonce: &Nonce) -> Result<Vec<u8>> {
+    fn decrypt_with_key(&self, key: &[u8], ciphertext: &[u8], aad: &[u8], nonce: &Nonce) -> Result<Vec<u8>> {
         use chacha20poly1305::{
-            aead::{Aead, KeyInit},
+            aead::{Aead, KeyInit, Payload},
             ChaCha20Poly1305,
         };
 
@@ -285,12 +371,115 @@ impl QKDEncryption {
             .map_err(|e| anyhow!("Key error: {}", e))?;
 
         let plaintext = cipher
-            .decrypt(nonce, ciphertext)
+            .decrypt(nonce, Payload { msg: ciphertext, aad })
             .map_err(|e| anyhow!("Decryption failed: {}", e))?;
 
         Ok(plaintext)
     }
 
+    fn stream_cipher(&self) -> Result<chacha20poly1305::ChaCha20Poly1305> {
+        use chacha20poly1305::{aead::KeyInit, ChaCha20Poly1305};
+
+        ChaCha20Poly1305::new_from_slice(&self.cipher_key()?).map_err(|e| anyhow!("Key error: {}", e))
+    }
+
+    /// Encrypt `reader` to `writer` in `STREAM_CHUNK_SIZE` segments without
+    /// buffering the whole input. Each segment gets its own nonce and tag, and
+    /// the final one is marked so truncation is detected. Returns the number
+    /// of plaintext bytes encrypted.
+    pub fn encrypt_stream<R: Read, W: Write>(&self, reader: R, writer: W) -> Result<u64> {
+        self.encrypt_stream_with_rng(reader, writer, &mut rand::thread_rng())
+    }
+
+    /// `encrypt_stream` drawing the nonce prefix from a caller-supplied RNG
+    pub fn encrypt_stream_with_rng<R: Read, W: Write, G: RngCore + CryptoRng + ?Sized>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+        rng: &mut G,
+    ) -> Result<u64> {
+        use chacha20poly1305::aead::Aead;
+
+        let cipher = self.stream_cipher()?;
+        let mut prefix = [0u8; STREAM_NONCE_PREFIX_SIZE];
+        rng.fill_bytes(&mut prefix);
+        writer.write_all(&prefix)?;
+
+        let mut current = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut next = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut len = read_full(&mut reader, &mut current)?;
+        let mut total = 0u64;
+        let mut counter = 0u32;
+
+        loop {
+            // Read ahead so the last segment is known before it is sealed
+            let next_len = if len == STREAM_CHUNK_SIZE { read_full(&mut reader, &mut next)? } else { 0 };
+            let last = next_len == 0;
+
+            let nonce = stream_nonce(&prefix, counter, last);
+            let sealed = cipher
+                .encrypt((&nonce).into(), &current[..len])
+                .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+            writer.write_all(&sealed)?;
+            total += len as u64;
+
+            if last {
+                break;
+            }
+            std::mem::swap(&mut current, &mut next);
+            len = next_len;
+            counter = counter.checked_add(1).ok_or_else(|| anyhow!("Stream too long"))?;
+        }
+
+        writer.flush()?;
+        Ok(total)
+    }
+
+    /// Decrypt output of `encrypt_stream`. Segments are written as soon as
+    /// they authenticate, so on error `writer` may hold a verified prefix of
+    /// the plaintext. Returns the number of plaintext bytes written.
+    pub fn decrypt_stream<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> Result<u64> {
+        use chacha20poly1305::aead::Aead;
+
+        let cipher = self.stream_cipher()?;
+        let mut prefix = [0u8; STREAM_NONCE_PREFIX_SIZE];
+        if read_full(&mut reader, &mut prefix)? < STREAM_NONCE_PREFIX_SIZE {
+            return Err(anyhow!("Ciphertext too short"));
+        }
+
+        let segment_size = STREAM_CHUNK_SIZE + STREAM_TAG_SIZE;
+        let mut current = vec![0u8; segment_size];
+        let mut next = vec![0u8; segment_size];
+        let mut len = read_full(&mut reader, &mut current)?;
+        let mut total = 0u64;
+        let mut counter = 0u32;
+
+        loop {
+            if len < STREAM_TAG_SIZE {
+                return Err(anyhow!("Stream truncated at segment {}", counter));
+            }
+            let next_len = if len == segment_size { read_full(&mut reader, &mut next)? } else { 0 };
+            let last = next_len == 0;
+
+            let nonce = stream_nonce(&prefix, counter, last);
+            let plaintext = cipher
+                .decrypt((&nonce).into(), &current[..len])
+                .map_err(|_| anyhow!("Decryption failed at segment {}", counter))?;
+            writer.write_all(&plaintext)?;
+            total += plaintext.len() as u64;
+
+            if last {
+                break;
+            }
+            std::mem::swap(&mut current, &mut next);
+            len = next_len;
+            counter = counter.checked_add(1).ok_or_else(|| anyhow!("Stream too long"))?;
+        }
+
+        writer.flush()?;
+        Ok(total)
+    }
+
     /// Save session to database
     pub fn save_session(&self) -> Result<()> {
         let session = QKDSession {
@@ -299,6 +488,7 @@ impl QKDEncryption {
             key_material: self.key_material.clone(),
             created_at: Utc::now(),
             expires_at: Some(Utc::now() + chrono::Duration::hours(24)),
+            previous_session_id: self.previous_session_id,
         };
 
         crate::db::redb_client::qkd_sessions::insert(&session)?;
@@ -307,27 +497,36 @@ impl QKDEncryption {
 
     /// Load session from database
     pub fn load_session(session_id: &Uuid) -> Result<Self> {
-        let session = crate::db::redb_client::qkd_sessions::get_by_id(session_id)?
+        let mut session = crate::db::redb_client::qkd_sessions::get_by_id(session_id)?
             .ok_or_else(|| anyhow!("Session not found"))?;
 
+        if let Some(expires_at) = session.expires_at {
+            if expires_at <= Utc::now() {
+                return Err(anyhow!("Session expired at {}", expires_at));
+            }
+        }
+
         Ok(Self {
             session_id: session.id,
-            key_material: session.key_material,
+            key_material: std::mem::take(&mut session.key_material),
+            previous_session_id: session.previous_session_id,
         })
     }
 }
 
 /// Post-Quantum Cryptography wrapper
 pub mod pqc {
-    use super::*;
-
     /// Kyber key encapsulation
     pub mod kyber {
         use pqcrypto_kyber::kyber1024::*;
         use pqcrypto_traits::kem::{PublicKey, SecretKey, SharedSecret, Ciphertext};
         use anyhow::Result;
+        use zeroize::{ZeroizeOnDrop, Zeroizing};
 
+        /// Secret key is wiped on drop
+        #[derive(ZeroizeOnDrop)]
         pub struct KyberKEM {
+            #[zeroize(skip)]
             public_key: Vec<u8>,
             secret_key: Vec<u8>,
         }
@@ -341,20 +540,22 @@ pub mod pqc {
                 }
             }
 
-            pub fn encapsulate(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+            /// Shared secret and the ciphertext carrying it. The secret is wiped on drop.
+            pub fn encapsulate(&self) -> Result<(Zeroizing<Vec<u8>>, Vec<u8>)> {
                 let pk = PublicKey::from_bytes(&self.public_key)
                     .map_err(|e| anyhow::anyhow!("Invalid public key: {:?}", e))?;
                 let (ss, ct) = encapsulate(&pk);
-                Ok((ss.as_bytes().to_vec(), ct.as_bytes().to_vec()))
+                Ok((Zeroizing::new(ss.as_bytes().to_vec()), ct.as_bytes().to_vec()))
             }
 
-            pub fn decapsulate(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+            /// Shared secret from `ciphertext`, wiped on drop
+            pub fn decapsulate(&self, ciphertext: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
                 let sk = SecretKey::from_bytes(&self.secret_key)
                     .map_err(|e| anyhow::anyhow!("Invalid secret key: {:?}", e))?;
                 let ct = Ciphertext::from_bytes(ciphertext)
                     .map_err(|e| anyhow::anyhow!("Invalid ciphertext: {:?}", e))?;
                 let ss = decapsulate(&ct, &sk);
-                Ok(ss.as_bytes().to_vec())
+                Ok(Zeroizing::new(ss.as_bytes().to_vec()))
             }
         }
     }
@@ -362,10 +563,14 @@ pub mod pqc {
     /// Dilithium digital signatures
     pub mod dilithium {
         use pqcrypto_dilithium::dilithium5::*;
-        use pqcrypto_traits::sign::{PublicKey, SecretKey, SignedMessage, DetachedSignature};
+        use pqcrypto_traits::sign::{PublicKey, SecretKey, SignedMessage};
         use anyhow::Result;
+        use zeroize::ZeroizeOnDrop;
 
+        /// Secret key is wiped on drop
+        #[derive(ZeroizeOnDrop)]
         pub struct DilithiumSignature {
+            #[zeroize(skip)]
             public_key: Vec<u8>,
             secret_key: Vec<u8>,
         }
@@ -420,6 +625,96 @@ mod tests {
         assert_eq!(plaintext.to_vec(), decrypted);
     }
 
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let a = QKDEncryption::new_session_with_rng(32, &mut StdRng::seed_from_u64(2024)).unwrap();
+        let b = QKDEncryption::new_session_with_rng(32, &mut StdRng::seed_from_u64(2024)).unwrap();
+        assert_eq!(a.key_material, b.key_material);
+
+        let c = QKDEncryption::new_session_with_rng(32, &mut StdRng::seed_from_u64(2025)).unwrap();
+        assert_ne!(a.key_material, c.key_material);
+
+        let first = a.encrypt_with_rng(b"payload", b"", &mut StdRng::seed_from_u64(7)).unwrap();
+        let second = b.encrypt_with_rng(b"payload", b"", &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(b.decrypt(&first).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn test_secret_types_zeroize_on_drop() {
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<QKDEncryption>();
+        assert_zeroize_on_drop::<QKDSession>();
+        assert_zeroize_on_drop::<pqc::kyber::KyberKEM>();
+        assert_zeroize_on_drop::<pqc::dilithium::DilithiumSignature>();
+
+        let mut qkd = QKDEncryption::new_session(32).unwrap();
+        let session_id = qkd.session_id();
+        qkd.zeroize();
+        assert!(qkd.key_material.is_empty());
+        assert_eq!(qkd.session_id(), session_id);
+    }
+
+    #[test]
+    fn test_aad_mismatch_fails() {
+        let qkd = QKDEncryption::new_session(32).unwrap();
+        let ciphertext = qkd.encrypt_with_aad(b"admin:admin", b"target=192.168.1.1").unwrap();
+
+        assert_eq!(qkd.decrypt_with_aad(&ciphertext, b"target=192.168.1.1").unwrap(), b"admin:admin");
+        assert!(qkd.decrypt_with_aad(&ciphertext, b"target=192.168.1.2").is_err());
+        assert!(qkd.decrypt(&ciphertext).is_err());
+
+        // No AAD is the same as empty AAD
+        let plain = qkd.encrypt(b"admin:admin").unwrap();
+        assert_eq!(qkd.decrypt_with_aad(&plain, b"").unwrap(), b"admin:admin");
+    }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let qkd = QKDEncryption::new_session(32).unwrap();
+        let firmware: Vec<u8> = (0..STREAM_CHUNK_SIZE * 3 + 1234).map(|i| (i % 251) as u8).collect();
+
+        let mut sealed = Vec::new();
+        let written = qkd.encrypt_stream(firmware.as_slice(), &mut sealed).unwrap();
+        assert_eq!(written, firmware.len() as u64);
+        assert_eq!(sealed.len(), STREAM_NONCE_PREFIX_SIZE + firmware.len() + 4 * STREAM_TAG_SIZE);
+
+        let mut opened = Vec::new();
+        qkd.decrypt_stream(sealed.as_slice(), &mut opened).unwrap();
+        assert_eq!(opened, firmware);
+
+        // Dropping whole trailing segments is caught by the last-segment flag
+        let truncated = &sealed[..STREAM_NONCE_PREFIX_SIZE + 2 * (STREAM_CHUNK_SIZE + STREAM_TAG_SIZE)];
+        assert!(qkd.decrypt_stream(truncated, &mut Vec::new()).is_err());
+
+        let mut empty = Vec::new();
+        qkd.encrypt_stream(&b""[..], &mut empty).unwrap();
+        let mut opened = Vec::new();
+        qkd.decrypt_stream(empty.as_slice(), &mut opened).unwrap();
+        assert!(opened.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let qkd = QKDEncryption::new_session(32).unwrap();
+        qkd.save_session().unwrap();
+        let ciphertext = qkd.encrypt(b"pre-rotation").unwrap();
+
+        let rotated = qkd.rotate_key().unwrap();
+        assert_ne!(rotated.session_id(), qkd.session_id());
+        assert_ne!(rotated.key_material, qkd.key_material);
+        assert!(rotated.decrypt(&ciphertext).is_err());
+
+        let reloaded = QKDEncryption::load_session(&rotated.session_id()).unwrap();
+        assert_eq!(reloaded.previous_session_id(), Some(qkd.session_id()));
+
+        let old = QKDEncryption::load_session(&qkd.session_id()).unwrap();
+        assert_eq!(old.decrypt(&ciphertext).unwrap(), b"pre-rotation");
+    }
+
     #[test]
     fn test_kyber_kem() {
         let kem = pqc::kyber::KyberKEM::generate();
@@ -1,9 +1,15 @@
 // Cryptex Dictionary - Function to Branding Name Mapping System
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use crate::db::models::{CryptexEntry, CryptexCategory};
+use crate::db::models::{CryptexEntry, CryptexCategory, ExploitMetadata};
 use crate::db::redb_client::cryptex as db;
+use crate::db::redb_client::exploits;
+
+/// Minimum score `fuzzy_search` callers usually want; about one typo in a
+/// short word
+pub const DEFAULT_FUZZY_THRESHOLD: f64 = 0.75;
 
 /// Cryptex Dictionary Manager
 pub struct CryptexDictionary;
@@ -36,6 +42,19 @@ impl CryptexDictionary {
         rust_impl: Option<String>,
         python_impl: Option<String>,
     ) -> Result<CryptexEntry> {
+        let entry = Self::entry_with_impl(function_name, branding_name, pseudo_code, category, rust_impl, python_impl);
+        db::insert(&entry)?;
+        Ok(entry)
+    }
+
+    fn entry_with_impl(
+        function_name: impl Into<String>,
+        branding_name: impl Into<String>,
+        pseudo_code: impl Into<String>,
+        category: CryptexCategory,
+        rust_impl: Option<String>,
+        python_impl: Option<String>,
+    ) -> CryptexEntry {
         let mut entry = CryptexEntry::new(
             function_name.into(),
             branding_name.into(),
@@ -45,7 +64,14 @@ impl CryptexDictionary {
 
         entry.rust_impl = rust_impl;
         entry.python_impl = python_impl;
+        entry
+    }
 
+    /// Replace an entry's tags
+    pub fn set_tags(id: &Uuid, tags: impl IntoIterator<Item = impl AsRef<str>>) -> Result<CryptexEntry> {
+        let mut entry = db::get_by_id(id)?.ok_or_else(|| anyhow!("Cryptex entry not found: {}", id))?;
+        entry.set_tags(tags);
+        entry.updated_at = Utc::now();
         db::insert(&entry)?;
         Ok(entry)
     }
@@ -60,11 +86,42 @@ impl CryptexDictionary {
         db::get_by_branding_name(branding_name)
     }
 
-    /// Search cryptex
+    /// Follow a branding name to the exploit its entry implements
+    pub fn resolve_exploit(branding_name: &str) -> Result<Option<ExploitMetadata>> {
+        match Self::lookup_branding(branding_name)? {
+            Some(entry) => exploits::find_by_cryptex_id(&entry.id),
+            None => Ok(None),
+        }
+    }
+
+    /// Search cryptex by case-insensitive substring
     pub fn search(query: &str) -> Result<Vec<CryptexEntry>> {
         db::search(query)
     }
 
+    /// Search function and branding names allowing typos. Entries scoring at
+    /// least `threshold` (0.0 to 1.0, where 1.0 is a substring match) are
+    /// returned best first.
+    pub fn fuzzy_search(query: &str, threshold: f64) -> Result<Vec<(CryptexEntry, f64)>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut matches: Vec<(CryptexEntry, f64)> = db::list_all()?
+            .into_iter()
+            .map(|entry| {
+                let score = name_score(&query, &entry.function_name).max(name_score(&query, &entry.branding_name));
+                (entry, score)
+            })
+            .filter(|(_, score)| *score >= threshold)
+            .collect();
+        matches.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| a.function_name.cmp(&b.function_name))
+        });
+        Ok(matches)
+    }
+
     /// List all entries
     pub fn list_all() -> Result<Vec<CryptexEntry>> {
         db::list_all()
@@ -76,143 +133,207 @@ impl CryptexDictionary {
         Ok(all.into_iter().filter(|e| e.category == category).collect())
     }
 
-    /// Delete entry
-    pub fn delete(id: &Uuid) -> Result<()> {
+    /// Entries changed after `since`, oldest change first
+    pub fn list_updated_after(since: DateTime<Utc>) -> Result<Vec<CryptexEntry>> {
+        db::list_updated_after(since)
+    }
+
+    /// Entries carrying `tag`, compared case-insensitively
+    pub fn list_by_tag(tag: &str) -> Result<Vec<CryptexEntry>> {
+        let all = db::list_all()?;
+        Ok(all.into_iter().filter(|e| e.has_tag(tag)).collect())
+    }
+
+    /// Soft delete: the entry leaves listings, searches and name lookups but
+    /// `get_by_id` and exploit links still find it. False if there was
+    /// nothing to delete.
+    pub fn delete(id: &Uuid) -> Result<bool> {
         db::delete(id)
     }
 
+    /// Bring back a soft-deleted entry. False if it wasn't deleted.
+    pub fn restore(id: &Uuid) -> Result<bool> {
+        db::restore(id)
+    }
+
+    /// Soft-deleted entries
+    pub fn list_deleted() -> Result<Vec<CryptexEntry>> {
+        db::list_deleted()
+    }
+
+    /// Permanently remove an entry
+    pub fn purge(id: &Uuid) -> Result<()> {
+        db::purge(id)
+    }
+
     /// Populate default pyroutersploit entries
     pub fn populate_defaults() -> Result<()> {
         tracing::info!("Populating default cryptex entries...");
 
-        // Exploits
-        Self::add_entry_with_impl(
-            "exploit_dlink_rce_hnap",
-            "pyroutersploit_dlink_hnap_pwn",
-            "Execute remote code on D-Link routers via HNAP vulnerability",
-            CryptexCategory::Exploit,
-            Some("crate::core::exploit::dlink::hnap_rce".to_string()),
-            Some("routersploit.modules.exploits.routers.dlink.hnap_login".to_string()),
-        )?;
-
-        Self::add_entry_with_impl(
-            "exploit_linksys_wrt54gl_rce",
-            "pyroutersploit_linksys_wrt54gl_exec",
-            "Remote command execution on Linksys WRT54GL routers",
-            CryptexCategory::Exploit,
-            None,
-            Some("routersploit.modules.exploits.routers.linksys.wrt54gl_exec".to_string()),
-        )?;
-
-        Self::add_entry_with_impl(
-            "exploit_netgear_setup_rce",
-            "pyroutersploit_netgear_unauth_exec",
-            "Unauthenticated remote code execution on Netgear routers",
-            CryptexCategory::Exploit,
-            None,
-            Some("routersploit.modules.exploits.routers.netgear.multi_rce".to_string()),
-        )?;
-
-        // Scanners
-        Self::add_entry_with_impl(
-            "scanner_router_autopwn",
-            "pyroutersploit_autopwn_scanner",
-            "Automated vulnerability scanner for routers across all protocols",
-            CryptexCategory::Scanner,
-            Some("crate::core::scanner::autopwn".to_string()),
-            Some("routersploit.modules.scanners.autopwn".to_string()),
-        )?;
-
-        Self::add_entry_with_impl(
-            "scanner_camera_vuln",
-            "pyroutersploit_camera_scanner",
-            "Vulnerability scanner targeting IP cameras",
-            CryptexCategory::Scanner,
-            None,
-            Some("routersploit.modules.scanners.cameras".to_string()),
-        )?;
-
-        // Credentials
-        Self::add_entry_with_impl(
-            "creds_ssh_default",
-            "pyroutersploit_ssh_bruteforce",
-            "Test default and common SSH credentials",
-            CryptexCategory::Credential,
-            Some("crate::core::creds::ssh_default".to_string()),
-            Some("routersploit.modules.creds.generic.ssh_default".to_string()),
-        )?;
-
-        Self::add_entry_with_impl(
-            "creds_telnet_default",
-            "pyroutersploit_telnet_bruteforce",
-            "Test default and common Telnet credentials",
-            CryptexCategory::Credential,
-            None,
-            Some("routersploit.modules.creds.generic.telnet_default".to_string()),
-        )?;
-
-        // Payloads
-        Self::add_entry_with_impl(
-            "payload_reverse_tcp_mipsle",
-            "pyroutersploit_mipsle_revshell",
-            "MIPS little-endian reverse TCP shell payload",
-            CryptexCategory::Payload,
-            Some("crate::core::payload::mipsle::reverse_tcp".to_string()),
-            Some("routersploit.modules.payloads.mipsle.reverse_tcp".to_string()),
-        )?;
-
-        Self::add_entry_with_impl(
-            "payload_reverse_tcp_armle",
-            "pyroutersploit_armle_revshell",
-            "ARM little-endian reverse TCP shell payload",
-            CryptexCategory::Payload,
-            Some("crate::core::payload::armle::reverse_tcp".to_string()),
-            Some("routersploit.modules.payloads.armle.reverse_tcp".to_string()),
-        )?;
-
-        // Encoders
-        Self::add_entry_with_impl(
-            "encoder_php_base64",
-            "pyroutersploit_php_b64_encoder",
-            "Base64 encoder for PHP payloads",
-            CryptexCategory::Encoder,
-            Some("crate::core::encoder::php::base64".to_string()),
-            Some("routersploit.modules.encoders.php.base64".to_string()),
-        )?;
-
-        // Utilities
-        Self::add_entry_with_impl(
-            "util_qkd_encrypt",
-            "pyroutersploit_quantum_encrypt",
-            "Quantum key distribution encryption utility",
-            CryptexCategory::Utility,
-            Some("crate::crypto::qkd::encrypt".to_string()),
-            None,
-        )?;
-
-        Self::add_entry_with_impl(
-            "util_multi_hash",
-            "pyroutersploit_omni_hasher",
-            "Multi-algorithm hashing utility (SHA-2/3, BLAKE, etc.)",
-            CryptexCategory::Utility,
-            Some("crate::crypto::hashing::multi_hash".to_string()),
-            None,
-        )?;
+        let entries = vec![
+            // Exploits
+            Self::entry_with_impl(
+                "exploit_dlink_rce_hnap",
+                "pyroutersploit_dlink_hnap_pwn",
+                "Execute remote code on D-Link routers via HNAP vulnerability",
+                CryptexCategory::Exploit,
+                Some("crate::core::exploit::dlink::hnap_rce".to_string()),
+                Some("routersploit.modules.exploits.routers.dlink.hnap_login".to_string()),
+            ),
+
+            Self::entry_with_impl(
+                "exploit_linksys_wrt54gl_rce",
+                "pyroutersploit_linksys_wrt54gl_exec",
+                "Remote command execution on Linksys WRT54GL routers",
+                CryptexCategory::Exploit,
+                None,
+                Some("routersploit.modules.exploits.routers.linksys.wrt54gl_exec".to_string()),
+            ),
+
+            Self::entry_with_impl(
+                "exploit_netgear_setup_rce",
+                "pyroutersploit_netgear_unauth_exec",
+                "Unauthenticated remote code execution on Netgear routers",
+                CryptexCategory::Exploit,
+                None,
+                Some("routersploit.modules.exploits.routers.netgear.multi_rce".to_string()),
+            ),
+
+            // Scanners
+            Self::entry_with_impl(
+                "scanner_router_autopwn",
+                "pyroutersploit_autopwn_scanner",
+                "Automated vulnerability scanner for routers across all protocols",
+                CryptexCategory::Scanner,
+                Some("crate::core::scanner::autopwn".to_string()),
+                Some("routersploit.modules.scanners.autopwn".to_string()),
+            ),
+
+            Self::entry_with_impl(
+                "scanner_camera_vuln",
+                "pyroutersploit_camera_scanner",
+                "Vulnerability scanner targeting IP cameras",
+                CryptexCategory::Scanner,
+                None,
+                Some("routersploit.modules.scanners.cameras".to_string()),
+            ),
+
+            // Credentials
+            Self::entry_with_impl(
+                "creds_ssh_default",
+                "pyroutersploit_ssh_bruteforce",
+                "Test default and common SSH credentials",
+                CryptexCategory::Credential,
+                Some("crate::core::creds::ssh_default".to_string()),
+                Some("routersploit.modules.creds.generic.ssh_default".to_string()),
+            ),
+
+            Self::entry_with_impl(
+                "creds_telnet_default",
+                "pyroutersploit_telnet_bruteforce",
+                "Test default and common Telnet credentials",
+                CryptexCategory::Credential,
+                None,
+                Some("routersploit.modules.creds.generic.telnet_default".to_string()),
+            ),
+
+            // Payloads
+            Self::entry_with_impl(
+                "payload_reverse_tcp_mipsle",
+                "pyroutersploit_mipsle_revshell",
+                "MIPS little-endian reverse TCP shell payload",
+                CryptexCategory::Payload,
+                Some("crate::core::payload::mipsle::reverse_tcp".to_string()),
+                Some("routersploit.modules.payloads.mipsle.reverse_tcp".to_string()),
+            ),
+
+            Self::entry_with_impl(
+                "payload_reverse_tcp_armle",
+                "pyroutersploit_armle_revshell",
+                "ARM little-endian reverse TCP shell payload",
+                CryptexCategory::Payload,
+                Some("crate::core::payload::armle::reverse_tcp".to_string()),
+                Some("routersploit.modules.payloads.armle.reverse_tcp".to_string()),
+            ),
+
+            // Encoders
+            Self::entry_with_impl(
+                "encoder_php_base64",
+                "pyroutersploit_php_b64_encoder",
+                "Base64 encoder for PHP payloads",
+                CryptexCategory::Encoder,
+                Some("crate::core::encoder::php::base64".to_string()),
+                Some("routersploit.modules.encoders.php.base64".to_string()),
+            ),
+
+            // Utilities
+            Self::entry_with_impl(
+                "util_qkd_encrypt",
+                "pyroutersploit_quantum_encrypt",
+                "Quantum key distribution encryption utility",
+                CryptexCategory::Utility,
+                Some("crate::crypto::qkd::encrypt".to_string()),
+                None,
+            ),
+
+            Self::entry_with_impl(
+                "util_multi_hash",
+                "pyroutersploit_omni_hasher",
+                "Multi-algorithm hashing utility (SHA-2/3, BLAKE, etc.)",
+                CryptexCategory::Utility,
+                Some("crate::crypto::hashing::multi_hash".to_string()),
+                None,
+            ),
+        ];
+        db::insert_many(&entries)?;
 
         tracing::info!("Default cryptex entries populated successfully");
         Ok(())
     }
 }
 
+/// Best similarity of the query to the whole name or any `_`-separated part of it
+fn name_score(query: &str, name: &str) -> f64 {
+    let name = name.to_lowercase();
+    if name.contains(query) {
+        return 1.0;
+    }
+    std::iter::once(name.as_str())
+        .chain(name.split(|c: char| !c.is_alphanumeric()).filter(|part| !part.is_empty()))
+        .map(|candidate| similarity(query, candidate))
+        .fold(0.0, f64::max)
+}
+
+/// 1 minus the Levenshtein distance over the longer length
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    // Single-row edit distance table
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
     async fn test_cryptex_operations() {
-        // Initialize test database
-        std::env::set_var("PYROUTERSPLOIT_DB_PATH", ":memory:");
-        crate::db::redb_client::init_database().await.unwrap();
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
 
         // Add entry
         let entry = CryptexDictionary::add_entry(
@@ -244,4 +365,105 @@ mod tests {
         let not_found = CryptexDictionary::lookup_function("test_function").unwrap();
         assert!(not_found.is_none());
     }
+
+    #[tokio::test]
+    async fn test_resolve_exploit_both_directions() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let entry = CryptexDictionary::add_entry(
+            "exploit_dlink_rce_hnap",
+            "pyroutersploit_dlink_hnap_pwn",
+            "Execute remote code on D-Link routers via HNAP",
+            CryptexCategory::Exploit,
+        )
+        .unwrap();
+        let metadata = ExploitMetadata {
+            id: Uuid::new_v4(),
+            name: "D-Link HNAP RCE".to_string(),
+            description: String::new(),
+            authors: vec![],
+            references: vec![],
+            devices: vec!["DIR-645".to_string()],
+            category: "exploits/routers/dlink".to_string(),
+            protocol: crate::db::models::Protocol::HTTP,
+            severity: crate::db::models::Severity::Critical,
+            verified: true,
+            cryptex_id: Some(entry.id),
+            cvss_vector: None,
+            updated_at: Utc::now(),
+        };
+        exploits::insert(&metadata).unwrap();
+
+        let resolved = CryptexDictionary::resolve_exploit("pyroutersploit_dlink_hnap_pwn").unwrap().unwrap();
+        assert_eq!(resolved.id, metadata.id);
+        assert!(CryptexDictionary::resolve_exploit("pyroutersploit_unknown").unwrap().is_none());
+
+        let (exploit, linked) = exploits::with_cryptex(&metadata.id).unwrap().unwrap();
+        assert_eq!(exploit.name, "D-Link HNAP RCE");
+        assert_eq!(linked.function_name, "exploit_dlink_rce_hnap");
+
+        // A soft-deleted entry still backs the link
+        CryptexDictionary::delete(&entry.id).unwrap();
+        assert!(exploits::with_cryptex(&metadata.id).unwrap().is_some());
+
+        // A dangling link is an error rather than a silent miss
+        CryptexDictionary::purge(&entry.id).unwrap();
+        assert!(exploits::with_cryptex(&metadata.id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let entry = CryptexDictionary::add_entry(
+            "utility_soft_deleted",
+            "pyroutersploit_soft_deleted",
+            "Removed, then brought back",
+            CryptexCategory::Utility,
+        )
+        .unwrap();
+        let listed = |id: Uuid| CryptexDictionary::list_all().unwrap().iter().any(|e| e.id == id);
+
+        assert!(CryptexDictionary::delete(&entry.id).unwrap());
+        assert!(!CryptexDictionary::delete(&entry.id).unwrap());
+        assert!(!listed(entry.id));
+        assert!(CryptexDictionary::lookup_function("utility_soft_deleted").unwrap().is_none());
+        assert!(CryptexDictionary::search("soft_deleted").unwrap().is_empty());
+        let deleted = CryptexDictionary::list_deleted().unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert!(deleted[0].deleted_at.is_some());
+
+        assert!(CryptexDictionary::restore(&entry.id).unwrap());
+        assert!(listed(entry.id));
+        assert!(CryptexDictionary::list_deleted().unwrap().is_empty());
+        assert!(CryptexDictionary::lookup_function("utility_soft_deleted").unwrap().unwrap().deleted_at.is_none());
+
+        CryptexDictionary::purge(&entry.id).unwrap();
+        assert!(!CryptexDictionary::restore(&entry.id).unwrap());
+        assert!(CryptexDictionary::list_deleted().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_ranks_typo_match_first() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        CryptexDictionary::add_entry("exploit_fuzz_heap_overflow", "pyroutersploit_fuzzheap", "", CryptexCategory::Exploit)
+            .unwrap();
+        CryptexDictionary::add_entry("exploit_fuzz_head_request", "pyroutersploit_fuzzhead", "", CryptexCategory::Exploit)
+            .unwrap();
+        CryptexDictionary::add_entry("scanner_fuzz_unrelated", "pyroutersploit_portsweep", "", CryptexCategory::Scanner)
+            .unwrap();
+
+        assert!(CryptexDictionary::search("pyroutersploit_fuzheap").unwrap().is_empty());
+
+        let results = CryptexDictionary::fuzzy_search("pyroutersploit_fuzheap", DEFAULT_FUZZY_THRESHOLD).unwrap();
+        let names: Vec<&str> = results.iter().map(|(entry, _)| entry.branding_name.as_str()).collect();
+        assert_eq!(names, ["pyroutersploit_fuzzheap", "pyroutersploit_fuzzhead"]);
+        assert!(results[0].1 > results[1].1);
+
+        // A part of the name is matched on its own
+        let results = CryptexDictionary::fuzzy_search("overflwo", DEFAULT_FUZZY_THRESHOLD).unwrap();
+        assert_eq!(results[0].0.function_name, "exploit_fuzz_heap_overflow");
+
+        assert!(CryptexDictionary::fuzzy_search("pyroutersploit_fuzheap", 1.0).unwrap().is_empty());
+        assert_eq!(similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+    }
 }
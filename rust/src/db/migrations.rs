@@ -0,0 +1,170 @@
+// Schema versioning and ordered migrations, run whenever the database is opened
+
+use anyhow::{anyhow, Context, Result};
+use redb::{Database, ReadableTable, WriteTransaction};
+use serde_json::Value;
+
+use crate::db::redb_client::{JsonTable, CONFIG_TABLE};
+
+/// Schema version written by this build
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Stored in the config table next to the app config
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrades a database from schema version `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&WriteTransaction) -> Result<()>,
+}
+
+/// Every step, in order. When a stored model changes in a way serde
+/// defaults can't absorb (a rename, a type change), add a step here and
+/// bump `SCHEMA_VERSION`.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "build the cryptex search index",
+    apply: crate::db::search_index::rebuild,
+}];
+
+/// Bring `db` up to `SCHEMA_VERSION`, returning the version it is now at
+pub fn run(db: &Database) -> Result<u32> {
+    run_steps(db, MIGRATIONS, SCHEMA_VERSION)
+}
+
+/// Databases from before versioning have no stored version and count as 1
+fn stored_version(txn: &WriteTransaction) -> Result<u32> {
+    let table = txn.open_table(CONFIG_TABLE)?;
+    let version = match table.get(SCHEMA_VERSION_KEY)? {
+        Some(value) => value
+            .value()
+            .parse()
+            .map_err(|_| anyhow!("Invalid stored schema version: {}", value.value()))?,
+        None => 1,
+    };
+    Ok(version)
+}
+
+/// All steps run in one transaction, so a failure leaves the old version intact
+fn run_steps(db: &Database, steps: &[Migration], target: u32) -> Result<u32> {
+    let txn = db.begin_write()?;
+    let current = stored_version(&txn)?;
+    if current > target {
+        anyhow::bail!(
+            "Database schema version {} is newer than this build supports ({})",
+            current,
+            target
+        );
+    }
+
+    for version in current..target {
+        let step = steps
+            .iter()
+            .find(|step| step.from == version)
+            .ok_or_else(|| anyhow!("No migration from schema version {}", version))?;
+        tracing::info!("Migrating database schema {} -> {}: {}", version, version + 1, step.description);
+        (step.apply)(&txn).with_context(|| format!("Migration from schema version {} failed", version))?;
+    }
+
+    {
+        let mut table = txn.open_table(CONFIG_TABLE)?;
+        table.insert(SCHEMA_VERSION_KEY, target.to_string().as_str())?;
+    }
+    txn.commit()?;
+    Ok(target)
+}
+
+/// Apply `f` to the JSON of every row in `definition`, for use inside a migration
+pub fn rewrite_rows(
+    txn: &WriteTransaction,
+    definition: JsonTable,
+    f: impl Fn(&mut Value) -> Result<()>,
+) -> Result<()> {
+    let mut table = txn.open_table(definition)?;
+    let mut rows = Vec::new();
+    for item in table.iter()? {
+        let (key, value) = item?;
+        rows.push((key.value().to_string(), value.value().to_string()));
+    }
+
+    for (key, value) in rows {
+        let mut json: Value = serde_json::from_str(&value)?;
+        f(&mut json)?;
+        table.insert(key.as_str(), json.to_string().as_str())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::CryptexEntry;
+    use crate::db::redb_client::CRYPTEX_TABLE;
+
+    // v1 stored cryptex rows with `description` where v2 has `pseudo_code`
+    fn rename_description(txn: &WriteTransaction) -> Result<()> {
+        rewrite_rows(txn, CRYPTEX_TABLE, |row| {
+            let row = row.as_object_mut().ok_or_else(|| anyhow!("Row is not an object"))?;
+            if let Some(description) = row.remove("description") {
+                row.insert("pseudo_code".to_string(), description);
+            }
+            Ok(())
+        })
+    }
+
+    fn version(db: &Database) -> String {
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(CONFIG_TABLE).unwrap();
+        let version = table.get(SCHEMA_VERSION_KEY).unwrap().unwrap().value().to_string();
+        version
+    }
+
+    #[test]
+    fn test_v1_to_v2_migration() {
+        let db = Database::builder()
+            .create_with_backend(redb::backends::InMemoryBackend::new())
+            .unwrap();
+
+        let entry = CryptexEntry::new(
+            "exploit_dlink_rce_hnap".to_string(),
+            "pyroutersploit_dlink_hnap_pwn".to_string(),
+            "Execute remote code via HNAP".to_string(),
+            crate::db::models::CryptexCategory::Exploit,
+        );
+        let mut v1_row = serde_json::to_value(&entry).unwrap();
+        let pseudo_code = v1_row.as_object_mut().unwrap().remove("pseudo_code").unwrap();
+        v1_row["description"] = pseudo_code;
+
+        let key = entry.id.to_string();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(CRYPTEX_TABLE).unwrap();
+            table.insert(key.as_str(), v1_row.to_string().as_str()).unwrap();
+            let mut config = txn.open_table(CONFIG_TABLE).unwrap();
+            config.insert(SCHEMA_VERSION_KEY, "1").unwrap();
+        }
+        txn.commit().unwrap();
+
+        let steps = [Migration {
+            from: 1,
+            description: "rename cryptex description to pseudo_code",
+            apply: rename_description,
+        }];
+        assert_eq!(run_steps(&db, &steps, 2).unwrap(), 2);
+        assert_eq!(version(&db), "2");
+
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(CRYPTEX_TABLE).unwrap();
+        let migrated: CryptexEntry = serde_json::from_str(table.get(key.as_str()).unwrap().unwrap().value()).unwrap();
+        assert_eq!(migrated.pseudo_code, "Execute remote code via HNAP");
+        drop(table);
+        drop(txn);
+
+        // Already current is a no-op; a newer database is refused
+        assert_eq!(run_steps(&db, &steps, 2).unwrap(), 2);
+        assert!(run_steps(&db, &[], 1).is_err());
+        assert!(run_steps(&db, &steps, 3).is_err());
+        assert_eq!(version(&db), "2");
+    }
+}
@@ -1,6 +1,8 @@
 pub mod models;
 pub mod redb_client;
 pub mod cryptex;
+pub mod migrations;
+pub mod search_index;
 
 pub use models::*;
 pub use redb_client::*;
@@ -1,9 +1,13 @@
 // Data models for PyRouterSploit
 
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use zeroize::ZeroizeOnDrop;
 
 /// Cryptex Dictionary Entry
 /// Maps function names to branding names and metadata
@@ -17,8 +21,14 @@ pub struct CryptexEntry {
     pub python_impl: Option<String>,
     pub category: CryptexCategory,
     pub metadata: HashMap<String, String>,
+    /// Lowercase labels for grouping, e.g. `cve-2023`, `arm`, `authenticated`
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set by a soft delete; the row stays so references still resolve
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -47,14 +57,33 @@ impl CryptexEntry {
             python_impl: None,
             category,
             metadata: HashMap::new(),
+            tags: Vec::new(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
         }
     }
+
+    /// Replace the tags, trimmed, lowercased and without duplicates
+    pub fn set_tags(&mut self, tags: impl IntoIterator<Item = impl AsRef<str>>) {
+        let mut tags: Vec<String> = tags
+            .into_iter()
+            .map(|tag| tag.as_ref().trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        self.tags = tags;
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        self.tags.contains(&tag)
+    }
 }
 
 /// Exploit metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ExploitMetadata {
     pub id: Uuid,
     pub name: String,
@@ -67,9 +96,16 @@ pub struct ExploitMetadata {
     pub severity: Severity,
     pub verified: bool,
     pub cryptex_id: Option<Uuid>,
+    /// CVSS v3.1 base vector, when one has been assigned
+    #[serde(default)]
+    pub cvss_vector: Option<String>,
+    /// Last change, for incremental sync; the Unix epoch on rows written
+    /// before it was tracked
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum Protocol {
     HTTP,
     HTTPS,
@@ -83,7 +119,8 @@ pub enum Protocol {
     Custom(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Ordered most to least severe, so `Critical < Info`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
 pub enum Severity {
     Critical,
     High,
@@ -103,22 +140,122 @@ pub struct ScanResult {
     pub status: ScanStatus,
     pub vulnerabilities: Vec<VulnerabilityFinding>,
     pub credentials: Vec<CredentialFinding>,
+    /// Aggregate risk, 0-100, derived from the vulnerable findings
+    #[serde(default)]
+    pub risk_score: f64,
+    /// Tenant that owns the scan; only that tenant may read it back
+    #[serde(default)]
+    pub tenant_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Webhook URLs notified as a scan progresses
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ScanCallbacks {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_vulnerability: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<String>,
+}
+
+/// A request to start a scan
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanRequest {
+    pub target: String,
+    pub scan_type: String,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    #[serde(default)]
+    pub callbacks: ScanCallbacks,
+    /// Validate and plan the scan without contacting the target
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Server name sent in TLS handshakes with the target, instead of its host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sni: Option<String>,
+    /// Max requests per second to the target, instead of `scan_rate_limit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<f64>,
+}
+
+impl ScanRequest {
+    pub fn new(target: impl Into<String>, scan_type: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            scan_type: scan_type.into(),
+            tenant_id: None,
+            callbacks: ScanCallbacks::default(),
+            dry_run: false,
+            sni: None,
+            rate_limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ScanStatus {
+    /// Waiting in the job queue for a worker
+    Queued,
     Running,
     Completed,
     Failed,
     Cancelled,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct VulnerabilityFinding {
     pub exploit_id: Uuid,
     pub vulnerable: bool,
-    pub proof: Option<String>,
+    pub proof: Option<Proof>,
     pub severity: Severity,
+    #[serde(default)]
+    pub cvss_score: Option<f64>,
+}
+
+/// Evidence behind a finding: what was sent to the target and what came back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(from = "StoredProof")]
+pub struct Proof {
+    pub request: String,
+    pub response: String,
+    pub captured_at: DateTime<Utc>,
+}
+
+impl Proof {
+    pub fn new(request: impl Into<String>, response: impl Into<String>) -> Self {
+        Self {
+            request: request.into(),
+            response: response.into(),
+            captured_at: Utc::now(),
+        }
+    }
+}
+
+/// Proofs stored before they were structured are a bare string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredProof {
+    Structured {
+        request: String,
+        response: String,
+        captured_at: DateTime<Utc>,
+    },
+    Legacy(String),
+}
+
+impl From<StoredProof> for Proof {
+    fn from(stored: StoredProof) -> Self {
+        match stored {
+            StoredProof::Structured { request, response, captured_at } => Self { request, response, captured_at },
+            // Only the response was kept, and not when it was captured
+            StoredProof::Legacy(response) => Self {
+                request: String::new(),
+                response,
+                captured_at: DateTime::<Utc>::UNIX_EPOCH,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,16 +264,80 @@ pub struct CredentialFinding {
     pub password: String,
     pub protocol: Protocol,
     pub verified: bool,
+    /// Whether the login can write, for protocols where that was checked
+    #[serde(default)]
+    pub writable: Option<bool>,
+    /// HTTP authentication realm the credentials were accepted for
+    #[serde(default)]
+    pub realm: Option<String>,
+}
+
+/// Factory default login for a vendor's device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultCredential {
+    pub vendor: String,
+    pub device: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// Shell session metadata, persisted so sessions are still listed after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub id: Uuid,
+    pub target: String,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub status: SessionStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionStatus {
+    Active,
+    Closed,
+    /// Recorded as active, but its connection is gone (e.g. the process restarted)
+    Lost,
+}
+
+/// API key for integrators that can't pass gateway identity headers.
+/// Only the Argon2 hash of the secret is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub key_id: String,
+    pub name: String,
+    pub secret_hash: String,
+    pub tenant_id: Option<String>,
+    pub roles: Vec<String>,
+    pub created_at: DateTime<Utc>,
 }
 
-/// QKD encryption metadata
+/// One MCP `tools/call`, with secrets in the arguments redacted
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub tool: String,
+    pub arguments: Value,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// QKD encryption metadata. Key material is wiped on drop.
+#[derive(Debug, Clone, Serialize, Deserialize, ZeroizeOnDrop)]
 pub struct QKDSession {
+    #[zeroize(skip)]
     pub id: Uuid,
+    #[zeroize(skip)]
     pub algorithm: String,
     pub key_material: Vec<u8>,
+    #[zeroize(skip)]
     pub created_at: DateTime<Utc>,
+    #[zeroize(skip)]
     pub expires_at: Option<DateTime<Utc>>,
+    /// Session this one was rotated from, if any
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub previous_session_id: Option<Uuid>,
 }
 
 /// Training model metadata
@@ -161,6 +362,7 @@ pub enum ModelType {
 
 /// Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub api_host: String,
     pub api_port: u16,
@@ -169,6 +371,46 @@ pub struct Config {
     pub timeout_seconds: u64,
     pub enable_qkd: bool,
     pub enable_python_compat: bool,
+    /// Max API requests per user or API key (or source IP) within the window
+    pub rate_limit_requests: usize,
+    pub rate_limit_window_secs: u64,
+    /// Cached results of read-only MCP tools; 0 disables the cache
+    pub mcp_cache_size: usize,
+    pub mcp_cache_ttl_secs: u64,
+    /// Comma-separated CIDRs and hostnames (`*.lab` for subdomains) that
+    /// exploits and scans may target; empty allows any target
+    pub scope_allowlist: String,
+    pub scope_block_private: bool,
+    pub scope_block_loopback: bool,
+    /// Worker tasks running queued scans, and how many scans may wait
+    pub scan_workers: usize,
+    pub scan_queue_size: usize,
+    /// Max requests per second a scan sends its target, unless the scan
+    /// asks for its own; 0 is unpaced
+    pub scan_rate_limit: f64,
+    /// Comma-separated origins browsers may call the REST API from, or `*`;
+    /// empty keeps the API same-origin only
+    pub cors_allowed_origins: String,
+    pub cors_allowed_methods: String,
+    pub cors_allowed_headers: String,
+    /// Largest accepted REST request body, and how long a request may take
+    pub max_body_bytes: usize,
+    pub request_timeout_secs: u64,
+    /// How long to wait for a TCP connect to the target before running an
+    /// exploit or scan against it; 0 skips the check
+    pub precheck_timeout_secs: u64,
+    /// `socks5://` or `http://` proxy, with optional `user:pass@`, that
+    /// exploit and scan traffic is sent through; empty connects directly
+    pub proxy_url: String,
+    /// HTTPS certificate checks on targets: strict unless invalid certs are
+    /// accepted, with an optional PEM bundle of extra CAs. The SNI override
+    /// is set per scan or exploit run.
+    pub tls_accept_invalid_certs: bool,
+    pub tls_ca_bundle: String,
+    /// Comma-separated `ip[:port]` nameservers target hostnames are
+    /// resolved with; empty uses the system resolver
+    pub dns_nameservers: String,
+    pub dns_timeout_secs: u64,
 }
 
 impl Default for Config {
@@ -181,6 +423,185 @@ impl Default for Config {
             timeout_seconds: 30,
             enable_qkd: true,
             enable_python_compat: true,
+            rate_limit_requests: 120,
+            rate_limit_window_secs: 60,
+            mcp_cache_size: 256,
+            mcp_cache_ttl_secs: 60,
+            scope_allowlist: String::new(),
+            scope_block_private: false,
+            scope_block_loopback: false,
+            scan_workers: 4,
+            scan_queue_size: 64,
+            scan_rate_limit: 0.0,
+            cors_allowed_origins: String::new(),
+            cors_allowed_methods: "GET,POST,OPTIONS".to_string(),
+            cors_allowed_headers: "authorization,content-type,x-api-key,x-request-id".to_string(),
+            max_body_bytes: 1024 * 1024,
+            request_timeout_secs: 30,
+            precheck_timeout_secs: 3,
+            proxy_url: String::new(),
+            tls_accept_invalid_certs: false,
+            tls_ca_bundle: String::new(),
+            dns_nameservers: String::new(),
+            dns_timeout_secs: 5,
+        }
+    }
+}
+
+impl Config {
+    fn to_map(&self) -> serde_json::Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
         }
     }
+
+    /// Names accepted by [`Config::set`]
+    pub fn keys() -> Vec<String> {
+        Config::default().to_map().keys().cloned().collect()
+    }
+
+    /// `(key, value)` pairs for display
+    pub fn entries(&self) -> Vec<(String, String)> {
+        self.to_map()
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(s) if key == "proxy_url" => (key, crate::core::net::redact_credentials(&s)),
+                Value::String(s) => (key, s),
+                other => (key, other.to_string()),
+            })
+            .collect()
+    }
+
+    /// Set `key` from its string form, parsed according to the field's type
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut map = self.to_map();
+        let current = map.get(key).ok_or_else(|| {
+            anyhow!("Unknown config key: {} (valid keys: {})", key, Config::keys().join(", "))
+        })?;
+
+        let parsed = match current {
+            Value::Bool(_) => Value::Bool(
+                value.parse().map_err(|_| anyhow!("{} expects true or false, got {}", key, value))?,
+            ),
+            Value::Number(_) => Value::Number(
+                value.parse().map_err(|_| anyhow!("{} expects a number, got {}", key, value))?,
+            ),
+            _ => Value::String(value.to_string()),
+        };
+        map.insert(key.to_string(), parsed);
+
+        let config: Config = serde_json::from_value(Value::Object(map))
+            .map_err(|e| anyhow!("Invalid value for {}: {}", key, e))?;
+        config.proxy()?;
+        config.dns()?;
+        config.scan_rate_limit()?;
+        *self = config;
+        Ok(())
+    }
+
+    /// The configured proxy, if any
+    pub fn proxy(&self) -> Result<Option<crate::core::net::ProxyConfig>> {
+        if self.proxy_url.trim().is_empty() {
+            return Ok(None);
+        }
+        crate::core::net::ProxyConfig::parse(&self.proxy_url).map(Some)
+    }
+
+    pub fn dns(&self) -> Result<crate::core::net::DnsConfig> {
+        Ok(crate::core::net::DnsConfig {
+            nameservers: crate::core::net::DnsConfig::parse_nameservers(&self.dns_nameservers)?,
+            timeout_secs: self.dns_timeout_secs,
+        })
+    }
+
+    /// Default pace of scans, `None` when unpaced
+    pub fn scan_rate_limit(&self) -> Result<Option<f64>> {
+        match self.scan_rate_limit {
+            0.0 => Ok(None),
+            rate if rate.is_finite() && rate > 0.0 => Ok(Some(rate)),
+            rate => Err(anyhow!("scan_rate_limit must be 0 or a positive number of requests per second, got {}", rate)),
+        }
+    }
+
+    pub fn tls(&self) -> crate::core::net::TlsOptions {
+        let non_empty = |value: &str| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        crate::core::net::TlsOptions {
+            accept_invalid_certs: self.tls_accept_invalid_certs,
+            ca_bundle: non_empty(&self.tls_ca_bundle),
+            sni: None,
+        }
+    }
+}
+
+/// Findings and scans for tests
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    /// A finding without proof
+    pub fn finding(exploit_id: Uuid, vulnerable: bool, severity: Severity, cvss_score: Option<f64>) -> VulnerabilityFinding {
+        VulnerabilityFinding {
+            exploit_id,
+            vulnerable,
+            proof: None,
+            severity,
+            cvss_score,
+        }
+    }
+
+    /// A completed autopwn scan of `target`, with no tenant or risk score
+    pub fn scan(target: &str, vulnerabilities: Vec<VulnerabilityFinding>) -> ScanResult {
+        ScanResult {
+            id: Uuid::new_v4(),
+            target: target.to_string(),
+            scan_type: "autopwn".to_string(),
+            started_at: Utc::now(),
+            completed_at: Some(Utc::now()),
+            status: ScanStatus::Completed,
+            vulnerabilities,
+            credentials: Vec::new(),
+            risk_score: 0.0,
+            tenant_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_proof_old_and_new_shapes() {
+        let finding = json!({
+            "exploit_id": Uuid::nil(),
+            "vulnerable": true,
+            "proof": "uid=0(root)",
+            "severity": "High",
+        });
+        let legacy: VulnerabilityFinding = serde_json::from_value(finding.clone()).unwrap();
+        let proof = legacy.proof.unwrap();
+        assert_eq!(proof.response, "uid=0(root)");
+        assert!(proof.request.is_empty());
+        assert_eq!(proof.captured_at, DateTime::<Utc>::UNIX_EPOCH);
+
+        let structured = VulnerabilityFinding {
+            proof: Some(Proof::new("GET /cgi-bin/luci HTTP/1.1", "HTTP/1.1 200 OK")),
+            ..serde_json::from_value(finding).unwrap()
+        };
+        let value = serde_json::to_value(&structured).unwrap();
+        assert_eq!(value["proof"]["request"], "GET /cgi-bin/luci HTTP/1.1");
+        let roundtrip: VulnerabilityFinding = serde_json::from_value(value).unwrap();
+        assert_eq!(roundtrip.proof, structured.proof);
+
+        let none: VulnerabilityFinding = serde_json::from_value(json!({
+            "exploit_id": Uuid::nil(),
+            "vulnerable": false,
+            "proof": null,
+            "severity": "Low",
+        }))
+        .unwrap();
+        assert!(none.proof.is_none());
+    }
 }
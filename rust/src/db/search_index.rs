@@ -0,0 +1,165 @@
+// Inverted index over cryptex entry text, used to narrow substring search
+//
+// One empty-valued key `token\0id` per lowercase token (a run of
+// alphanumerics) of an entry's function name, branding name or pseudo code,
+// so postings for a token, or for every token with a prefix, are one key
+// range. The index only picks candidates: search still checks the substring
+// on each one, so results match a full scan.
+
+use anyhow::Result;
+use redb::{ReadableTable, Table, WriteTransaction};
+use std::collections::BTreeSet;
+
+use crate::db::models::CryptexEntry;
+use crate::db::redb_client::{CRYPTEX_INDEX_TABLE, CRYPTEX_TABLE};
+
+/// Lowercase words of `text`, split on anything that isn't alphanumeric
+pub fn tokenize(text: &str) -> BTreeSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn entry_tokens(entry: &CryptexEntry) -> BTreeSet<String> {
+    let mut tokens = tokenize(&entry.function_name);
+    tokens.extend(tokenize(&entry.branding_name));
+    tokens.extend(tokenize(&entry.pseudo_code));
+    tokens
+}
+
+/// Separates the token from the id in a posting key. Tokens are
+/// alphanumeric, so it never appears inside one.
+const SEP: char = '\0';
+
+fn posting_key(token: &str, id: &str) -> String {
+    format!("{}{}{}", token, SEP, id)
+}
+
+/// Ids of the postings whose key starts with `prefix`, passing each key's
+/// token through `keep`
+fn scan_prefix(
+    index: &impl ReadableTable<&'static str, &'static str>,
+    prefix: &str,
+    keep: impl Fn(&str) -> bool,
+) -> Result<BTreeSet<String>> {
+    let mut ids = BTreeSet::new();
+    for item in index.range(prefix..)? {
+        let (key, _) = item?;
+        let key = key.value();
+        if !key.starts_with(prefix) {
+            break;
+        }
+        if let Some((token, id)) = key.split_once(SEP) {
+            if keep(token) {
+                ids.insert(id.to_string());
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Ids of entries with `token` as a whole word
+fn ids_for(index: &impl ReadableTable<&'static str, &'static str>, token: &str) -> Result<BTreeSet<String>> {
+    scan_prefix(index, &format!("{}{}", token, SEP), |_| true)
+}
+
+/// Ids of entries with a token matching `keep`, visiting each distinct
+/// token once: after a token's postings the scan seeks past them
+fn ids_matching(
+    index: &impl ReadableTable<&'static str, &'static str>,
+    keep: impl Fn(&str) -> bool,
+) -> Result<BTreeSet<String>> {
+    let mut ids = BTreeSet::new();
+    let mut from = String::new();
+    loop {
+        let token = match index.range(from.as_str()..)?.next() {
+            Some(item) => {
+                let (key, _) = item?;
+                key.value().split_once(SEP).map_or(key.value(), |(token, _)| token).to_string()
+            }
+            None => return Ok(ids),
+        };
+        if keep(&token) {
+            ids.extend(ids_for(index, &token)?);
+        }
+        // Just past every `token\0...` key
+        from = format!("{}{}", token, char::from(1));
+    }
+}
+
+pub fn add(index: &mut Table<&'static str, &'static str>, entry: &CryptexEntry) -> Result<()> {
+    let id = entry.id.to_string();
+    for token in entry_tokens(entry) {
+        index.insert(posting_key(&token, &id).as_str(), "")?;
+    }
+    Ok(())
+}
+
+pub fn remove(index: &mut Table<&'static str, &'static str>, entry: &CryptexEntry) -> Result<()> {
+    let id = entry.id.to_string();
+    for token in entry_tokens(entry) {
+        index.remove(posting_key(&token, &id).as_str())?;
+    }
+    Ok(())
+}
+
+/// Ids of entries that may contain `query`, in table key order. `None` when
+/// the query has no alphanumerics, so the index can't narrow it.
+///
+/// A word with separators on both sides in the query must be a whole token
+/// of the entry and is looked up directly. The last word, after a separator,
+/// may be cut off at its end, so it matches every token it prefixes. The
+/// first word may be cut off at its start too, which no key range covers:
+/// it is matched against each distinct token.
+pub fn candidates(index: &impl ReadableTable<&'static str, &'static str>, query: &str) -> Result<Option<BTreeSet<String>>> {
+    let words: Vec<String> = query
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_string)
+        .collect();
+    let last = words.len() - 1;
+
+    let mut candidates: Option<BTreeSet<String>> = None;
+    for (i, word) in words.iter().enumerate() {
+        if word.is_empty() {
+            continue;
+        }
+        let ids = if i > 0 && i < last {
+            ids_for(index, word)?
+        } else if i > 0 {
+            scan_prefix(index, word, |_| true)?
+        } else if i < last {
+            ids_matching(index, |token| token.ends_with(word.as_str()))?
+        } else {
+            ids_matching(index, |token| token.contains(word.as_str()))?
+        };
+
+        let narrowed = match candidates {
+            Some(candidates) => candidates.intersection(&ids).cloned().collect(),
+            None => ids,
+        };
+        if narrowed.is_empty() {
+            return Ok(Some(narrowed));
+        }
+        candidates = Some(narrowed);
+    }
+    Ok(candidates)
+}
+
+/// Index every stored entry from scratch, for databases written before the
+/// index existed
+pub fn rebuild(txn: &WriteTransaction) -> Result<()> {
+    txn.delete_table(CRYPTEX_INDEX_TABLE)?;
+    let entries = txn.open_table(CRYPTEX_TABLE)?;
+    let mut index = txn.open_table(CRYPTEX_INDEX_TABLE)?;
+    for item in entries.iter()? {
+        let (_, value) = item?;
+        // Unreadable rows are skipped by every listing, so they stay unsearchable
+        if let Ok(entry) = serde_json::from_str::<CryptexEntry>(value.value()) {
+            add(&mut index, &entry)?;
+        }
+    }
+    Ok(())
+}
@@ -8,6 +8,8 @@ pub mod api;
 pub mod mcp;
 pub mod nodered;
 pub mod python_compat;
+pub mod logging;
+pub mod telemetry;
 
 // Re-exports
 pub use crate::core::{exploit, scanner, payload, session};
@@ -19,16 +21,12 @@ pub use crate::mcp::server as mcp_server;
 use anyhow::Result;
 use tracing::{info, error};
 
-/// Initialize the PyRouterSploit library
+/// Initialize the PyRouterSploit library. Safe to call more than once.
+///
+/// Logging is owned by the embedding application: this never installs a
+/// tracing subscriber, it only emits events. The CLI sets one up with
+/// [`logging::init`] before calling this.
 pub async fn init() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
-
     info!("PyRouterSploit v{} initializing...", env!("CARGO_PKG_VERSION"));
 
     // Initialize database
@@ -37,6 +35,8 @@ pub async fn init() -> Result<()> {
     // Initialize crypto subsystem
     crypto::init()?;
 
+    telemetry::init();
+
     info!("PyRouterSploit initialized successfully");
     Ok(())
 }
@@ -44,6 +44,37 @@ pub async fn init() -> Result<()> {
 /// Shutdown and cleanup
 pub async fn shutdown() -> Result<()> {
     info!("PyRouterSploit shutting down...");
+
+    // Let running scans persist their results before the database goes away
+    let active = core::scanner::active::count();
+    if active > 0 {
+        info!("Waiting for {} active scan(s) to finish...", active);
+        if !core::scanner::active::drain(std::time::Duration::from_secs(30)).await {
+            error!("Timed out waiting for active scans, closing database anyway");
+        }
+    }
+
     db::redb_client::close_database().await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_init_twice() {
+        // Take the database's turn, then open the configured path instead
+        let _db = db::redb_client::test_support::init_database().await.unwrap();
+        db::redb_client::close_database().await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("pyroutersploit-init-{}.redb", uuid::Uuid::new_v4()));
+        std::env::set_var("PYROUTERSPLOIT_DB_PATH", &path);
+        init().await.unwrap();
+        init().await.unwrap();
+        assert!(db::redb_client::is_initialized());
+        assert!(path.exists());
+        db::redb_client::close_database().await.unwrap();
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,115 @@
+// Tracing subscriber setup for the CLI
+
+use anyhow::{Result, anyhow};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::str::FromStr;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+lazy_static! {
+    /// Filter of the subscriber installed by `init`, for runtime level changes
+    static ref FILTER: Mutex<Option<FilterHandle>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format: {} (expected text or json)", other)),
+        }
+    }
+}
+
+/// `RUST_LOG` directives take precedence over `level`
+fn filter(level: &str) -> Result<EnvFilter> {
+    let level: tracing::Level = level
+        .parse()
+        .map_err(|_| anyhow!("Invalid log level: {} (expected trace, debug, info, warn or error)", level))?;
+    Ok(EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy())
+}
+
+fn reloadable_subscriber(level: &str, format: LogFormat) -> Result<(Box<dyn Subscriber + Send + Sync>, FilterHandle)> {
+    let (filter, handle) = reload::Layer::new(filter(level)?);
+    let registry = tracing_subscriber::registry().with(filter);
+    let subscriber: Box<dyn Subscriber + Send + Sync> = match format {
+        // stdout carries MCP stdio traffic, so logs never go there
+        LogFormat::Text => Box::new(registry.with(fmt::layer().with_writer(std::io::stderr))),
+        LogFormat::Json => Box::new(registry.with(fmt::layer().json().with_writer(std::io::stderr))),
+    };
+    Ok((subscriber, handle))
+}
+
+/// Build the subscriber without installing it
+pub fn subscriber(level: &str, format: LogFormat) -> Result<Box<dyn Subscriber + Send + Sync>> {
+    Ok(reloadable_subscriber(level, format)?.0)
+}
+
+/// Install the global subscriber. Only the binary calls this; the library
+/// itself never installs one, so embedders keep control of logging.
+/// Fails if a global subscriber is already set.
+pub fn init(level: &str, format: LogFormat) -> Result<()> {
+    let (subscriber, handle) = reloadable_subscriber(level, format)?;
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| anyhow!("Logging already initialized: {}", e))?;
+    *FILTER.lock() = Some(handle);
+    Ok(())
+}
+
+/// Change the level of the subscriber installed by `init`, overriding
+/// `RUST_LOG`. Without one (an embedder owns logging) this does nothing.
+pub fn set_level(level: Level) -> Result<()> {
+    let Some(handle) = FILTER.lock().clone() else {
+        return Ok(());
+    };
+    handle
+        .reload(EnvFilter::default().add_directive(level.into()))
+        .map_err(|e| anyhow!("Failed to change log level: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_subscriber_constructs() {
+        let json = subscriber("debug", LogFormat::Json).unwrap();
+        tracing::subscriber::with_default(json, || {
+            tracing::info!(scan_id = "abc", "structured log line");
+        });
+
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+        assert!(subscriber("loud", LogFormat::Text).is_err());
+    }
+
+    #[test]
+    fn test_set_level_reloads_filter() {
+        let (subscriber, handle) = reloadable_subscriber("error", LogFormat::Text).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(Level::WARN));
+
+            *FILTER.lock() = Some(handle);
+            set_level(Level::WARN).unwrap();
+            assert!(tracing::enabled!(Level::WARN));
+            // Cleared while the subscriber is alive, so other tests never reload a dropped one
+            *FILTER.lock() = None;
+        });
+    }
+}
@@ -1,14 +1,24 @@
 // PyRouterSploit - Main Entry Point
 // Cross-platform security exploitation framework
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use pyroutersploit::crypto::DigestEncoding;
+use pyroutersploit::logging::{self, LogFormat};
 use pyroutersploit::{init, shutdown};
 
 #[derive(Parser)]
 #[command(name = "pyroutersploit")]
 #[command(about = "Cross-platform security exploitation framework", long_about = None)]
 struct Cli {
+    /// Minimum level to log (RUST_LOG directives take precedence)
+    #[arg(long, global = true, default_value = "info")]
+    log_level: String,
+
+    /// Log output format: text or json
+    #[arg(long, global = true, default_value = "text")]
+    log_format: LogFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -51,6 +61,26 @@ enum Commands {
         algorithm: Option<String>,
         #[arg(long)]
         all: bool,
+        /// Digest encoding: hex, base64 or base64url
+        #[arg(long, default_value = "hex")]
+        encoding: DigestEncoding,
+    },
+
+    /// Hash a file (or stdin with `-`) with multiple algorithms
+    HashFile {
+        path: std::path::PathBuf,
+        #[arg(short, long)]
+        algorithm: Option<String>,
+        #[arg(long)]
+        all: bool,
+        /// Digest encoding: hex, base64 or base64url
+        #[arg(long, default_value = "hex")]
+        encoding: DigestEncoding,
+    },
+
+    /// Guess which algorithms could have produced a hex or base64 digest
+    IdentifyHash {
+        hash: String,
     },
 
     /// QKD encryption operations
@@ -58,6 +88,25 @@ enum Commands {
         #[command(subcommand)]
         operation: QkdOperations,
     },
+
+    /// View or edit the stored configuration
+    Config {
+        #[command(subcommand)]
+        operation: ConfigOperations,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigOperations {
+    /// Print every setting
+    Show,
+    /// Change one setting
+    Set {
+        key: String,
+        value: String,
+    },
+    /// Restore the defaults
+    Reset,
 }
 
 #[derive(Subcommand)]
@@ -67,10 +116,13 @@ enum QkdOperations {
         #[arg(short, long)]
         data: String,
     },
-    /// Decrypt data
+    /// Decrypt data encrypted under a saved session
     Decrypt {
         #[arg(short, long)]
         ciphertext: String,
+        /// Session ID printed by `qkd encrypt`
+        #[arg(short, long)]
+        session_id: uuid::Uuid,
     },
     /// Generate a new key
     GenerateKey {
@@ -81,22 +133,16 @@ enum QkdOperations {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
-
     let cli = Cli::parse();
+    logging::init(&cli.log_level, cli.log_format)?;
 
     // Initialize PyRouterSploit
     init().await?;
 
     match cli.command {
         Commands::Serve { host, port } => {
-            serve_api(&host, port).await?;
+            // The server runs shutdown() itself once it has drained
+            return serve_api(&host, port).await;
         }
         Commands::Mcp { transport } => {
             start_mcp_server(&transport).await?;
@@ -111,12 +157,21 @@ async fn main() -> Result<()> {
         Commands::Cryptex { search, list_all } => {
             handle_cryptex_command(search, list_all)?;
         }
-        Commands::Hash { data, algorithm, all } => {
-            handle_hash_command(&data, algorithm, all)?;
+        Commands::Hash { data, algorithm, all, encoding } => {
+            handle_hash_command(&data, algorithm, all, encoding)?;
+        }
+        Commands::HashFile { path, algorithm, all, encoding } => {
+            handle_hash_file_command(&path, algorithm, all, encoding)?;
+        }
+        Commands::IdentifyHash { hash } => {
+            handle_identify_hash_command(&hash);
         }
         Commands::Qkd { operation } => {
             handle_qkd_command(operation).await?;
         }
+        Commands::Config { operation } => {
+            handle_config_command(operation)?;
+        }
     }
 
     shutdown().await?;
@@ -124,21 +179,26 @@ async fn main() -> Result<()> {
 }
 
 async fn serve_api(host: &str, port: u16) -> Result<()> {
-    use axum::Router;
+    use pyroutersploit::api::rest::{create_router, serve, shutdown_signal};
     use tokio::net::TcpListener;
 
     println!("🚀 Starting PyRouterSploit API server at http://{}:{}", host, port);
 
-    let app = pyroutersploit::api::rest::create_router().await;
+    let config = pyroutersploit::db::redb_client::config::load().unwrap_or_default();
+    pyroutersploit::core::jobs::start_from_config(&config);
+
+    let app = create_router().await;
     let addr = format!("{}:{}", host, port);
     let listener = TcpListener::bind(&addr).await?;
 
-    axum::serve(listener, app).await?;
+    serve(listener, app, shutdown_signal()).await?;
+    println!("✓ Server stopped cleanly");
     Ok(())
 }
 
 async fn start_mcp_server(transport: &str) -> Result<()> {
-    println!("🔌 Starting MCP server (transport: {})", transport);
+    // stdout is the JSON-RPC channel, so nothing else may be printed there
+    eprintln!("🔌 Starting MCP server (transport: {})", transport);
     pyroutersploit::mcp_server::start(transport).await?;
     Ok(())
 }
@@ -168,14 +228,14 @@ fn handle_cryptex_command(search: Option<String>, list_all: bool) -> Result<()>
     Ok(())
 }
 
-fn handle_hash_command(data: &str, algorithm: Option<String>, all: bool) -> Result<()> {
+fn handle_hash_command(data: &str, algorithm: Option<String>, all: bool, encoding: DigestEncoding) -> Result<()> {
     use pyroutersploit::crypto::{HashAlgorithm, MultiHasher};
 
     if all {
         let results = MultiHasher::hash_all(data.as_bytes())?;
         println!("\n🔐 Hashing '{}' with all algorithms:\n", data);
         for result in results {
-            println!("  {:<15} : {}", result.algorithm.to_string(), result.hex);
+            println!("  {:<15} : {}", result.label(), result.encode(encoding));
         }
     } else if let Some(algo_str) = algorithm {
         let algo = match algo_str.to_uppercase().as_str() {
@@ -189,10 +249,87 @@ fn handle_hash_command(data: &str, algorithm: Option<String>, all: bool) -> Resu
             }
         };
         let result = MultiHasher::hash(algo, data.as_bytes())?;
-        println!("\n{} hash: {}", algo, result.hex);
+        println!("\n{} hash: {}", algo, result.encode(encoding));
     } else {
         let result = MultiHasher::hash(HashAlgorithm::SHA256, data.as_bytes())?;
-        println!("\nSHA256 hash: {}", result.hex);
+        println!("\nSHA256 hash: {}", result.encode(encoding));
+    }
+
+    Ok(())
+}
+
+fn handle_hash_file_command(
+    path: &std::path::Path,
+    algorithm: Option<String>,
+    all: bool,
+    encoding: DigestEncoding,
+) -> Result<()> {
+    use pyroutersploit::crypto::{HashAlgorithm, MultiHasher};
+    use std::io::Read;
+
+    let from_stdin = path.as_os_str() == "-";
+    let data = if from_stdin {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data)?;
+        data
+    } else {
+        std::fs::read(path).with_context(|| format!("Cannot read {}", path.display()))?
+    };
+
+    let results = if all {
+        MultiHasher::hash_all(&data)?
+    } else {
+        let algorithm = match algorithm {
+            Some(name) => name.parse()?,
+            None => HashAlgorithm::SHA256,
+        };
+        vec![MultiHasher::hash(algorithm, &data)?]
+    };
+
+    let source = if from_stdin { "stdin".to_string() } else { path.display().to_string() };
+    println!("\n🔐 {} ({} bytes):\n", source, data.len());
+    for result in results {
+        println!("  {:<15} : {}", result.label(), result.encode(encoding));
+    }
+
+    Ok(())
+}
+
+fn handle_identify_hash_command(hash: &str) {
+    use pyroutersploit::crypto::HashAlgorithm;
+
+    let candidates = HashAlgorithm::identify(hash);
+    if candidates.is_empty() {
+        println!("No known algorithm produces a digest like '{}'", hash);
+        return;
+    }
+
+    println!("\n🔎 Possible algorithms ({}):\n", candidates.len());
+    for algorithm in candidates {
+        println!("  • {}", algorithm);
+    }
+}
+
+fn handle_config_command(operation: ConfigOperations) -> Result<()> {
+    use pyroutersploit::db::redb_client::config;
+
+    let config = match operation {
+        ConfigOperations::Show => config::load()?,
+        ConfigOperations::Set { key, value } => {
+            let config = config::set(&key, &value)?;
+            println!("✓ {} updated", key);
+            config
+        }
+        ConfigOperations::Reset => {
+            let config = config::reset()?;
+            println!("✓ Configuration reset to defaults");
+            config
+        }
+    };
+
+    println!("\n⚙ Configuration:\n");
+    for (key, value) in config.entries() {
+        println!("  {:<24} = {}", key, value);
     }
 
     Ok(())
@@ -207,14 +344,16 @@ async fn handle_qkd_command(operation: QkdOperations) -> Result<()> {
             let ciphertext = qkd.encrypt(data.as_bytes())?;
             let ciphertext_hex = hex::encode(&ciphertext);
             println!("\n🔐 Encrypted (hex): {}", ciphertext_hex);
-            println!("Session ID: {}", qkd.session_id);
+            println!("Session ID: {}", qkd.session_id());
             qkd.save_session()?;
             println!("✓ Session saved to database");
         }
-        QkdOperations::Decrypt { ciphertext } => {
-            let ciphertext_bytes = hex::decode(&ciphertext)?;
-            // For demo, create a new session - in real use, would load from DB
-            println!("⚠ Note: This is a demo. In production, load session from DB");
+        QkdOperations::Decrypt { ciphertext, session_id } => {
+            let ciphertext = hex::decode(ciphertext.trim()).context("Ciphertext must be hex encoded")?;
+            let qkd = QKDEncryption::load_session(&session_id)
+                .with_context(|| format!("Cannot decrypt with QKD session {}", session_id))?;
+            let plaintext = qkd.decrypt(&ciphertext)?;
+            println!("\n🔓 Decrypted: {}", String::from_utf8_lossy(&plaintext));
         }
         QkdOperations::GenerateKey { size } => {
             let key = QKDKeyGenerator::generate_hybrid_key(size)?;
@@ -0,0 +1,66 @@
+// Audit trail of MCP tool calls
+
+use chrono::Utc;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::db::models::AuditEntry;
+use crate::db::redb_client::audit;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Field names that hold credentials anywhere in the arguments
+const SECRET_FIELDS: &[&str] = &["password", "passwd", "secret", "token", "api_key", "private_key", "credential"];
+
+/// Fields carrying the caller's raw payload, which may itself be a secret
+const PAYLOAD_FIELDS: &[(&str, &str)] = &[("qkd_encrypt", "data"), ("multi_hash", "data"), ("multi_hash_batch", "inputs")];
+
+fn is_secret(field: &str) -> bool {
+    let field = field.to_ascii_lowercase();
+    SECRET_FIELDS.iter().any(|secret| field.contains(secret))
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (field, value) in map.iter_mut() {
+                if is_secret(field) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// Copy of `arguments` that is safe to store
+pub fn redact(tool: &str, arguments: &Value) -> Value {
+    let mut redacted = arguments.clone();
+    redact_value(&mut redacted);
+    if let Value::Object(map) = &mut redacted {
+        for (_, field) in PAYLOAD_FIELDS.iter().filter(|(name, _)| *name == tool) {
+            if let Some(value) = map.get_mut(*field) {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+    redacted
+}
+
+/// Record a tool call. Audit failures are logged, never surfaced to the caller.
+pub fn record(tool: &str, arguments: &Value, error: Option<&anyhow::Error>) {
+    let entry = AuditEntry {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        tool: tool.to_string(),
+        arguments: redact(tool, arguments),
+        success: error.is_none(),
+        error: error.map(|e| e.to_string()),
+    };
+    if let Err(e) = audit::insert(&entry) {
+        tracing::error!("Failed to write audit entry for {}: {}", tool, e);
+    }
+}
@@ -0,0 +1,218 @@
+// LRU cache for read-only MCP tool results
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::db::models::Config;
+
+/// Read-only tools whose results may be served from the cache. They all
+/// read the cryptex table, so the cache is dropped whenever it changes.
+/// Hash results are never cached, as the key would hold the hashed input.
+const CACHEABLE_TOOLS: &[&str] = &["cryptex_query"];
+
+struct CacheEntry {
+    value: Value,
+    inserted: Instant,
+    last_used: u64,
+    hits: u64,
+}
+
+/// Size-bounded cache with per-entry TTL. Eviction scans for the least
+/// recently used entry, which is fine for the few hundred entries we keep.
+/// Entries belong to one generation of the underlying data; seeing a newer
+/// one drops them all.
+pub struct ToolCache {
+    capacity: usize,
+    ttl: Duration,
+    clock: u64,
+    generation: u64,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ToolCache {
+    /// A capacity of 0 disables caching
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            clock: 0,
+            generation: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Move to `generation` if it is newer, dropping what was cached. False
+    /// if it is older, so a result computed from stale data isn't stored.
+    fn advance(&mut self, generation: u64) -> bool {
+        if generation > self.generation {
+            self.entries.clear();
+            self.generation = generation;
+        }
+        generation == self.generation
+    }
+
+    pub fn get(&mut self, key: &str, generation: u64) -> Option<Value> {
+        if !self.advance(generation) {
+            return None;
+        }
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        if entry.inserted.elapsed() >= self.ttl {
+            self.entries.remove(key);
+            return None;
+        }
+        entry.last_used = self.clock;
+        entry.hits += 1;
+        Some(entry.value.clone())
+    }
+
+    /// Store a result computed when the data was at `generation`
+    pub fn insert(&mut self, key: String, value: Value, generation: u64) {
+        if self.capacity == 0 || !self.advance(generation) {
+            return;
+        }
+        self.clock += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, CacheEntry {
+            value,
+            inserted: Instant::now(),
+            last_used: self.clock,
+            hits: 0,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+lazy_static! {
+    static ref TOOL_CACHE: Mutex<ToolCache> = Mutex::new(from_config(&Config::default()));
+}
+
+fn from_config(config: &Config) -> ToolCache {
+    ToolCache::new(config.mcp_cache_size, Duration::from_secs(config.mcp_cache_ttl_secs))
+}
+
+/// Resize the shared cache from `config`, dropping anything cached
+pub fn configure(config: &Config) {
+    *TOOL_CACHE.lock() = from_config(config);
+}
+
+pub fn is_cacheable(tool: &str) -> bool {
+    CACHEABLE_TOOLS.contains(&tool)
+}
+
+/// Compact JSON with object keys sorted, so argument order doesn't matter
+fn canonicalize(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonicalize(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            format!("[{}]", items.iter().map(canonicalize).collect::<Vec<_>>().join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+pub fn cache_key(tool: &str, arguments: &Value) -> String {
+    format!("{}:{}", tool, canonicalize(arguments))
+}
+
+/// Current generation of the data cached results are computed from. Read
+/// it before running the tool and pass it to `get` and `insert`.
+pub fn generation() -> u64 {
+    crate::db::redb_client::cryptex::generation()
+}
+
+pub fn get(key: &str, generation: u64) -> Option<Value> {
+    TOOL_CACHE.lock().get(key, generation)
+}
+
+pub fn insert(key: String, value: Value, generation: u64) {
+    TOOL_CACHE.lock().insert(key, value, generation);
+}
+
+/// Times `key` was served from the cache, if it is cached
+#[cfg(test)]
+pub(crate) fn hits(key: &str) -> Option<u64> {
+    TOOL_CACHE.lock().entries.get(key).map(|entry| entry.hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let mut cache = ToolCache::new(2, Duration::from_secs(60));
+        cache.insert("a".to_string(), json!(1), 0);
+        cache.insert("b".to_string(), json!(2), 0);
+        assert_eq!(cache.get("a", 0), Some(json!(1)));
+
+        // "b" is least recently used
+        cache.insert("c".to_string(), json!(3), 0);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b", 0).is_none());
+        assert!(cache.get("a", 0).is_some());
+
+        let mut expired = ToolCache::new(2, Duration::ZERO);
+        expired.insert("a".to_string(), json!(1), 0);
+        assert!(expired.get("a", 0).is_none());
+
+        let mut disabled = ToolCache::new(0, Duration::from_secs(60));
+        disabled.insert("a".to_string(), json!(1), 0);
+        assert!(disabled.is_empty());
+
+        assert_eq!(
+            cache_key("cryptex_query", &json!({ "query": "x", "limit": 5 })),
+            cache_key("cryptex_query", &json!({ "limit": 5, "query": "x" })),
+        );
+        assert!(!is_cacheable("multi_hash"));
+    }
+
+    #[test]
+    fn test_new_generation_drops_entries() {
+        let mut cache = ToolCache::new(4, Duration::from_secs(60));
+        cache.insert("a".to_string(), json!(1), 1);
+        assert_eq!(cache.get("a", 1), Some(json!(1)));
+
+        assert!(cache.get("a", 2).is_none());
+        assert!(cache.is_empty());
+
+        // A result computed before the change is not stored
+        cache.insert("b".to_string(), json!(2), 1);
+        assert!(cache.is_empty());
+        cache.insert("b".to_string(), json!(2), 2);
+        assert_eq!(cache.get("b", 2), Some(json!(2)));
+    }
+}
@@ -2,17 +2,38 @@
 
 use anyhow::{Result, anyhow};
 use serde_json::{json, Value};
+use uuid::Uuid;
+use std::time::Duration;
 
-use crate::db::{CryptexDictionary, models::CryptexCategory};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::exploit::{bounded, call_timeout, registry, Bounded, Options, Target};
+use crate::core::jobs::{self, JobQueue};
+use crate::core::net;
+use crate::core::options::ExploitOptions;
+use crate::db::{CryptexDictionary, models::{CryptexCategory, CryptexEntry, ScanRequest, ScanResult}};
 use crate::crypto::{HashAlgorithm, MultiHasher, QKDEncryption};
 use super::schema::*;
 
 pub struct MCPHandlers;
 
+const CRYPTEX_BRANDING_URI: &str = "cryptex://branding/";
+const CRYPTEX_FUNCTION_URI: &str = "cryptex://function/";
+const EXPLOIT_URI: &str = "exploit://";
+
 impl MCPHandlers {
     /// List all available tools
     pub fn list_tools() -> Result<Value> {
-        let tools = vec![
+        Ok(json!({ "tools": Self::tools() }))
+    }
+
+    /// Input schema advertised for `name`, used to validate tool arguments
+    pub fn tool_schema(name: &str) -> Option<Value> {
+        Self::tools().into_iter().find(|tool| tool.name == name).map(|tool| tool.input_schema)
+    }
+
+    fn tools() -> Vec<MCPTool> {
+        vec![
             MCPTool {
                 name: "cryptex_query".to_string(),
                 description: "Query the cryptex dictionary by function name, branding name, or search term".to_string(),
@@ -35,6 +56,14 @@ impl MCPHandlers {
                             "type": "string",
                             "enum": ["Exploit", "Scanner", "Credential", "Payload", "Encoder", "Utility"],
                             "description": "Filter by category"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Only entries with this tag (e.g., cve-2023, arm)"
+                        },
+                        "updated_after": {
+                            "type": "string",
+                            "description": "Only entries changed after this RFC 3339 time, for incremental sync"
                         }
                     }
                 }),
@@ -68,22 +97,66 @@ impl MCPHandlers {
                         "python_impl": {
                             "type": "string",
                             "description": "Python implementation path (optional)"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Labels for grouping entries (optional)"
                         }
                     },
                     "required": ["function_name", "branding_name", "pseudo_code", "category"]
                 }),
             },
+            MCPTool {
+                name: "cryptex_delete".to_string(),
+                description: "Delete a cryptex entry by id, function name, or branding name. The entry is kept as a tombstone and can be restored".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "Entry UUID"
+                        },
+                        "function_name": {
+                            "type": "string",
+                            "description": "Internal function name"
+                        },
+                        "branding_name": {
+                            "type": "string",
+                            "description": "Branding name (e.g., pyroutersploit_*)"
+                        }
+                    },
+                    "oneOf": [
+                        { "required": ["id"] },
+                        { "required": ["function_name"] },
+                        { "required": ["branding_name"] }
+                    ]
+                }),
+            },
             MCPTool {
                 name: "list_exploits".to_string(),
-                description: "List all available exploits".to_string(),
+                description: "List available exploits, optionally for one device".to_string(),
                 input_schema: json!({
                     "type": "object",
-                    "properties": {}
+                    "properties": {
+                        "target_platform": {
+                            "type": "string",
+                            "description": "Device name to filter by, e.g. DIR-850L"
+                        },
+                        "updated_after": {
+                            "type": "string",
+                            "description": "Only exploits changed after this RFC 3339 time"
+                        },
+                        "verified": {
+                            "type": "boolean",
+                            "description": "Only confirmed (true) or unconfirmed (false) exploits"
+                        }
+                    }
                 }),
             },
             MCPTool {
                 name: "run_exploit".to_string(),
-                description: "Execute an exploit against a target".to_string(),
+                description: "Check a target for an exploit's vulnerability, or execute the exploit".to_string(),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
@@ -101,7 +174,20 @@ impl MCPHandlers {
                         },
                         "options": {
                             "type": "object",
-                            "description": "Additional exploit options"
+                            "description": "Exploit options: rhost, rport, ssl, timeout, username, password and any module-specific keys"
+                        },
+                        "mode": {
+                            "type": "string",
+                            "enum": ["check", "exploit"],
+                            "description": "check only reports whether the target is vulnerable; exploit runs the payload (default: check)"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Validate and return the planned actions without touching the target"
+                        },
+                        "sni": {
+                            "type": "string",
+                            "description": "Server name for TLS handshakes, when the target's certificate is for another name (optional)"
                         }
                     },
                     "required": ["target"]
@@ -125,11 +211,60 @@ impl MCPHandlers {
                         "threads": {
                             "type": "integer",
                             "description": "Number of threads (default: 10)"
+                        },
+                        "callbacks": {
+                            "type": "object",
+                            "properties": {
+                                "on_complete": { "type": "string" },
+                                "on_vulnerability": { "type": "string" },
+                                "on_error": { "type": "string" }
+                            },
+                            "description": "Webhook URLs to POST signed scan events to (optional)"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Validate and return the scan plan without starting it"
+                        },
+                        "rate_limit": {
+                            "type": "number",
+                            "description": "Max requests per second to the target (default: the scan_rate_limit setting)"
+                        },
+                        "sni": {
+                            "type": "string",
+                            "description": "Server name for TLS handshakes, when the target's certificate is for another name (optional)"
                         }
                     },
                     "required": ["target"]
                 }),
             },
+            MCPTool {
+                name: "scan_status".to_string(),
+                description: "Get the status and findings of a scan started with scan_target".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "scan_id": {
+                            "type": "string",
+                            "description": "Scan UUID returned by scan_target"
+                        }
+                    },
+                    "required": ["scan_id"]
+                }),
+            },
+            MCPTool {
+                name: "cancel_scan".to_string(),
+                description: "Stop a queued or running scan started with scan_target, keeping its findings so far".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "scan_id": {
+                            "type": "string",
+                            "description": "Scan UUID returned by scan_target"
+                        }
+                    },
+                    "required": ["scan_id"]
+                }),
+            },
             MCPTool {
                 name: "multi_hash".to_string(),
                 description: "Hash data with one or all available algorithms (SHA-2/3, BLAKE, MD5, etc.)".to_string(),
@@ -142,7 +277,7 @@ impl MCPHandlers {
                         },
                         "algorithm": {
                             "type": "string",
-                            "enum": ["SHA224", "SHA256", "SHA384", "SHA512", "SHA3_256", "SHA3_512", "BLAKE2b", "BLAKE2s", "BLAKE3", "MD5", "RIPEMD160"],
+                            "enum": ["SHA224", "SHA256", "SHA384", "SHA512", "SHA3_256", "SHA3_512", "SHAKE128", "SHAKE256", "BLAKE2b", "BLAKE2s", "BLAKE3", "MD5", "SHA1", "RIPEMD160"],
                             "description": "Specific algorithm (optional)"
                         },
                         "all_algorithms": {
@@ -153,6 +288,26 @@ impl MCPHandlers {
                     "required": ["data"]
                 }),
             },
+            MCPTool {
+                name: "multi_hash_batch".to_string(),
+                description: "Hash several inputs with one algorithm in a single call, results in input order".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "inputs": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Data to hash, one digest per item"
+                        },
+                        "algorithm": {
+                            "type": "string",
+                            "enum": ["SHA224", "SHA256", "SHA384", "SHA512", "SHA3_256", "SHA3_512", "SHAKE128", "SHAKE256", "BLAKE2b", "BLAKE2s", "BLAKE3", "MD5", "SHA1", "RIPEMD160"],
+                            "description": "Algorithm for every input (default SHA256)"
+                        }
+                    },
+                    "required": ["inputs"]
+                }),
+            },
             MCPTool {
                 name: "qkd_encrypt".to_string(),
                 description: "Encrypt data using Quantum Key Distribution (QKD) encryption".to_string(),
@@ -171,9 +326,152 @@ impl MCPHandlers {
                     "required": ["data"]
                 }),
             },
-        ];
+            MCPTool {
+                name: "qkd_decrypt".to_string(),
+                description: "Decrypt data encrypted with qkd_encrypt using its saved session".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session UUID returned by qkd_encrypt"
+                        },
+                        "ciphertext": {
+                            "type": "string",
+                            "description": "Hex-encoded ciphertext"
+                        }
+                    },
+                    "required": ["session_id", "ciphertext"]
+                }),
+            },
+        ]
+    }
+
+    /// List cryptex entries and exploits as readable resources
+    pub fn list_resources() -> Result<Value> {
+        use crate::db::redb_client::exploits;
+
+        let mut resources: Vec<MCPResource> = CryptexDictionary::list_all()?
+            .into_iter()
+            .map(|entry| MCPResource {
+                uri: format!("{}{}", CRYPTEX_BRANDING_URI, entry.branding_name),
+                name: entry.branding_name,
+                description: format!("Cryptex entry for {}", entry.function_name),
+                mime_type: "application/json".to_string(),
+            })
+            .collect();
+
+        resources.extend(exploits::list_all()?.into_iter().map(|exploit| MCPResource {
+            uri: format!("{}{}", EXPLOIT_URI, exploit.id),
+            name: exploit.name,
+            description: exploit.description,
+            mime_type: "application/json".to_string(),
+        }));
+
+        Ok(json!({ "resources": resources }))
+    }
+
+    /// Read a resource by URI
+    pub fn read_resource(uri: &str) -> Result<Value> {
+        use crate::db::redb_client::exploits;
+
+        let contents = if let Some(branding_name) = uri.strip_prefix(CRYPTEX_BRANDING_URI) {
+            CryptexDictionary::lookup_branding(branding_name)?
+                .map(|entry| serde_json::to_string_pretty(&entry))
+                .transpose()?
+        } else if let Some(function_name) = uri.strip_prefix(CRYPTEX_FUNCTION_URI) {
+            CryptexDictionary::lookup_function(function_name)?
+                .map(|entry| serde_json::to_string_pretty(&entry))
+                .transpose()?
+        } else if let Some(id) = uri.strip_prefix(EXPLOIT_URI) {
+            let id = Uuid::parse_str(id).map_err(|_| anyhow!("Invalid exploit id: {}", id))?;
+            exploits::get_by_id(&id)?
+                .map(|exploit| serde_json::to_string_pretty(&exploit))
+                .transpose()?
+        } else {
+            return Err(anyhow!("Unsupported resource URI: {}", uri));
+        };
+
+        let text = contents.ok_or_else(|| anyhow!("Resource not found: {}", uri))?;
+        Ok(json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": "application/json",
+                "text": text
+            }]
+        }))
+    }
+
+    fn prompts() -> Vec<MCPPrompt> {
+        let argument = |name: &str, description: &str, required: bool| MCPPromptArgument {
+            name: name.to_string(),
+            description: description.to_string(),
+            required,
+        };
+
+        vec![
+            MCPPrompt {
+                name: "triage_target".to_string(),
+                description: "Scan a target and summarize what is exploitable".to_string(),
+                arguments: vec![
+                    argument("target", "Target IP address or hostname", true),
+                    argument("scan_type", "Scan type (autopwn, http, ssh, telnet, all)", false),
+                ],
+            },
+            MCPPrompt {
+                name: "select_exploit".to_string(),
+                description: "Pick the best exploit for a device".to_string(),
+                arguments: vec![
+                    argument("device", "Device model, e.g. DIR-850L", true),
+                    argument("vendor", "Device vendor, e.g. D-Link", false),
+                ],
+            },
+        ]
+    }
+
+    /// List available prompt templates
+    pub fn list_prompts() -> Result<Value> {
+        Ok(json!({ "prompts": Self::prompts() }))
+    }
+
+    /// Render a prompt template with the caller's arguments
+    pub fn get_prompt(params: PromptGetParams) -> Result<Value> {
+        let prompt = Self::prompts()
+            .into_iter()
+            .find(|p| p.name == params.name)
+            .ok_or_else(|| anyhow!("Unknown prompt: {}", params.name))?;
+
+        for arg in prompt.arguments.iter().filter(|a| a.required) {
+            if !params.arguments.contains_key(&arg.name) {
+                return Err(anyhow!("Missing required argument: {}", arg.name));
+            }
+        }
+        let arg = |name: &str| params.arguments.get(name).map(String::as_str);
 
-        Ok(json!({ "tools": tools }))
+        let text = match prompt.name.as_str() {
+            "triage_target" => format!(
+                "Triage {target}. Start a scan with the scan_target tool (scan_type \"{scan_type}\"), \
+                 poll scan_status until it completes, then look up each finding with cryptex_query \
+                 and summarize which vulnerabilities are confirmed and how severe they are.",
+                target = arg("target").unwrap_or_default(),
+                scan_type = arg("scan_type").unwrap_or("autopwn"),
+            ),
+            _ => format!(
+                "Find an exploit for the {vendor}{device}. Use list_exploits and cryptex_query to \
+                 find modules matching this device, explain why the best match applies, \
+                 and give its branding name so it can be passed to run_exploit.",
+                vendor = arg("vendor").map(|v| format!("{} ", v)).unwrap_or_default(),
+                device = arg("device").unwrap_or_default(),
+            ),
+        };
+
+        Ok(json!({
+            "description": prompt.description,
+            "messages": [{
+                "role": "user",
+                "content": MCPContent::Text { text }
+            }]
+        }))
     }
 
     /// Handle cryptex query
@@ -188,12 +486,9 @@ impl MCPHandlers {
             return Ok(json!({ "result": entry }));
         }
 
-        if let Some(search) = params.search {
-            let results = CryptexDictionary::search(&search)?;
-            return Ok(json!({ "results": results, "count": results.len() }));
-        }
-
-        if let Some(category_str) = params.category {
+        let mut results = if let Some(search) = params.search {
+            CryptexDictionary::search(&search)?
+        } else if let Some(category_str) = params.category {
             let category = match category_str.as_str() {
                 "Exploit" => CryptexCategory::Exploit,
                 "Scanner" => CryptexCategory::Scanner,
@@ -203,13 +498,19 @@ impl MCPHandlers {
                 "Utility" => CryptexCategory::Utility,
                 _ => return Err(anyhow!("Invalid category")),
             };
-            let results = CryptexDictionary::list_by_category(category)?;
-            return Ok(json!({ "results": results, "count": results.len() }));
-        }
+            CryptexDictionary::list_by_category(category)?
+        } else {
+            // List all if no specific query
+            CryptexDictionary::list_all()?
+        };
 
-        // List all if no specific query
-        let all = CryptexDictionary::list_all()?;
-        Ok(json!({ "results": all, "count": all.len() }))
+        if let Some(tag) = params.tag {
+            results.retain(|entry| entry.has_tag(&tag));
+        }
+        if let Some(since) = params.updated_after {
+            results.retain(|entry| entry.updated_at > since);
+        }
+        Ok(json!({ "results": results, "count": results.len() }))
     }
 
     /// Handle cryptex add entry
@@ -224,7 +525,7 @@ impl MCPHandlers {
             _ => return Err(anyhow!("Invalid category")),
         };
 
-        let entry = CryptexDictionary::add_entry_with_impl(
+        let mut entry = CryptexDictionary::add_entry_with_impl(
             params.function_name,
             params.branding_name,
             params.pseudo_code,
@@ -232,6 +533,9 @@ impl MCPHandlers {
             params.rust_impl,
             params.python_impl,
         )?;
+        if !params.tags.is_empty() {
+            entry = CryptexDictionary::set_tags(&entry.id, &params.tags)?;
+        }
 
         Ok(json!({
             "success": true,
@@ -240,36 +544,303 @@ impl MCPHandlers {
         }))
     }
 
+    /// Handle cryptex delete entry
+    pub fn handle_cryptex_delete(params: CryptexDeleteParams) -> Result<Value> {
+        let entry = if let Some(id) = &params.id {
+            let id = Uuid::parse_str(id).map_err(|_| anyhow!("Invalid id: {}", id))?;
+            crate::db::redb_client::cryptex::get_by_id(&id)?.filter(|entry| entry.deleted_at.is_none())
+        } else if let Some(function_name) = &params.function_name {
+            CryptexDictionary::lookup_function(function_name)?
+        } else if let Some(branding_name) = &params.branding_name {
+            CryptexDictionary::lookup_branding(branding_name)?
+        } else {
+            return Err(anyhow!("One of id, function_name or branding_name is required"));
+        };
+
+        match entry {
+            Some(entry) => {
+                CryptexDictionary::delete(&entry.id)?;
+                Ok(json!({
+                    "success": true,
+                    "deleted": true,
+                    "entry": entry,
+                    "message": "Cryptex entry deleted"
+                }))
+            }
+            None => Ok(json!({
+                "success": true,
+                "deleted": false,
+                "message": "No matching cryptex entry"
+            })),
+        }
+    }
+
     /// Handle list exploits
-    pub fn handle_list_exploits() -> Result<Value> {
+    pub fn handle_list_exploits(params: ListExploitsParams) -> Result<Value> {
         use crate::db::redb_client::exploits;
 
-        let exploits = exploits::list_all()?;
+        let mut exploits = match params.target_platform.as_deref().filter(|p| !p.trim().is_empty()) {
+            Some(device) => exploits::list_by_device(device)?,
+            None => exploits::list_all()?,
+        };
+        if let Some(since) = params.updated_after {
+            exploits.retain(|metadata| metadata.updated_at > since);
+        }
+        if let Some(verified) = params.verified {
+            exploits.retain(|metadata| metadata.verified == verified);
+        }
         Ok(json!({
             "exploits": exploits,
             "count": exploits.len()
         }))
     }
 
+    /// Resolve the cryptex entry naming the exploit's Rust implementation
+    fn resolve_exploit_entry(params: &ExploitRunParams) -> Result<CryptexEntry> {
+        use crate::db::redb_client::{cryptex, exploits};
+
+        if let Some(branding_name) = &params.branding_name {
+            return CryptexDictionary::lookup_branding(branding_name)?
+                .ok_or_else(|| anyhow!("Unknown exploit: {}", branding_name));
+        }
+
+        let exploit_id = params
+            .exploit_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("Either exploit_id or branding_name is required"))?;
+
+        let Ok(id) = Uuid::parse_str(exploit_id) else {
+            return CryptexDictionary::lookup_function(exploit_id)?
+                .ok_or_else(|| anyhow!("Unknown exploit: {}", exploit_id));
+        };
+
+        if let Some((_, entry)) = exploits::with_cryptex(&id)? {
+            return Ok(entry);
+        }
+        if let Some(metadata) = exploits::get_by_id(&id)? {
+            return Err(anyhow!("Exploit {} is not linked to a cryptex entry", metadata.name));
+        }
+
+        cryptex::get_by_id(&id)?.ok_or_else(|| anyhow!("Unknown exploit: {}", exploit_id))
+    }
+
     /// Handle run exploit
-    pub fn handle_run_exploit(params: ExploitRunParams) -> Result<Value> {
-        // This is a placeholder - actual implementation would execute the exploit
+    pub async fn handle_run_exploit(params: ExploitRunParams) -> Result<Value> {
+        let entry = Self::resolve_exploit_entry(&params)?;
+        let exploit = registry::for_entry(&entry)
+            .map(|registration| (registration.constructor)())
+            .ok_or_else(|| anyhow!("Exploit {} has no Rust implementation", entry.branding_name))?;
+
+        let target = Target::parse(&params.target)?;
+        let addresses = crate::core::scope::enforce(&params.target).await?;
+        let options = match params.options.clone() {
+            None | Some(Value::Null) => Options::new(),
+            Some(Value::Object(options)) => options,
+            Some(_) => return Err(RpcError::InvalidParams("options must be an object".to_string()).into()),
+        };
+        // Only `run` takes options, so a check isn't held up by missing ones
+        let options = match params.mode {
+            ExploitMode::Exploit => ExploitOptions::validate(&options, &exploit.options_schema())
+                .map_err(|e| RpcError::InvalidParams(format!("{:#}", e)))?
+                .to_options(),
+            ExploitMode::Check => options,
+        };
+
+        if params.dry_run {
+            let metadata = exploit.metadata();
+            let action = match params.mode {
+                ExploitMode::Check => format!("Check whether {} is vulnerable to {}", target, metadata.name),
+                ExploitMode::Exploit => format!("Run {} with {} option(s)", metadata.name, options.len()),
+            };
+            return Ok(json!({
+                "success": true,
+                "dry_run": true,
+                "mode": params.mode,
+                "exploit": metadata.name,
+                "branding_name": entry.branding_name,
+                "target": params.target,
+                "plan": {
+                    "protocol": metadata.protocol,
+                    "options": options,
+                    "actions": [
+                        format!("Connect to {} over {:?}", target, metadata.protocol),
+                        action,
+                    ]
+                }
+            }));
+        }
+
+        let settings = crate::db::redb_client::config::load()?;
+        let tls = net::TlsOptions { sni: params.sni.clone(), ..settings.tls() };
+        let target = target
+            .with_proxy(settings.proxy()?)
+            .with_tls(tls)
+            .with_dns(settings.dns()?)
+            .with_addresses(addresses);
+        let timeout = call_timeout(&options, settings.timeout_seconds);
+        // Exploits without a known port, like UDP ones, go straight ahead
+        let port = target.port.or(net::default_port(&exploit.metadata().protocol));
+        if let Some(port) = port.filter(|_| settings.precheck_timeout_secs > 0) {
+            let precheck_timeout = Duration::from_secs(settings.precheck_timeout_secs).min(timeout);
+            if let Err(e) = net::precheck(&target, port, precheck_timeout).await {
+                tracing::info!("Not running {}: {}", entry.branding_name, e);
+                return Ok(json!({
+                    "success": false,
+                    "unreachable": true,
+                    "mode": params.mode,
+                    "exploit": exploit.metadata().name,
+                    "branding_name": entry.branding_name,
+                    "target": params.target,
+                    "output": e.to_string()
+                }));
+            }
+        }
+
+        let timed_out = || {
+            json!({
+                "success": false,
+                "timeout": true,
+                "mode": params.mode,
+                "exploit": exploit.metadata().name,
+                "branding_name": entry.branding_name,
+                "target": params.target,
+                "output": format!("No response from {} within {}s", target, timeout.as_secs())
+            })
+        };
+
+        if params.mode == ExploitMode::Check {
+            tracing::info!("Checking {} against {}", entry.branding_name, target);
+            let outcome = match bounded(exploit.check_with_proof(&target), timeout, &CancellationToken::new()).await? {
+                Bounded::Completed(outcome) => outcome,
+                Bounded::Timeout | Bounded::Cancelled => return Ok(timed_out()),
+            };
+            return Ok(json!({
+                "success": true,
+                "mode": params.mode,
+                "exploit": exploit.metadata().name,
+                "branding_name": entry.branding_name,
+                "target": params.target,
+                "vulnerable": outcome.vulnerable,
+                "proof": outcome.proof
+            }));
+        }
+
+        tracing::info!("Running exploit {} against {}", entry.branding_name, target);
+        let result = match bounded(exploit.run(&target, &options), timeout, &CancellationToken::new()).await? {
+            Bounded::Completed(result) => result,
+            Bounded::Timeout | Bounded::Cancelled => return Ok(timed_out()),
+        };
+
         Ok(json!({
-            "success": true,
-            "message": "Exploit execution not yet fully implemented",
+            "success": result.success,
+            "mode": params.mode,
+            "exploit": exploit.metadata().name,
+            "branding_name": entry.branding_name,
             "target": params.target,
-            "note": "This is a framework stub - will be implemented with full exploit engine"
+            "output": result.output,
+            "proof": result.proof
+        }))
+    }
+
+    /// Scan request and scanner settings for `params`, from the stored config
+    pub fn scan_job(params: ScanParams) -> Result<(ScanRequest, crate::core::scanner::ScannerConfig)> {
+        let settings = crate::db::redb_client::config::load()?;
+        let config = crate::core::scanner::ScannerConfig {
+            threads: params.threads.unwrap_or(10),
+            timeout: settings.timeout_seconds,
+            rate_limit: settings.scan_rate_limit()?,
+            precheck_timeout: settings.precheck_timeout_secs,
+            proxy: settings.proxy()?,
+            tls: settings.tls(),
+            dns: settings.dns()?,
+        };
+        let scan_type = params.scan_type.unwrap_or_else(|| "autopwn".to_string());
+        let request = ScanRequest {
+            callbacks: params.callbacks,
+            dry_run: params.dry_run,
+            sni: params.sni,
+            rate_limit: params.rate_limit,
+            ..ScanRequest::new(params.target, scan_type)
+        };
+        Ok((request, config))
+    }
+
+    /// Handle scan target, queueing the scan on the shared worker pool
+    pub async fn handle_scan_target(params: ScanParams) -> Result<Value> {
+        Self::scan_target(params, jobs::shared()).await
+    }
+
+    /// Plan the scan, or queue it on `queue`
+    pub(crate) async fn scan_target(params: ScanParams, queue: Option<JobQueue>) -> Result<Value> {
+        use crate::core::scanner;
+
+        let (request, config) = Self::scan_job(params)?;
+        if request.dry_run {
+            let plan = scanner::plan(&request, &config).await?;
+            return Ok(json!({
+                "success": true,
+                "dry_run": true,
+                "plan": plan
+            }));
+        }
+        let queue = queue.ok_or_else(|| anyhow!("Scan queue is not running"))?;
+        let scan = queue.enqueue(request, config).await?;
+
+        Ok(json!({
+            "success": true,
+            "message": "Scan queued",
+            "scan_id": scan.id,
+            "status": scan.status,
+            "target": scan.target,
+            "scan_type": scan.scan_type
+        }))
+    }
+
+    /// Load a scan the MCP server may see. It isn't tenant-scoped, so like a
+    /// REST caller of one tenant it never sees another tenant's scans, and
+    /// only sees those with no tenant.
+    fn load_scan(scan_id: &str) -> Result<ScanResult> {
+        use crate::db::redb_client::scans;
+
+        let id = Uuid::parse_str(scan_id).map_err(|_| anyhow!("Invalid scan_id: {}", scan_id))?;
+        scans::get_by_id(&id)?
+            .filter(|scan| scan.tenant_id.is_none())
+            .ok_or_else(|| anyhow!("Scan not found: {}", id))
+    }
+
+    /// Handle scan status
+    pub fn handle_scan_status(params: ScanStatusParams) -> Result<Value> {
+        let scan = Self::load_scan(&params.scan_id)?;
+
+        Ok(json!({
+            "scan_id": scan.id,
+            "status": scan.status,
+            "target": scan.target,
+            "scan_type": scan.scan_type,
+            "started_at": scan.started_at,
+            "completed_at": scan.completed_at,
+            "vulnerabilities_found": scan.vulnerabilities.len(),
+            "risk_score": scan.risk_score,
+            "vulnerabilities": scan.vulnerabilities
         }))
     }
 
-    /// Handle scan target
-    pub fn handle_scan_target(params: ScanParams) -> Result<Value> {
+    /// Handle cancel scan
+    pub fn handle_cancel_scan(params: ScanStatusParams) -> Result<Value> {
+        use crate::core::scanner;
+        use crate::db::redb_client::scans;
+
+        let scan = Self::load_scan(&params.scan_id)?;
+        if !scanner::cancel(&scan.id)? {
+            return Err(anyhow!("Scan {} already finished", scan.id));
+        }
+        let scan = scans::get_by_id(&scan.id)?.unwrap_or(scan);
+
         Ok(json!({
             "success": true,
-            "message": "Scan initiated",
-            "target": params.target,
-            "scan_type": params.scan_type.unwrap_or("autopwn".to_string()),
-            "note": "Scanner engine will be fully implemented in next phase"
+            "message": "Scan cancelled",
+            "scan_id": scan.id,
+            "status": scan.status,
         }))
     }
 
@@ -277,44 +848,73 @@ impl MCPHandlers {
     pub fn handle_multi_hash(params: HashParams) -> Result<Value> {
         if params.all_algorithms {
             let results = MultiHasher::hash_all(params.data.as_bytes())?;
-            let hashes: Vec<_> = results.iter().map(|r| {
-                json!({
-                    "algorithm": r.algorithm.to_string(),
-                    "hash": r.hex
-                })
-            }).collect();
-            return Ok(json!({
+            let hashes: Vec<HashOutput> = results.iter().map(HashOutput::from).collect();
+            let mut response = json!({
                 "data": params.data,
                 "hashes": hashes,
                 "count": hashes.len()
-            }));
-        }
-
-        let algorithm = if let Some(algo_str) = params.algorithm {
-            match algo_str.to_uppercase().as_str() {
-                "SHA224" => HashAlgorithm::SHA224,
-                "SHA256" => HashAlgorithm::SHA256,
-                "SHA384" => HashAlgorithm::SHA384,
-                "SHA512" => HashAlgorithm::SHA512,
-                "SHA3_256" => HashAlgorithm::SHA3_256,
-                "SHA3_512" => HashAlgorithm::SHA3_512,
-                "BLAKE2B" => HashAlgorithm::BLAKE2b,
-                "BLAKE2S" => HashAlgorithm::BLAKE2s,
-                "BLAKE3" => HashAlgorithm::BLAKE3,
-                "MD5" => HashAlgorithm::MD5,
-                "RIPEMD160" => HashAlgorithm::RIPEMD160,
-                _ => HashAlgorithm::SHA256,
+            });
+            if let Some(note) = HashOutput::xof_note(&results) {
+                response["note"] = json!(note);
             }
-        } else {
-            HashAlgorithm::SHA256
-        };
+            return Ok(response);
+        }
 
+        let algorithm = Self::hash_algorithm(params.algorithm.as_deref());
         let result = MultiHasher::hash(algorithm, params.data.as_bytes())?;
-        Ok(json!({
+        let output = HashOutput::from(&result);
+        let mut response = json!({
             "data": params.data,
-            "algorithm": result.algorithm.to_string(),
-            "hash": result.hex
-        }))
+            "algorithm": output.algorithm,
+            "hash": output.hash,
+            "bytes": output.bytes
+        });
+        if let Some(note) = HashOutput::xof_note(std::slice::from_ref(&result)) {
+            response["note"] = json!(note);
+        }
+        Ok(response)
+    }
+
+    /// Handle multi-hash over several inputs, results in input order
+    pub fn handle_multi_hash_batch(params: HashBatchParams) -> Result<Value> {
+        let algorithm = Self::hash_algorithm(params.algorithm.as_deref());
+        let results = params
+            .inputs
+            .iter()
+            .map(|input| MultiHasher::hash(algorithm, input.as_bytes()))
+            .collect::<Result<Vec<_>>>()?;
+        let hashes: Vec<HashOutput> = results.iter().map(HashOutput::from).collect();
+        let mut response = json!({
+            "algorithm": algorithm.to_string(),
+            "hashes": hashes,
+            "count": hashes.len()
+        });
+        // Every input uses the same algorithm, so one result is enough for the note
+        if let Some(note) = results.first().and_then(|r| HashOutput::xof_note(std::slice::from_ref(r))) {
+            response["note"] = json!(note);
+        }
+        Ok(response)
+    }
+
+    /// Algorithm named in hash tool arguments, SHA256 when absent or unknown
+    fn hash_algorithm(name: Option<&str>) -> HashAlgorithm {
+        match name.map(str::to_uppercase).as_deref() {
+            Some("SHA224") => HashAlgorithm::SHA224,
+            Some("SHA256") => HashAlgorithm::SHA256,
+            Some("SHA384") => HashAlgorithm::SHA384,
+            Some("SHA512") => HashAlgorithm::SHA512,
+            Some("SHA3_256") => HashAlgorithm::SHA3_256,
+            Some("SHA3_512") => HashAlgorithm::SHA3_512,
+            Some("SHAKE128") => HashAlgorithm::SHAKE128,
+            Some("SHAKE256") => HashAlgorithm::SHAKE256,
+            Some("BLAKE2B") => HashAlgorithm::BLAKE2b,
+            Some("BLAKE2S") => HashAlgorithm::BLAKE2s,
+            Some("BLAKE3") => HashAlgorithm::BLAKE3,
+            Some("MD5") => HashAlgorithm::MD5,
+            Some("SHA1") => HashAlgorithm::SHA1,
+            Some("RIPEMD160") => HashAlgorithm::RIPEMD160,
+            _ => HashAlgorithm::SHA256,
+        }
     }
 
     /// Handle QKD encryption
@@ -329,9 +929,622 @@ impl MCPHandlers {
         Ok(json!({
             "success": true,
             "ciphertext": ciphertext_hex,
-            "session_id": qkd.session_id,
+            "session_id": qkd.session_id(),
             "key_size": key_size,
             "message": "Data encrypted with QKD-derived key"
         }))
     }
+
+    /// Handle QKD decryption
+    pub fn handle_qkd_decrypt(params: QKDDecryptParams) -> Result<Value> {
+        let session_id = Uuid::parse_str(&params.session_id)
+            .map_err(|_| anyhow!("Invalid session_id: {}", params.session_id))?;
+        let ciphertext = hex::decode(params.ciphertext.trim())
+            .map_err(|e| anyhow!("Ciphertext must be hex: {}", e))?;
+
+        let qkd = QKDEncryption::load_session(&session_id)?;
+        let plaintext = qkd.decrypt(&ciphertext)?;
+
+        Ok(json!({
+            "success": true,
+            "session_id": session_id,
+            "plaintext": String::from_utf8(plaintext.clone()).ok(),
+            "plaintext_hex": hex::encode(&plaintext)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exploit::test_support::{self, MockExploit};
+    use crate::core::exploit::Exploit;
+    use crate::db::models::Protocol;
+
+    fn dummy_exploit() -> Box<dyn Exploit> {
+        MockExploit::new("Dummy RCE").protocol(Protocol::HTTP).boxed()
+    }
+
+    #[tokio::test]
+    async fn test_run_exploit_dispatches_to_registry() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        // The targets don't exist, so don't wait on them answering
+        crate::db::redb_client::config::set("precheck_timeout_secs", "0").unwrap();
+        // Registered under other names, so the entry can only reach it through rust_impl
+        registry::register_impl("crate::core::exploit::dummy::rce", "exploit_dummy_impl", "pyroutersploit_dummy_impl", dummy_exploit);
+        CryptexDictionary::add_entry_with_impl(
+            "exploit_dummy_rce",
+            "pyroutersploit_dummy_pwn",
+            "Dummy exploit",
+            CryptexCategory::Exploit,
+            Some("crate::core::exploit::dummy::rce".to_string()),
+            None,
+        )
+        .unwrap();
+
+        let result = MCPHandlers::handle_run_exploit(ExploitRunParams {
+            exploit_id: None,
+            branding_name: Some("pyroutersploit_dummy_pwn".to_string()),
+            target: "192.168.1.1".to_string(),
+            options: Some(json!({ "marker": "uid=0(root)" })),
+            mode: ExploitMode::Exploit,
+            dry_run: false,
+            sni: None,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result["success"], true);
+        assert_eq!(result["output"], "Executed against 192.168.1.1");
+        assert_eq!(result["proof"], r#"{"marker":"uid=0(root)"}"#);
+
+        // Function names resolve too
+        let result = MCPHandlers::handle_run_exploit(ExploitRunParams {
+            exploit_id: Some("exploit_dummy_rce".to_string()),
+            branding_name: None,
+            target: "10.0.0.1".to_string(),
+            options: None,
+            mode: ExploitMode::Exploit,
+            dry_run: false,
+            sni: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(result["exploit"], "Dummy RCE");
+    }
+
+    const PAYLOAD_TARGET: &str = "198.51.100.88";
+
+    #[tokio::test]
+    async fn test_check_mode_reports_without_running_payload() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        registry::register("exploit_payload", "pyroutersploit_payload", || {
+            MockExploit::new("Payload").vulnerable_on(PAYLOAD_TARGET).boxed()
+        });
+        CryptexDictionary::add_entry_with_impl(
+            "exploit_payload",
+            "pyroutersploit_payload",
+            "Payload exploit",
+            CryptexCategory::Exploit,
+            None,
+            None,
+        )
+        .unwrap();
+
+        // No mode given means check
+        let params: ExploitRunParams =
+            serde_json::from_value(json!({ "exploit_id": "exploit_payload", "target": PAYLOAD_TARGET })).unwrap();
+        assert_eq!(params.mode, ExploitMode::Check);
+        let result = MCPHandlers::handle_run_exploit(params.clone()).await.unwrap();
+        assert_eq!(result["mode"], "check");
+        assert_eq!(result["vulnerable"], true);
+        assert!(result.get("output").is_none());
+
+        let result = MCPHandlers::handle_run_exploit(ExploitRunParams {
+            target: "198.51.100.89".to_string(),
+            ..params.clone()
+        })
+        .await
+        .unwrap();
+        assert_eq!(result["vulnerable"], false);
+        assert_eq!(test_support::runs("Payload"), 0);
+
+        let result = MCPHandlers::handle_run_exploit(ExploitRunParams { mode: ExploitMode::Exploit, ..params })
+            .await
+            .unwrap();
+        assert_eq!(result["mode"], "exploit");
+        assert_eq!(result["output"], format!("Executed against {}", PAYLOAD_TARGET));
+        assert!(result.get("vulnerable").is_none());
+        assert_eq!(test_support::runs("Payload"), 1);
+    }
+
+    const SPY_TARGET: &str = "198.51.100.77";
+
+    #[tokio::test]
+    async fn test_dry_run_plans_without_contacting_target() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        registry::register("exploit_spy", "pyroutersploit_spy", || {
+            MockExploit::new("Spy").protocol(Protocol::HTTP).vulnerable(false).boxed()
+        });
+        CryptexDictionary::add_entry_with_impl(
+            "exploit_spy",
+            "pyroutersploit_spy",
+            "Spy exploit",
+            CryptexCategory::Exploit,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let result = MCPHandlers::handle_run_exploit(ExploitRunParams {
+            exploit_id: Some("exploit_spy".to_string()),
+            branding_name: None,
+            target: format!("{}:8080", SPY_TARGET),
+            options: Some(json!({ "cmd": "id" })),
+            mode: ExploitMode::Exploit,
+            dry_run: true,
+            sni: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(result["dry_run"], true);
+        assert_eq!(result["plan"]["options"]["cmd"], "id");
+        assert_eq!(result["plan"]["actions"][0], format!("Connect to {}:8080 over HTTP", SPY_TARGET));
+
+        let result = MCPHandlers::handle_scan_target(ScanParams {
+            target: SPY_TARGET.to_string(),
+            scan_type: Some("http".to_string()),
+            threads: Some(2),
+            callbacks: Default::default(),
+            dry_run: true,
+            sni: None,
+            rate_limit: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(result["dry_run"], true);
+        assert!(result["plan"]["exploits"].as_array().unwrap().contains(&json!("Spy")));
+        assert!(result.get("scan_id").is_none());
+
+        // Invalid input is still rejected
+        assert!(MCPHandlers::handle_scan_target(ScanParams {
+            target: " ".to_string(),
+            scan_type: None,
+            threads: None,
+            callbacks: Default::default(),
+            dry_run: true,
+            sni: None,
+            rate_limit: None,
+        })
+        .await
+        .is_err());
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(test_support::checks("Spy") + test_support::runs("Spy"), 0);
+        assert!(crate::db::redb_client::scans::list_all().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_target_rate_limit() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        assert!(crate::db::redb_client::config::set("scan_rate_limit", "-1").is_err());
+        crate::db::redb_client::config::set("scan_rate_limit", "2.5").unwrap();
+
+        let params = ScanParams {
+            target: "192.0.2.70".to_string(),
+            scan_type: Some("http".to_string()),
+            threads: Some(1),
+            callbacks: Default::default(),
+            dry_run: true,
+            sni: None,
+            rate_limit: None,
+        };
+        let paced = |plan: &Value, rate: &str| {
+            plan["plan"]["actions"].as_array().unwrap().iter().any(|action| {
+                action.as_str().unwrap().ends_with(&format!("at most {} per second", rate))
+            })
+        };
+        let result = MCPHandlers::handle_scan_target(params.clone()).await.unwrap();
+        assert!(paced(&result, "2.5"));
+
+        // The scan's own limit wins over the setting
+        let result = MCPHandlers::handle_scan_target(ScanParams { rate_limit: Some(1.0), ..params.clone() }).await.unwrap();
+        assert!(paced(&result, "1"));
+        assert!(MCPHandlers::handle_scan_target(ScanParams { rate_limit: Some(0.0), ..params }).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scan_target_creates_scan_record() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        crate::db::redb_client::config::set("precheck_timeout_secs", "0").unwrap();
+
+        let params = ScanParams {
+            target: "192.168.1.1".to_string(),
+            scan_type: Some("http".to_string()),
+            threads: Some(2),
+            callbacks: Default::default(),
+            dry_run: false,
+            sni: None,
+            rate_limit: None,
+        };
+        let err = MCPHandlers::scan_target(params.clone(), None).await.unwrap_err();
+        assert_eq!(err.to_string(), "Scan queue is not running");
+
+        let result = MCPHandlers::scan_target(params, Some(JobQueue::start(1, 4))).await.unwrap();
+        let scan_id = result["scan_id"].as_str().unwrap().to_string();
+        assert_eq!(result["status"], "Queued");
+
+        let status = MCPHandlers::handle_scan_status(ScanStatusParams { scan_id: scan_id.clone() }).unwrap();
+        assert_eq!(status["target"], "192.168.1.1");
+        assert_eq!(status["scan_type"], "http");
+
+        // The background scan finishes and updates the record
+        for _ in 0..100 {
+            let status = MCPHandlers::handle_scan_status(ScanStatusParams { scan_id: scan_id.clone() }).unwrap();
+            if status["status"] == "Completed" {
+                assert!(!status["completed_at"].is_null());
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("scan did not complete");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_scan() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        crate::db::redb_client::config::set("precheck_timeout_secs", "0").unwrap();
+        registry::register("exploit_hanging", "pyroutersploit_hanging", || {
+            MockExploit::new("Hanging").delay(Duration::from_secs(30)).boxed()
+        });
+
+        // Nothing listens, so fingerprinting fails fast and the check hangs
+        let params = ScanParams {
+            target: "127.0.0.1:9".to_string(),
+            scan_type: Some("all".to_string()),
+            threads: Some(1),
+            callbacks: Default::default(),
+            dry_run: false,
+            sni: None,
+            rate_limit: None,
+        };
+        let result = MCPHandlers::scan_target(params, Some(JobQueue::start(1, 4))).await.unwrap();
+        let scan_id = result["scan_id"].as_str().unwrap().to_string();
+
+        let result = MCPHandlers::handle_cancel_scan(ScanStatusParams { scan_id: scan_id.clone() }).unwrap();
+        assert_eq!(result["success"], true);
+        for _ in 0..100 {
+            let status = MCPHandlers::handle_scan_status(ScanStatusParams { scan_id: scan_id.clone() }).unwrap();
+            if status["status"] == "Cancelled" {
+                let err = MCPHandlers::handle_cancel_scan(ScanStatusParams { scan_id }).unwrap_err();
+                assert!(err.to_string().contains("already finished"));
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("scan was not cancelled");
+    }
+
+    #[tokio::test]
+    async fn test_tenant_scans_are_hidden() {
+        use crate::db::models::test_support::scan;
+        use crate::db::redb_client::scans;
+
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let tenant_scan = ScanResult { tenant_id: Some("tenant-a".to_string()), ..scan("192.0.2.70", Vec::new()) };
+        let own_scan = scan("192.0.2.71", Vec::new());
+        scans::insert(&tenant_scan).unwrap();
+        scans::insert(&own_scan).unwrap();
+
+        let status = MCPHandlers::handle_scan_status(ScanStatusParams { scan_id: own_scan.id.to_string() }).unwrap();
+        assert_eq!(status["target"], "192.0.2.71");
+        let err = MCPHandlers::handle_scan_status(ScanStatusParams { scan_id: tenant_scan.id.to_string() }).unwrap_err();
+        assert!(err.to_string().starts_with("Scan not found"));
+        let err = MCPHandlers::handle_cancel_scan(ScanStatusParams { scan_id: tenant_scan.id.to_string() }).unwrap_err();
+        assert!(err.to_string().starts_with("Scan not found"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_target_rejects_unknown_scan_type() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let params = ScanParams {
+            target: "192.168.1.1".to_string(),
+            scan_type: Some("nmap".to_string()),
+            threads: None,
+            callbacks: Default::default(),
+            dry_run: false,
+            sni: None,
+            rate_limit: None,
+        };
+        let err = MCPHandlers::scan_target(params, Some(JobQueue::start(1, 4))).await.unwrap_err();
+        assert!(err.to_string().contains("Unsupported scan type"));
+    }
+
+    #[tokio::test]
+    async fn test_cryptex_delete_by_function_name() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        CryptexDictionary::add_entry(
+            "scanner_stale_entry",
+            "pyroutersploit_stale_scanner",
+            "Entry added by mistake",
+            CryptexCategory::Scanner,
+        )
+        .unwrap();
+
+        let params = CryptexDeleteParams {
+            id: None,
+            function_name: Some("scanner_stale_entry".to_string()),
+            branding_name: None,
+        };
+        let result = MCPHandlers::handle_cryptex_delete(params.clone()).unwrap();
+        assert_eq!(result["deleted"], true);
+        assert!(CryptexDictionary::lookup_function("scanner_stale_entry").unwrap().is_none());
+
+        // Deleting again reports nothing removed
+        let result = MCPHandlers::handle_cryptex_delete(params).unwrap();
+        assert_eq!(result["deleted"], false);
+    }
+
+    #[tokio::test]
+    async fn test_cryptex_query_by_tag() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let add = |function_name: &str, category: &str, tags: &[&str]| {
+            MCPHandlers::handle_cryptex_add(CryptexAddParams {
+                function_name: function_name.to_string(),
+                branding_name: format!("pyroutersploit_{}", function_name),
+                pseudo_code: String::new(),
+                category: category.to_string(),
+                rust_impl: None,
+                python_impl: None,
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            })
+            .unwrap()
+        };
+        let added = add("tagged_arm_rce", "Exploit", &["ARM", "cve-2023", "arm"]);
+        assert_eq!(added["entry"]["tags"], json!(["arm", "cve-2023"]));
+        add("tagged_arm_creds", "Credential", &["arm", "authenticated"]);
+        add("tagged_mips_rce", "Exploit", &["mips", "cve-2023"]);
+
+        let query = |category: Option<&str>, tag: &str| {
+            let result = MCPHandlers::handle_cryptex_query(CryptexQueryParams {
+                function_name: None,
+                branding_name: None,
+                search: None,
+                category: category.map(str::to_string),
+                tag: Some(tag.to_string()),
+                updated_after: None,
+            })
+            .unwrap();
+            let mut names: Vec<String> = result["results"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| entry["function_name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(query(None, "arm"), ["tagged_arm_creds", "tagged_arm_rce"]);
+        assert_eq!(query(None, "CVE-2023"), ["tagged_arm_rce", "tagged_mips_rce"]);
+        assert_eq!(query(Some("Exploit"), "arm"), ["tagged_arm_rce"]);
+        assert_eq!(CryptexDictionary::list_by_tag("cve-2023").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_qkd_encrypt_decrypt_round_trip() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let encrypted = MCPHandlers::handle_qkd_encrypt(QKDEncryptParams {
+            data: "router admin password".to_string(),
+            key_size: None,
+        })
+        .unwrap();
+
+        let decrypted = MCPHandlers::handle_qkd_decrypt(QKDDecryptParams {
+            session_id: encrypted["session_id"].as_str().unwrap().to_string(),
+            ciphertext: encrypted["ciphertext"].as_str().unwrap().to_string(),
+        })
+        .unwrap();
+        assert_eq!(decrypted["plaintext"], "router admin password");
+    }
+
+    #[tokio::test]
+    async fn test_qkd_decrypt_rejects_expired_session() {
+        use crate::db::models::QKDSession;
+        use chrono::{Duration, Utc};
+
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let session = QKDSession {
+            id: Uuid::new_v4(),
+            algorithm: "BB84-Hybrid".to_string(),
+            key_material: vec![7u8; 32],
+            created_at: Utc::now() - Duration::hours(48),
+            expires_at: Some(Utc::now() - Duration::hours(24)),
+            previous_session_id: None,
+        };
+        crate::db::redb_client::qkd_sessions::insert(&session).unwrap();
+
+        let err = MCPHandlers::handle_qkd_decrypt(QKDDecryptParams {
+            session_id: session.id.to_string(),
+            ciphertext: "00".repeat(32),
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_read_resources() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        CryptexDictionary::add_entry_with_impl(
+            "exploit_dlink_hnap_rce",
+            "pyroutersploit_dlink_hnap_pwn",
+            "Send crafted SOAPAction header",
+            CryptexCategory::Exploit,
+            None,
+            Some("routersploit.modules.exploits.routers.dlink.hnap_rce".to_string()),
+        )
+        .unwrap();
+
+        let listed = MCPHandlers::list_resources().unwrap();
+        let uris: Vec<&str> = listed["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["uri"].as_str().unwrap())
+            .collect();
+        assert!(uris.contains(&"cryptex://branding/pyroutersploit_dlink_hnap_pwn"));
+
+        let read = MCPHandlers::read_resource("cryptex://branding/pyroutersploit_dlink_hnap_pwn").unwrap();
+        assert_eq!(read["contents"][0]["mimeType"], "application/json");
+        let entry: CryptexEntry =
+            serde_json::from_str(read["contents"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(entry.function_name, "exploit_dlink_hnap_rce");
+
+        assert!(MCPHandlers::read_resource("cryptex://branding/missing").is_err());
+        assert!(MCPHandlers::read_resource("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_list_prompts_declares_arguments() {
+        let listed = MCPHandlers::list_prompts().unwrap();
+        let prompts = listed["prompts"].as_array().unwrap();
+        assert!(!prompts.is_empty());
+
+        for prompt in prompts {
+            assert!(prompt["name"].is_string());
+            let arguments = prompt["arguments"].as_array().unwrap();
+            assert!(arguments.iter().any(|a| a["required"] == true));
+            for argument in arguments {
+                assert!(argument["name"].is_string());
+                assert!(argument["description"].is_string());
+                assert!(argument["required"].is_boolean());
+            }
+        }
+
+        let rendered = MCPHandlers::get_prompt(PromptGetParams {
+            name: "triage_target".to_string(),
+            arguments: [("target".to_string(), "192.168.1.1".to_string())].into(),
+        })
+        .unwrap();
+        assert_eq!(rendered["messages"][0]["role"], "user");
+        assert!(rendered["messages"][0]["content"]["text"]
+            .as_str()
+            .unwrap()
+            .contains("192.168.1.1"));
+
+        assert!(MCPHandlers::get_prompt(PromptGetParams {
+            name: "triage_target".to_string(),
+            arguments: Default::default(),
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_exploit_unknown_identifier() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+
+        let err = MCPHandlers::handle_run_exploit(ExploitRunParams {
+            exploit_id: None,
+            branding_name: Some("pyroutersploit_does_not_exist".to_string()),
+            target: "192.168.1.1".to_string(),
+            options: None,
+            mode: ExploitMode::Check,
+            dry_run: false,
+            sni: None,
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Unknown exploit"));
+    }
+
+    #[tokio::test]
+    async fn test_run_exploit_prechecks_target() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        registry::register("exploit_unreachable", "pyroutersploit_unreachable", || {
+            MockExploit::new("Unreachable").protocol(Protocol::HTTP).boxed()
+        });
+        CryptexDictionary::add_entry_with_impl(
+            "exploit_unreachable",
+            "pyroutersploit_unreachable",
+            "Unreachable exploit",
+            CryptexCategory::Exploit,
+            None,
+            None,
+        )
+        .unwrap();
+
+        // Bound and released, so nothing listens there
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        for mode in [ExploitMode::Check, ExploitMode::Exploit] {
+            let result = MCPHandlers::handle_run_exploit(ExploitRunParams {
+                exploit_id: Some("exploit_unreachable".to_string()),
+                branding_name: None,
+                target: format!("127.0.0.1:{}", closed),
+                options: None,
+                mode,
+                dry_run: false,
+                sni: None,
+            })
+            .await
+            .unwrap();
+            assert_eq!(result["success"], false);
+            assert_eq!(result["unreachable"], true);
+            assert!(result["output"].as_str().unwrap().starts_with("Host unreachable"), "{}", result["output"]);
+        }
+        assert_eq!(test_support::checks("Unreachable") + test_support::runs("Unreachable"), 0);
+    }
+
+    #[test]
+    fn test_multi_hash_reports_byte_lengths() {
+        let result = MCPHandlers::handle_multi_hash(HashParams {
+            data: "abc".to_string(),
+            algorithm: None,
+            all_algorithms: true,
+        })
+        .unwrap();
+        for hash in result["hashes"].as_array().unwrap() {
+            let hex_len = hash["hash"].as_str().unwrap().len();
+            assert_eq!(hash["bytes"].as_u64().unwrap() as usize, hex_len / 2, "{}", hash["algorithm"]);
+        }
+        let note = result["note"].as_str().unwrap();
+        assert!(note.contains("SHAKE128 (32 bytes)") && note.contains("SHAKE256 (64 bytes)"), "{}", note);
+
+        let result = MCPHandlers::handle_multi_hash(HashParams {
+            data: "abc".to_string(),
+            algorithm: Some("SHA256".to_string()),
+            all_algorithms: false,
+        })
+        .unwrap();
+        assert_eq!(result["bytes"], 32);
+        assert!(result.get("note").is_none());
+
+        let result = MCPHandlers::handle_multi_hash(HashParams {
+            data: "abc".to_string(),
+            algorithm: Some("SHAKE256".to_string()),
+            all_algorithms: false,
+        })
+        .unwrap();
+        assert_eq!(result["bytes"], 64);
+        assert!(result["note"].as_str().unwrap().starts_with("SHAKE256 (64 bytes) is extendable-output"));
+    }
+
+    #[test]
+    fn test_multi_hash_batch_keeps_input_order() {
+        let inputs = ["alpha", "beta", "gamma"];
+        let result = MCPHandlers::handle_multi_hash_batch(HashBatchParams {
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            algorithm: Some("MD5".to_string()),
+        })
+        .unwrap();
+
+        assert_eq!(result["algorithm"], "MD5");
+        assert_eq!(result["count"], 3);
+        let hashes = result["hashes"].as_array().unwrap();
+        assert_eq!(hashes.len(), 3);
+        for (input, hash) in inputs.iter().zip(hashes) {
+            assert_eq!(hash["hash"], MultiHasher::hash(HashAlgorithm::MD5, input.as_bytes()).unwrap().hex);
+            assert_eq!(hash["bytes"], 16);
+        }
+    }
 }
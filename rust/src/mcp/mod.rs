@@ -1,8 +1,11 @@
 // MCP (Model Context Protocol) Server Implementation
 
+pub mod audit;
+pub mod cache;
 pub mod server;
 pub mod handlers;
 pub mod schema;
+pub mod validation;
 
 pub use server::*;
 pub use handlers::*;
@@ -1,7 +1,15 @@
 // MCP Protocol Schema and Types
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::crypto::HashResult;
+use crate::db::models::ScanCallbacks;
+
+/// MCP protocol revisions the server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPRequest {
@@ -29,6 +37,41 @@ pub struct MCPError {
     pub data: Option<Value>,
 }
 
+/// Request errors with their own JSON-RPC code. Any other error is
+/// answered as an internal error (-32603).
+#[derive(Debug)]
+pub enum RpcError {
+    /// Missing or malformed params or tool arguments (-32602)
+    InvalidParams(String),
+    /// No such method (-32601)
+    MethodNotFound(String),
+}
+
+impl RpcError {
+    pub fn code(&self) -> i32 {
+        match self {
+            RpcError::InvalidParams(_) => -32602,
+            RpcError::MethodNotFound(_) => -32601,
+        }
+    }
+
+    /// JSON-RPC code for any error, -32603 unless it is an `RpcError`
+    pub fn code_of(error: &anyhow::Error) -> i32 {
+        error.downcast_ref::<RpcError>().map_or(-32603, RpcError::code)
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::InvalidParams(message) => f.write_str(message),
+            RpcError::MethodNotFound(method) => write!(f, "Unknown method: {}", method),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
 /// MCP Tool Definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPTool {
@@ -37,6 +80,125 @@ pub struct MCPTool {
     pub input_schema: Value,
 }
 
+/// MCP Resource Definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPResource {
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+}
+
+/// initialize parameters; client info and capabilities are not used
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InitializeParams {
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: Option<String>,
+}
+
+/// resources/read parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceReadParams {
+    pub uri: String,
+}
+
+/// Syslog-style severity used by MCP logging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LoggingLevel {
+    /// Closest tracing level; tracing has nothing above error
+    pub fn tracing_level(self) -> tracing::Level {
+        match self {
+            LoggingLevel::Debug => tracing::Level::DEBUG,
+            LoggingLevel::Info | LoggingLevel::Notice => tracing::Level::INFO,
+            LoggingLevel::Warning => tracing::Level::WARN,
+            LoggingLevel::Error | LoggingLevel::Critical | LoggingLevel::Alert | LoggingLevel::Emergency => {
+                tracing::Level::ERROR
+            }
+        }
+    }
+}
+
+/// logging/setLevel parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetLevelParams {
+    pub level: LoggingLevel,
+}
+
+/// MCP Prompt Definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPPrompt {
+    pub name: String,
+    pub description: String,
+    pub arguments: Vec<MCPPromptArgument>,
+}
+
+/// Argument a client fills in before rendering a prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPPromptArgument {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
+/// prompts/get parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptGetParams {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: HashMap<String, String>,
+}
+
+/// Content item in a tools/call result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MCPContent {
+    Text { text: String },
+}
+
+/// tools/call result envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPToolResult {
+    pub content: Vec<MCPContent>,
+    #[serde(rename = "structuredContent", skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+    #[serde(rename = "isError")]
+    pub is_error: bool,
+}
+
+impl MCPToolResult {
+    /// Successful result carrying a JSON value as text and structured content
+    pub fn success(value: Value) -> Self {
+        let text = serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string());
+        Self {
+            content: vec![MCPContent::Text { text }],
+            structured_content: Some(value),
+            is_error: false,
+        }
+    }
+
+    /// Tool-level failure reported to the client rather than as a protocol error
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            content: vec![MCPContent::Text { text: message.into() }],
+            structured_content: None,
+            is_error: true,
+        }
+    }
+}
+
 /// Cryptex query parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CryptexQueryParams {
@@ -48,6 +210,12 @@ pub struct CryptexQueryParams {
     pub search: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
+    /// Narrows search, category and list results to entries with this tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Narrows them to entries changed after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_after: Option<DateTime<Utc>>,
 }
 
 /// Cryptex add entry parameters
@@ -61,6 +229,30 @@ pub struct CryptexAddParams {
     pub rust_impl: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub python_impl: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Cryptex delete parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptexDeleteParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branding_name: Option<String>,
+}
+
+/// Whether `run_exploit` only checks the target or runs the payload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExploitMode {
+    /// Call the exploit's `check()`; the payload is never sent
+    #[default]
+    Check,
+    /// Call the exploit's `run()`
+    Exploit,
 }
 
 /// Exploit execution parameters
@@ -71,6 +263,15 @@ pub struct ExploitRunParams {
     pub target: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<Value>,
+    /// Defaults to `check` so a bare call never fires the payload
+    #[serde(default)]
+    pub mode: ExploitMode,
+    /// Validate and return the planned actions without running the exploit
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Server name sent in TLS handshakes with the target, instead of its host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sni: Option<String>,
 }
 
 /// Scan parameters
@@ -81,6 +282,36 @@ pub struct ScanParams {
     pub scan_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub threads: Option<usize>,
+    #[serde(default)]
+    pub callbacks: ScanCallbacks,
+    /// Validate and return the scan plan without starting it
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Server name sent in TLS handshakes with the target, instead of its host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sni: Option<String>,
+    /// Max requests per second to the target, instead of `scan_rate_limit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<f64>,
+}
+
+/// List exploits parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListExploitsParams {
+    /// Device name, matched case-insensitively as a substring
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_platform: Option<String>,
+    /// Only exploits changed after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_after: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+/// Scan status parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanStatusParams {
+    pub scan_id: String,
 }
 
 /// Hash parameters
@@ -93,6 +324,54 @@ pub struct HashParams {
     pub all_algorithms: bool,
 }
 
+/// Batch hash parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashBatchParams {
+    pub inputs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+}
+
+/// One digest in a `multi_hash` result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashOutput {
+    pub algorithm: String,
+    /// Hex-encoded digest
+    pub hash: String,
+    /// Digest length in bytes
+    pub bytes: usize,
+}
+
+impl From<&HashResult> for HashOutput {
+    fn from(result: &HashResult) -> Self {
+        Self {
+            algorithm: result.label(),
+            hash: result.hex.clone(),
+            bytes: result.hash.len(),
+        }
+    }
+}
+
+impl HashOutput {
+    /// Note for results that include extendable-output functions, whose
+    /// digests are cut to a default length rather than fixed by the algorithm
+    pub fn xof_note(results: &[HashResult]) -> Option<String> {
+        let xof: Vec<String> = results
+            .iter()
+            .filter(|r| r.algorithm.output_size().is_none())
+            .map(|r| format!("{} ({} bytes)", r.algorithm, r.hash.len()))
+            .collect();
+        if xof.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{} {} extendable-output; output truncated to the default length",
+            xof.join(", "),
+            if xof.len() == 1 { "is" } else { "are" }
+        ))
+    }
+}
+
 /// QKD encryption parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QKDEncryptParams {
@@ -100,3 +379,10 @@ pub struct QKDEncryptParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_size: Option<usize>,
 }
+
+/// QKD decryption parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QKDDecryptParams {
+    pub session_id: String,
+    pub ciphertext: String,
+}
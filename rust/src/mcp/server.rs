@@ -1,14 +1,21 @@
 // MCP Server Implementation
 
 use anyhow::{Result, anyhow};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::{json, Value};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use std::io::Write;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::sync::mpsc;
 
+use super::cache;
 use super::schema::*;
 use super::handlers::MCPHandlers;
 
 pub async fn start(transport: &str) -> Result<()> {
+    let config = crate::db::redb_client::config::load().unwrap_or_default();
+    cache::configure(&config);
+    crate::core::jobs::start_from_config(&config);
+
     match transport {
         "stdio" => start_stdio_server().await,
         "http" => start_http_server().await,
@@ -29,39 +36,89 @@ async fn start_stdio_server() -> Result<()> {
         "version": env!("CARGO_PKG_VERSION"),
         "capabilities": {
             "tools": true,
-            "resources": false,
-            "prompts": false
+            "resources": true,
+            "prompts": true,
+            "logging": true
         }
     }))).await?;
 
-    while let Some(line) = lines.next_line().await? {
-        if line.trim().is_empty() {
-            continue;
+    serve_lines(&mut lines, &mut stdout).await
+}
+
+/// Process newline-delimited JSON-RPC messages until the reader is exhausted.
+/// Each line is handled on its own task so a slow tool doesn't hold up the
+/// others. Only this loop writes, one whole line per reply, in the order the
+/// replies complete; clients match them to requests by id.
+async fn serve_lines<R, W>(lines: &mut Lines<R>, writer: &mut W) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (sender, mut replies) = mpsc::unbounded_channel();
+    // Dropped at end of input, so `replies` closes once every task has answered
+    let mut sender = Some(sender);
+
+    loop {
+        tokio::select! {
+            line = lines.next_line(), if sender.is_some() => match line? {
+                Some(line) if line.trim().is_empty() => {}
+                Some(line) => {
+                    if let Some(sender) = &sender {
+                        let sender = sender.clone();
+                        tokio::spawn(async move {
+                            if let Some(reply) = handle_line(&line).await {
+                                // The receiver only goes away if writing failed
+                                let _ = sender.send(reply);
+                            }
+                        });
+                    }
+                }
+                None => sender = None,
+            },
+            reply = replies.recv() => match reply {
+                Some(reply) => send_response_obj(writer, reply).await?,
+                None => break,
+            },
         }
+    }
 
-        match serde_json::from_str::<MCPRequest>(&line) {
-            Ok(request) => {
-                let response = handle_request(request).await;
-                send_response_obj(&mut stdout, response).await?;
+    Ok(())
+}
+
+/// What one input line is answered with
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Reply {
+    Single(MCPResponse),
+    Batch(Vec<MCPResponse>),
+}
+
+async fn handle_line(line: &str) -> Option<Reply> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::error!("Failed to parse request: {}", e);
+            return Some(Reply::Single(error_response(None, -32700, format!("Parse error: {}", e))));
+        }
+    };
+
+    match message {
+        Value::Array(batch) => {
+            if batch.is_empty() {
+                return Some(Reply::Single(error_response(None, -32600, "Invalid request: empty batch".to_string())));
             }
-            Err(e) => {
-                tracing::error!("Failed to parse request: {}", e);
-                let error_response = MCPResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: None,
-                    result: None,
-                    error: Some(MCPError {
-                        code: -32700,
-                        message: format!("Parse error: {}", e),
-                        data: None,
-                    }),
-                };
-                send_response_obj(&mut stdout, error_response).await?;
+
+            // Responses keep request order; notifications are left out
+            let mut responses = Vec::new();
+            for message in batch {
+                if let Some(response) = handle_message(message).await {
+                    responses.push(response);
+                }
             }
+            (!responses.is_empty()).then_some(Reply::Batch(responses))
         }
+        message => handle_message(message).await.map(Reply::Single),
     }
-
-    Ok(())
 }
 
 async fn start_http_server() -> Result<()> {
@@ -69,91 +126,245 @@ async fn start_http_server() -> Result<()> {
     Err(anyhow!("HTTP transport not yet implemented"))
 }
 
-async fn handle_request(request: MCPRequest) -> MCPResponse {
+fn error_response(id: Option<Value>, code: i32, message: String) -> MCPResponse {
+    MCPResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(MCPError {
+            code,
+            message,
+            data: None,
+        }),
+    }
+}
+
+/// Handle one parsed JSON value, rejecting anything that is not a request object
+async fn handle_message(message: Value) -> Option<MCPResponse> {
+    match serde_json::from_value::<MCPRequest>(message) {
+        Ok(request) => handle_request(request).await,
+        Err(e) => Some(error_response(None, -32600, format!("Invalid request: {}", e))),
+    }
+}
+
+/// Handle a single message. Notifications (no id) never produce a response.
+async fn handle_request(request: MCPRequest) -> Option<MCPResponse> {
     tracing::debug!("Handling request: method={}", request.method);
 
-    let result = match request.method.as_str() {
+    let id = request.id.clone();
+    if id.is_none() {
+        let method = request.method.clone();
+        if let Err(e) = dispatch(request).await {
+            tracing::debug!("Notification {} failed: {}", method, e);
+        }
+        return None;
+    }
+
+    Some(match dispatch(request).await {
+        Ok(value) => MCPResponse {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(value),
+            error: None,
+        },
+        Err(e) => error_response(id, RpcError::code_of(&e), e.to_string()),
+    })
+}
+
+fn invalid_params(message: impl Into<String>) -> anyhow::Error {
+    RpcError::InvalidParams(message.into()).into()
+}
+
+/// Method params as `T`, failing with `InvalidParams` when missing or malformed
+fn parse_params<T: DeserializeOwned>(params: Option<Value>) -> Result<T> {
+    let params = params.ok_or_else(|| invalid_params("Missing params"))?;
+    serde_json::from_value(params).map_err(|e| invalid_params(format!("Invalid params: {}", e)))
+}
+
+/// Tool arguments as `T`, failing with `InvalidParams` when malformed
+fn parse_arguments<T: DeserializeOwned>(arguments: Value) -> Result<T> {
+    serde_json::from_value(arguments).map_err(|e| invalid_params(format!("Invalid arguments: {}", e)))
+}
+
+async fn dispatch(request: MCPRequest) -> Result<Value> {
+    match request.method.as_str() {
         "tools/list" => MCPHandlers::list_tools(),
 
         "tools/call" => {
-            let params = request.params.ok_or_else(|| anyhow!("Missing params"))?;
+            let params = request.params.ok_or_else(|| invalid_params("Missing params"))?;
             let tool_name = params.get("name")
                 .and_then(|v| v.as_str())
-                .ok_or_else(|| anyhow!("Missing tool name"))?;
+                .ok_or_else(|| invalid_params("Missing tool name"))?;
             let arguments = params.get("arguments")
-                .ok_or_else(|| anyhow!("Missing arguments"))?;
+                .ok_or_else(|| invalid_params("Missing arguments"))?;
+
+            // Tool failures are reported in the result so the model can see them;
+            // bad arguments are a protocol error like any other bad params
+            let result = match run_tool(tool_name, arguments).await {
+                Ok(value) => MCPToolResult::success(value),
+                Err(e) if e.is::<RpcError>() => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Tool {} failed: {}", tool_name, e);
+                    MCPToolResult::error(e.to_string())
+                }
+            };
+            Ok(serde_json::to_value(result)?)
+        }
+
+        "resources/list" => MCPHandlers::list_resources(),
 
-            handle_tool_call(tool_name, arguments.clone())
+        "resources/read" => {
+            let params: ResourceReadParams = parse_params(request.params)?;
+            MCPHandlers::read_resource(&params.uri)
+        }
+
+        "prompts/list" => MCPHandlers::list_prompts(),
+
+        "prompts/get" => {
+            let params: PromptGetParams = parse_params(request.params)?;
+            MCPHandlers::get_prompt(params)
         }
 
         "initialize" => {
+            let params: InitializeParams = match request.params {
+                Some(params) => parse_params(Some(params))?,
+                None => InitializeParams::default(),
+            };
             Ok(json!({
+                "protocolVersion": negotiate_protocol_version(params.protocol_version.as_deref()),
                 "server": "pyroutersploit-mcp",
                 "version": env!("CARGO_PKG_VERSION"),
                 "capabilities": {
-                    "tools": true
+                    "tools": true,
+                    "resources": true,
+                    "prompts": true,
+                    "logging": true
                 }
             }))
         }
 
-        _ => Err(anyhow!("Unknown method: {}", request.method)),
-    };
+        "logging/setLevel" => {
+            let params: SetLevelParams = parse_params(request.params)?;
+            crate::logging::set_level(params.level.tracing_level())?;
+            tracing::info!("Log level set to {:?}", params.level);
+            Ok(json!({}))
+        }
 
-    match result {
-        Ok(value) => MCPResponse {
-            jsonrpc: "2.0".to_string(),
-            id: request.id,
-            result: Some(value),
-            error: None,
-        },
-        Err(e) => MCPResponse {
-            jsonrpc: "2.0".to_string(),
-            id: request.id,
-            result: None,
-            error: Some(MCPError {
-                code: -32603,
-                message: e.to_string(),
-                data: None,
+        // Liveness check; the spec asks for an empty result
+        "ping" => Ok(json!({})),
+
+        // Client notifications such as notifications/initialized need no action
+        method if method.starts_with("notifications/") => Ok(Value::Null),
+
+        _ => Err(RpcError::MethodNotFound(request.method).into()),
+    }
+}
+
+/// The client's protocol version if supported, else our newest. Per the MCP
+/// spec the client disconnects if it can't use the version we answer with.
+fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    let latest = SUPPORTED_PROTOCOL_VERSIONS[0];
+    match requested {
+        Some(requested) => SUPPORTED_PROTOCOL_VERSIONS
+            .iter()
+            .copied()
+            .find(|version| *version == requested)
+            .unwrap_or_else(|| {
+                tracing::warn!("Client asked for unsupported MCP protocol {}, offering {}", requested, latest);
+                latest
             }),
-        },
+        None => latest,
+    }
+}
+
+/// Run a tool as `tools/call` does, recording telemetry and an audit entry.
+/// Also used by the Node-RED bridge.
+pub(crate) async fn run_tool(tool_name: &str, arguments: &Value) -> Result<Value> {
+    let started = std::time::Instant::now();
+    let outcome = handle_tool_call(tool_name, arguments.clone()).await;
+    crate::telemetry::record_tool_call(tool_name, outcome.is_ok(), started.elapsed());
+    super::audit::record(tool_name, arguments, outcome.as_ref().err());
+    outcome
+}
+
+async fn handle_tool_call(tool_name: &str, arguments: Value) -> Result<Value> {
+    // Reject arguments that don't match the advertised schema before serde sees them
+    if let Some(schema) = MCPHandlers::tool_schema(tool_name) {
+        super::validation::validate(&schema, &arguments)?;
     }
+
+    if !cache::is_cacheable(tool_name) {
+        return call_tool(tool_name, arguments).await;
+    }
+
+    let key = cache::cache_key(tool_name, &arguments);
+    let generation = cache::generation();
+    let cached = cache::get(&key, generation);
+    crate::telemetry::record_tool_cache(tool_name, cached.is_some());
+    if let Some(value) = cached {
+        return Ok(value);
+    }
+    let value = call_tool(tool_name, arguments).await?;
+    cache::insert(key, value.clone(), generation);
+    Ok(value)
 }
 
-fn handle_tool_call(tool_name: &str, arguments: Value) -> Result<Value> {
+async fn call_tool(tool_name: &str, arguments: Value) -> Result<Value> {
     match tool_name {
         "cryptex_query" => {
-            let params: CryptexQueryParams = serde_json::from_value(arguments)?;
+            let params: CryptexQueryParams = parse_arguments(arguments)?;
             MCPHandlers::handle_cryptex_query(params)
         }
         "cryptex_add" => {
-            let params: CryptexAddParams = serde_json::from_value(arguments)?;
+            let params: CryptexAddParams = parse_arguments(arguments)?;
             MCPHandlers::handle_cryptex_add(params)
         }
+        "cryptex_delete" => {
+            let params: CryptexDeleteParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_cryptex_delete(params)
+        }
         "list_exploits" => {
-            MCPHandlers::handle_list_exploits()
+            let params: ListExploitsParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_list_exploits(params)
         }
         "run_exploit" => {
-            let params: ExploitRunParams = serde_json::from_value(arguments)?;
-            MCPHandlers::handle_run_exploit(params)
+            let params: ExploitRunParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_run_exploit(params).await
         }
         "scan_target" => {
-            let params: ScanParams = serde_json::from_value(arguments)?;
-            MCPHandlers::handle_scan_target(params)
+            let params: ScanParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_scan_target(params).await
+        }
+        "scan_status" => {
+            let params: ScanStatusParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_scan_status(params)
+        }
+        "cancel_scan" => {
+            let params: ScanStatusParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_cancel_scan(params)
         }
         "multi_hash" => {
-            let params: HashParams = serde_json::from_value(arguments)?;
+            let params: HashParams = parse_arguments(arguments)?;
             MCPHandlers::handle_multi_hash(params)
         }
+        "multi_hash_batch" => {
+            let params: HashBatchParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_multi_hash_batch(params)
+        }
         "qkd_encrypt" => {
-            let params: QKDEncryptParams = serde_json::from_value(arguments)?;
+            let params: QKDEncryptParams = parse_arguments(arguments)?;
             MCPHandlers::handle_qkd_encrypt(params)
         }
-        _ => Err(anyhow!("Unknown tool: {}", tool_name)),
+        "qkd_decrypt" => {
+            let params: QKDDecryptParams = parse_arguments(arguments)?;
+            MCPHandlers::handle_qkd_decrypt(params)
+        }
+        _ => Err(invalid_params(format!("Unknown tool: {}", tool_name))),
     }
 }
 
-async fn send_response(
-    stdout: &mut tokio::io::Stdout,
+async fn send_response<W: AsyncWrite + Unpin>(
+    stdout: &mut W,
     id: Option<Value>,
     result: Option<Value>,
 ) -> Result<()> {
@@ -166,9 +377,9 @@ async fn send_response(
     send_response_obj(stdout, response).await
 }
 
-async fn send_response_obj(
-    stdout: &mut tokio::io::Stdout,
-    response: MCPResponse,
+async fn send_response_obj<W: AsyncWrite + Unpin>(
+    stdout: &mut W,
+    response: impl Serialize,
 ) -> Result<()> {
     let json = serde_json::to_string(&response)?;
     stdout.write_all(json.as_bytes()).await?;
@@ -180,11 +391,302 @@ async fn send_response_obj(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::exploit::test_support::MockExploit;
 
     #[tokio::test]
     async fn test_handle_list_tools() {
-        crate::db::redb_client::init_database().await.unwrap();
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
         let result = MCPHandlers::list_tools().unwrap();
         assert!(result.get("tools").is_some());
     }
+
+    fn tool_call(name: &str, arguments: Value) -> MCPRequest {
+        MCPRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({ "name": name, "arguments": arguments })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_content_format() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let response = handle_request(tool_call(
+            "multi_hash",
+            json!({ "data": "test", "algorithm": "SHA256" }),
+        ))
+        .await
+        .unwrap();
+        let result = response.result.unwrap();
+
+        assert_eq!(result["isError"], false);
+        let content = result["content"].as_array().unwrap();
+        assert_eq!(content.len(), 1);
+        assert_eq!(content[0]["type"], "text");
+
+        let text: Value = serde_json::from_str(content[0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            text["hash"],
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_failure_sets_is_error() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let scan_id = uuid::Uuid::new_v4().to_string();
+        let response = handle_request(tool_call("scan_status", json!({ "scan_id": scan_id }))).await.unwrap();
+
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["content"][0]["type"], "text");
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_schema_validation() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let response = handle_request(tool_call(
+            "cryptex_query",
+            json!({ "category": "Rootkit" }),
+        ))
+        .await
+        .unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.starts_with("Invalid arguments: `category` must be one of: Exploit"), "{}", error.message);
+
+        let response = handle_request(tool_call("scan_target", json!({ "threads": 4 }))).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.contains("`target`"), "{}", error.message);
+    }
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let request = |method: &str, params: Option<Value>| MCPRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: method.to_string(),
+            params,
+        };
+        let code = |response: Option<MCPResponse>| response.unwrap().error.unwrap().code;
+
+        assert_eq!(code(handle_request(request("tools/fly", None)).await), -32601);
+        assert_eq!(code(handle_request(request("tools/call", None)).await), -32602);
+        assert_eq!(code(handle_request(request("tools/call", Some(json!({ "arguments": {} })))).await), -32602);
+        assert_eq!(code(handle_request(request("tools/call", Some(json!({ "name": "multi_hash" })))).await), -32602);
+        assert_eq!(code(handle_request(tool_call("no_such_tool", json!({}))).await), -32602);
+        assert_eq!(code(handle_request(request("initialize", Some(json!({ "protocolVersion": 7 })))).await), -32602);
+    }
+
+    #[tokio::test]
+    async fn test_cryptex_write_invalidates_cached_query() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let function_name = format!("cached_{}", uuid::Uuid::new_v4().simple());
+        let arguments = json!({ "function_name": function_name });
+
+        let first = handle_tool_call("cryptex_query", arguments.clone()).await.unwrap();
+        assert!(first["result"].is_null());
+
+        // Written straight to the database, not through an MCP tool
+        let entry = crate::db::CryptexEntry::new(
+            function_name.clone(),
+            "Cache Buster".to_string(),
+            "noop()".to_string(),
+            crate::db::models::CryptexCategory::Utility,
+        );
+        crate::db::redb_client::cryptex::insert(&entry).unwrap();
+        let second = handle_tool_call("cryptex_query", arguments).await.unwrap();
+        assert_eq!(second["result"]["function_name"], function_name.as_str());
+
+        let hashed = json!({ "data": "s3cret" });
+        handle_tool_call("multi_hash", hashed.clone()).await.unwrap();
+        assert!(cache::hits(&cache::cache_key("multi_hash", &hashed)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_writes_audit_entry() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        handle_request(tool_call(
+            "run_exploit",
+            json!({ "target": "192.0.2.1", "exploit_id": "no_such_exploit", "options": { "password": "hunter2" } }),
+        ))
+        .await
+        .unwrap();
+        handle_request(tool_call("multi_hash", json!({ "data": "s3cret" }))).await.unwrap();
+
+        let entries = crate::db::redb_client::audit::list(10, 0).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tool, "multi_hash");
+        assert!(entries[0].success);
+        assert_eq!(entries[0].arguments["data"], "[REDACTED]");
+
+        assert_eq!(entries[1].tool, "run_exploit");
+        assert!(!entries[1].success);
+        assert!(entries[1].error.is_some());
+        assert_eq!(entries[1].arguments["target"], "192.0.2.1");
+        assert_eq!(entries[1].arguments["options"]["password"], "[REDACTED]");
+
+        assert_eq!(crate::db::redb_client::audit::list(10, 1).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_protocol_version() {
+        let initialize = |version: &str| -> MCPRequest {
+            serde_json::from_value(json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": { "protocolVersion": version, "capabilities": {}, "clientInfo": { "name": "test" } }
+            }))
+            .unwrap()
+        };
+
+        let result = handle_request(initialize("2024-11-05")).await.unwrap().result.unwrap();
+        assert_eq!(result["protocolVersion"], "2024-11-05");
+        assert_eq!(result["capabilities"]["tools"], true);
+
+        let result = handle_request(initialize("1999-01-01")).await.unwrap().result.unwrap();
+        assert_eq!(result["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let request: MCPRequest = serde_json::from_value(json!({ "jsonrpc": "2.0", "id": "p1", "method": "ping" })).unwrap();
+        let response = handle_request(request).await.unwrap();
+        assert_eq!(response.id, Some(json!("p1")));
+        assert!(response.error.is_none());
+        assert_eq!(response.result, Some(json!({})));
+    }
+
+    #[tokio::test]
+    async fn test_logging_set_level() {
+        let request = |level: &str| -> MCPRequest {
+            serde_json::from_value(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "logging/setLevel",
+                "params": { "level": level }
+            }))
+            .unwrap()
+        };
+
+        let response = handle_request(request("debug")).await.unwrap();
+        assert!(response.error.is_none());
+        assert_eq!(response.result, Some(json!({})));
+
+        let response = handle_request(request("loud")).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_notification_gets_no_response() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#, "\n",
+            r#"{"jsonrpc":"2.0","method":"tools/list"}"#, "\n",
+        );
+        let mut lines = BufReader::new(input.as_bytes()).lines();
+        let mut output = Vec::new();
+
+        serve_lines(&mut lines, &mut output).await.unwrap();
+        assert!(output.is_empty());
+
+        let input = concat!(r#"{"jsonrpc":"2.0","id":7,"method":"tools/list"}"#, "\n");
+        let mut lines = BufReader::new(input.as_bytes()).lines();
+        serve_lines(&mut lines, &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        let response: MCPResponse = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(response.id, Some(json!(7)));
+    }
+
+    #[tokio::test]
+    async fn test_batch_request() {
+        let input = concat!(
+            r#"[{"jsonrpc":"2.0","id":1,"method":"tools/list"},"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"},"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"prompts/list"}]"#, "\n",
+            "[]\n",
+        );
+        let mut lines = BufReader::new(input.as_bytes()).lines();
+        let mut output = Vec::new();
+        serve_lines(&mut lines, &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines();
+
+        let batch: Vec<MCPResponse> = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].id, Some(json!(1)));
+        assert_eq!(batch[1].id, Some(json!(2)));
+
+        let empty: MCPResponse = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(empty.error.unwrap().code, -32600);
+        assert!(lines.next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fast_request_answers_before_slow_one() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        // Takes a while to check, so it answers after requests sent later
+        crate::core::exploit::registry::register("exploit_slow_check", "pyroutersploit_slow_check", || {
+            MockExploit::new("Slow").delay(std::time::Duration::from_millis(300)).boxed()
+        });
+        crate::db::CryptexDictionary::add_entry_with_impl(
+            "exploit_slow_check",
+            "pyroutersploit_slow_check",
+            "Slow check",
+            crate::db::models::CryptexCategory::Exploit,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":"slow","method":"tools/call","params":{"name":"run_exploit","arguments":{"exploit_id":"exploit_slow_check","target":"192.0.2.60"}}}"#, "\n",
+            r#"{"jsonrpc":"2.0","id":"fast","method":"tools/call","params":{"name":"multi_hash","arguments":{"data":"fast"}}}"#, "\n",
+        );
+        let mut lines = BufReader::new(input.as_bytes()).lines();
+        let mut output = Vec::new();
+        serve_lines(&mut lines, &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let responses: Vec<MCPResponse> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].id, Some(json!("fast")));
+        assert_eq!(responses[1].id, Some(json!("slow")));
+        assert_eq!(responses[1].result.as_ref().unwrap()["isError"], false);
+        assert!(responses[1].result.as_ref().unwrap()["content"][0]["text"].as_str().unwrap().contains("\"vulnerable\": true"));
+    }
+
+    #[tokio::test]
+    async fn test_run_exploit_invalid_options() {
+        use crate::core::exploit::test_support::{echo, BRANDING_NAME, FUNCTION_NAME};
+
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        crate::core::exploit::registry::register(FUNCTION_NAME, BRANDING_NAME, || echo().boxed());
+        crate::db::CryptexDictionary::add_entry_with_impl(
+            FUNCTION_NAME,
+            BRANDING_NAME,
+            "Echo exploit",
+            crate::db::models::CryptexCategory::Exploit,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let run = |options: Value| tool_call("run_exploit", json!({ "exploit_id": FUNCTION_NAME, "target": "192.0.2.61", "options": options, "mode": "exploit", "dry_run": true }));
+        let response = handle_request(run(json!({ "rport": 80 }))).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.contains("command"));
+
+        let response = handle_request(run(json!({ "command": "id" }))).await.unwrap();
+        assert_eq!(response.result.unwrap()["isError"], false);
+    }
+
 }
@@ -0,0 +1,125 @@
+// Tool argument validation against the input schemas advertised by tools/list
+
+use anyhow::Result;
+use serde_json::Value;
+
+use super::schema::RpcError;
+
+/// Check `value` against `schema`. Covers the JSON Schema keywords our tool
+/// schemas use: type, enum, properties, required, items and oneOf.
+/// The error is `RpcError::InvalidParams` naming the offending field, e.g.
+/// `callbacks.on_complete`.
+pub fn validate(schema: &Value, value: &Value) -> Result<()> {
+    check(schema, value, "").map_err(|e| RpcError::InvalidParams(format!("Invalid arguments: {}", e)).into())
+}
+
+fn field_name(path: &str) -> &str {
+    if path.is_empty() { "arguments" } else { path }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !type_matches(expected, value) {
+            return Err(format!("`{}` must be of type {}", field_name(path), expected));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed
+                .iter()
+                .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                .collect();
+            return Err(format!("`{}` must be one of: {}", field_name(path), allowed.join(", ")));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            if let Some(missing) = required
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|key| !object.contains_key(*key))
+            {
+                return Err(format!("missing required field `{}`", join(path, missing)));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, property) in properties {
+                if let Some(field) = object.get(key) {
+                    check(property, field, &join(path, key))?;
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check(items, item, &format!("{}[{}]", field_name(path), i))?;
+        }
+    }
+
+    if let Some(variants) = schema.get("oneOf").and_then(Value::as_array) {
+        let matched = variants.iter().filter(|variant| check(variant, value, path).is_ok()).count();
+        if matched != 1 {
+            let options: Vec<String> = variants
+                .iter()
+                .filter_map(|variant| variant.get("required").and_then(Value::as_array))
+                .flat_map(|required| required.iter().filter_map(Value::as_str))
+                .map(|key| format!("`{}`", join(path, key)))
+                .collect();
+            return Err(if options.is_empty() {
+                format!("`{}` must match exactly one allowed form", field_name(path))
+            } else {
+                format!("exactly one of {} is required", options.join(", "))
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nested_field_named() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "callbacks": {
+                    "type": "object",
+                    "properties": { "on_complete": { "type": "string" } }
+                }
+            },
+            "required": ["target"]
+        });
+
+        let err = validate(&schema, &json!({})).unwrap_err();
+        assert!(err.to_string().contains("`target`"));
+
+        let err = validate(&schema, &json!({ "target": "x", "callbacks": { "on_complete": 5 } })).unwrap_err();
+        assert!(err.to_string().contains("`callbacks.on_complete` must be of type string"));
+
+        assert!(validate(&schema, &json!({ "target": "x" })).is_ok());
+    }
+}
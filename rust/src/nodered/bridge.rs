@@ -1,5 +1,284 @@
 // Node-RED bridge for workflow integration
+//
+// Each node POSTs its whole message to /api/nodered/<node>. `msg.payload`
+// holds the tool arguments and comes back replaced by the tool result; the
+// other message properties (`_msgid`, `topic`, ...) are passed through.
 
-use anyhow::Result;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::{json, Map, Value};
 
-// Node-RED bridge implementation will be added here
+use crate::api::auth::TenantUser;
+use crate::api::scans::job_queue;
+use crate::core::jobs::JobQueue;
+use crate::core::scanner;
+use crate::db::models::ScanRequest;
+use crate::mcp::schema::ScanParams;
+use crate::mcp::MCPHandlers;
+
+/// Palette category the nodes are listed under
+pub const CATEGORY: &str = "pyroutersploit";
+pub const PALETTE_PATH: &str = "/api/nodered/palette.json";
+
+/// A node in the palette and the MCP tool it runs
+#[derive(Debug, Clone, Copy)]
+pub struct NodeDefinition {
+    /// Name under /api/nodered/
+    pub name: &'static str,
+    pub label: &'static str,
+    pub tool: &'static str,
+    pub color: &'static str,
+    pub icon: &'static str,
+}
+
+impl NodeDefinition {
+    /// Type registered with `RED.nodes.registerType`
+    pub fn node_type(&self) -> String {
+        format!("pyroutersploit-{}", self.name)
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("/api/nodered/{}", self.name)
+    }
+
+    /// Node-RED `defaults` generated from the tool's input schema
+    fn defaults(&self) -> Value {
+        let schema = MCPHandlers::tool_schema(self.tool).unwrap_or_default();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut defaults = Map::new();
+        defaults.insert("name".to_string(), json!({ "value": "" }));
+        if let Some(properties) = schema["properties"].as_object() {
+            for (field, property) in properties {
+                let value = match property["type"].as_str() {
+                    Some("boolean") => json!(false),
+                    Some("object") => json!({}),
+                    Some("integer") | Some("number") => Value::Null,
+                    _ => json!(""),
+                };
+                defaults.insert(field.clone(), json!({ "value": value, "required": required.contains(&field.as_str()) }));
+            }
+        }
+        Value::Object(defaults)
+    }
+}
+
+pub const NODES: &[NodeDefinition] = &[
+    NodeDefinition { name: "scanner", label: "scan", tool: "scan_target", color: "#E2D96E", icon: "bridge.svg" },
+    NodeDefinition { name: "exploit", label: "run exploit", tool: "run_exploit", color: "#E9967A", icon: "alert.svg" },
+    NodeDefinition { name: "multi-hash", label: "hash", tool: "multi_hash", color: "#C0DEED", icon: "hash.svg" },
+    NodeDefinition { name: "qkd-encrypt", label: "qkd encrypt", tool: "qkd_encrypt", color: "#A6BBCF", icon: "white-globe.svg" },
+];
+
+/// Palette description of every node, for the Node-RED editor package
+pub fn palette() -> Value {
+    let nodes: Vec<Value> = NODES
+        .iter()
+        .map(|node| {
+            json!({
+                "type": node.node_type(),
+                "category": CATEGORY,
+                "label": node.label,
+                "color": node.color,
+                "icon": node.icon,
+                "inputs": 1,
+                "outputs": 1,
+                "endpoint": node.endpoint(),
+                "method": "POST",
+                "defaults": node.defaults(),
+            })
+        })
+        .collect();
+
+    json!({
+        "name": "@pyroutersploit/node-red-contrib-pyroutersploit",
+        "version": env!("CARGO_PKG_VERSION"),
+        "nodes": nodes,
+    })
+}
+
+/// Message as Node-RED's catch node expects it: `msg.error` naming the node type
+fn error_message(mut msg: Map<String, Value>, node_type: &str, message: String) -> Response {
+    msg.insert("error".to_string(), json!({ "message": message, "source": { "type": node_type } }));
+    (StatusCode::BAD_REQUEST, Json(Value::Object(msg))).into_response()
+}
+
+/// Queue a scan for the caller's tenant, like `POST /api/v1/vulnscan/scan`
+async fn run_scan(arguments: Value, tenant_id: &str, queue: Option<JobQueue>) -> anyhow::Result<Value> {
+    let params: ScanParams = serde_json::from_value(arguments)?;
+    let (request, config) = MCPHandlers::scan_job(params)?;
+    let request = ScanRequest { tenant_id: Some(tenant_id.to_string()), ..request };
+    if request.dry_run {
+        let plan = scanner::plan(&request, &config).await?;
+        return Ok(json!({ "success": true, "dry_run": true, "plan": plan }));
+    }
+    let queue = queue.ok_or_else(|| anyhow::anyhow!("Scan queue is not running"))?;
+    let scan = queue.enqueue(request, config).await?;
+    Ok(json!({
+        "success": true,
+        "message": "Scan queued",
+        "scan_id": scan.id,
+        "status": scan.status,
+        "target": scan.target,
+        "scan_type": scan.scan_type
+    }))
+}
+
+/// Runs a node for an authenticated user of a tenant, like the REST scan routes
+async fn run_node(
+    Path(name): Path<String>,
+    TenantUser { user, tenant_id }: TenantUser,
+    queue: Option<Extension<JobQueue>>,
+    Json(msg): Json<Map<String, Value>>,
+) -> Response {
+    let Some(node) = NODES.iter().find(|node| node.name == name) else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": { "message": format!("Unknown node: {}", name) } })))
+            .into_response();
+    };
+    let node_type = node.node_type();
+    let arguments = match msg.get("payload") {
+        Some(Value::Object(arguments)) => Value::Object(arguments.clone()),
+        _ => return error_message(msg, &node_type, "msg.payload must be an object of tool arguments".to_string()),
+    };
+
+    tracing::info!("User {} running Node-RED node {}", user.user_id, node_type);
+    let result = match node.tool {
+        "scan_target" => run_scan(arguments, &tenant_id, job_queue(queue)).await,
+        tool => crate::mcp::server::run_tool(tool, &arguments).await,
+    };
+    match result {
+        Ok(result) => {
+            let mut msg = msg;
+            msg.insert("payload".to_string(), result);
+            Json(Value::Object(msg)).into_response()
+        }
+        Err(e) => error_message(msg, &node_type, e.to_string()),
+    }
+}
+
+async fn palette_json() -> Json<Value> {
+    Json(palette())
+}
+
+/// Node endpoints and the palette, merged into the REST router
+pub fn router() -> Router {
+    Router::new()
+        .route(PALETTE_PATH, get(palette_json))
+        .route("/api/nodered/:node", post(run_node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::{TENANT_ID_HEADER, USER_ID_HEADER};
+    use crate::api::rest::create_router_with_config;
+    use crate::db::models::Config;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_palette_lists_nodes() {
+        let app = create_router_with_config(&Config::default());
+        let response = app
+            .oneshot(Request::get(PALETTE_PATH).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let palette: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+        let types: Vec<&str> = palette["nodes"].as_array().unwrap().iter().map(|n| n["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            types,
+            ["pyroutersploit-scanner", "pyroutersploit-exploit", "pyroutersploit-multi-hash", "pyroutersploit-qkd-encrypt"]
+        );
+        let scanner = &palette["nodes"][0];
+        assert_eq!(scanner["endpoint"], "/api/nodered/scanner");
+        assert_eq!(scanner["defaults"]["target"]["required"], true);
+        assert_eq!(scanner["defaults"]["dry_run"]["value"], false);
+    }
+
+    #[tokio::test]
+    async fn test_node_returns_message() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config::default());
+        let post = |msg: Value| {
+            Request::post("/api/nodered/multi-hash")
+                .header(USER_ID_HEADER, "flow-user")
+                .header(TENANT_ID_HEADER, "tenant-a")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(msg.to_string()))
+                .unwrap()
+        };
+
+        let msg = json!({ "_msgid": "abc.1", "topic": "hashes", "payload": { "data": "test", "algorithm": "SHA256" } });
+        let response = app.clone().oneshot(post(msg)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let msg: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(msg["_msgid"], "abc.1");
+        assert_eq!(msg["topic"], "hashes");
+        assert_eq!(msg["payload"]["hash"], "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+
+        let response = app.oneshot(post(json!({ "payload": "test" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let msg: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(msg["error"]["source"]["type"], "pyroutersploit-multi-hash");
+    }
+
+    #[tokio::test]
+    async fn test_scan_node_belongs_to_tenant() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config::default()).layer(Extension(JobQueue::start(1, 4)));
+
+        let msg = json!({ "_msgid": "scan.1", "payload": { "target": "192.0.2.61", "scan_type": "http" } });
+        let request = Request::post("/api/nodered/scanner")
+            .header(USER_ID_HEADER, "flow-user")
+            .header(TENANT_ID_HEADER, "tenant-a")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(msg.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let msg: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        let scan_id = msg["payload"]["scan_id"].as_str().unwrap().to_string();
+
+        let results = |tenant: &'static str| {
+            Request::get(format!("/api/v1/vulnscan/scans/{}/results", scan_id))
+                .header(USER_ID_HEADER, "analyst")
+                .header(TENANT_ID_HEADER, tenant)
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(app.clone().oneshot(results("tenant-a")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.oneshot(results("tenant-b")).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_nodes_require_tenant_user() {
+        let _db = crate::db::redb_client::test_support::init_database().await.unwrap();
+        let app = create_router_with_config(&Config::default());
+        let msg = json!({ "payload": { "target": "192.0.2.62", "exploit_id": "exploit_missing" } });
+        let post = |headers: &[(&'static str, &'static str)]| {
+            let mut request = Request::post("/api/nodered/exploit").header(header::CONTENT_TYPE, "application/json");
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            request.body(Body::from(msg.to_string())).unwrap()
+        };
+
+        let response = app.clone().oneshot(post(&[])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(post(&[(USER_ID_HEADER, "flow-user")])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.oneshot(post(&[(USER_ID_HEADER, "flow-user"), (TENANT_ID_HEADER, "tenant-a")])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
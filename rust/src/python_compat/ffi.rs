@@ -1,6 +1,149 @@
 // Python FFI using PyO3
 
 use pyo3::prelude::*;
-use anyhow::Result;
+use pyo3::types::IntoPyDict;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::path::Path;
 
-// Python FFI bindings will be added here
+/// Name of the module class every RouterSploit module defines
+const MODULE_CLASS: &str = "Exploit";
+
+/// Method invoked on a Python module instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PythonAction {
+    Check,
+    Run,
+}
+
+impl PythonAction {
+    fn method(self) -> &'static str {
+        match self {
+            PythonAction::Check => "check",
+            PythonAction::Run => "run",
+        }
+    }
+}
+
+/// Convert a Python exception into an error carrying its type and message
+pub(crate) fn py_error(py: Python<'_>, err: PyErr) -> anyhow::Error {
+    let kind = err
+        .get_type(py)
+        .name()
+        .map(str::to_string)
+        .unwrap_or_else(|_| "Exception".to_string());
+    anyhow!("Python {}: {}", kind, err.value(py))
+}
+
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<&'py PyAny> {
+    py.import("json")?.call_method1("loads", (value.to_string(),))
+}
+
+fn to_json(py: Python<'_>, value: &PyAny) -> Result<Value> {
+    // Anything json can't encode natively is stringified
+    let builtins = py.import("builtins").map_err(|e| py_error(py, e))?;
+    let kwargs = [("default", builtins.getattr("str").map_err(|e| py_error(py, e))?)];
+    let encoded: String = py
+        .import("json")
+        .and_then(|json| json.call_method("dumps", (value,), Some(kwargs.into_py_dict(py))))
+        .and_then(|s| s.extract())
+        .map_err(|e| py_error(py, e))?;
+    Ok(serde_json::from_str(&encoded)?)
+}
+
+/// Prepend a directory to `sys.path` so modules under it can be imported
+pub fn add_python_path(path: &Path) -> Result<()> {
+    let path = path.to_string_lossy().to_string();
+
+    Python::with_gil(|py| {
+        let sys_path = py.import("sys")?.getattr("path")?;
+        if !sys_path.contains(&path)? {
+            sys_path.call_method1("insert", (0, &path))?;
+        }
+        Ok(())
+    })
+    .map_err(|e: PyErr| Python::with_gil(|py| py_error(py, e)))
+}
+
+/// Import `module_path`, instantiate its exploit class, apply `options` and invoke `action`.
+/// `options` must be a JSON object; each key is set as an attribute on the instance.
+pub fn call_module(module_path: &str, options: &Value, action: PythonAction) -> Result<Value> {
+    let options = match options {
+        Value::Null => serde_json::Map::new(),
+        Value::Object(map) => map.clone(),
+        _ => return Err(anyhow!("Options must be a JSON object")),
+    };
+
+    Python::with_gil(|py| {
+        let instance = py
+            .import(module_path)
+            .and_then(|module| module.getattr(MODULE_CLASS))
+            .and_then(|class| class.call0())
+            .map_err(|e| py_error(py, e))?;
+
+        for (name, value) in &options {
+            to_python(py, value)
+                .and_then(|value| instance.setattr(name.as_str(), value))
+                .map_err(|e| py_error(py, e))
+                .map_err(|e| anyhow!("Failed to set option {}: {}", name, e))?;
+        }
+
+        let result = instance
+            .call_method0(action.method())
+            .map_err(|e| py_error(py, e))?;
+        to_json(py, result)
+    })
+}
+
+/// Run a Python module's `check()`
+pub fn check_module(module_path: &str, options: &Value) -> Result<Value> {
+    call_module(module_path, options, PythonAction::Check)
+}
+
+/// Run a Python module's `run()`
+pub fn run_module(module_path: &str, options: &Value) -> Result<Value> {
+    call_module(module_path, options, PythonAction::Run)
+}
+
+#[cfg(all(test, feature = "python-tests"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FIXTURE: &str = r#"
+class Exploit:
+    target = ""
+    port = 80
+
+    def check(self):
+        return self.port == 8080
+
+    def run(self):
+        if not self.target:
+            raise ValueError("target is required")
+        return {"target": self.target, "port": self.port}
+"#;
+
+    fn fixture_module() -> String {
+        let dir = std::env::temp_dir().join(format!("pyrsf_ffi_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("trivial_exploit.py"), FIXTURE).unwrap();
+        add_python_path(&dir).unwrap();
+        "trivial_exploit".to_string()
+    }
+
+    #[test]
+    fn test_call_python_module() {
+        let module = fixture_module();
+
+        let result = run_module(&module, &json!({ "target": "192.168.1.1", "port": 8080 })).unwrap();
+        assert_eq!(result, json!({ "target": "192.168.1.1", "port": 8080 }));
+
+        assert_eq!(check_module(&module, &json!({ "port": 8080 })).unwrap(), json!(true));
+        assert_eq!(check_module(&module, &Value::Null).unwrap(), json!(false));
+
+        let err = run_module(&module, &json!({})).unwrap_err();
+        assert!(err.to_string().contains("ValueError"));
+        assert!(run_module("no_such_module_xyz", &json!({})).is_err());
+    }
+}
@@ -1,5 +1,295 @@
 // Legacy Python module loader
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use pyo3::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
 
-// Python module loading will be added here
+use crate::db::models::{ExploitMetadata, Protocol, Severity};
+use super::ffi::py_error;
+
+/// Module kinds imported from the Python tree
+pub const MODULE_KINDS: &[&str] = &["exploits", "scanners", "creds"];
+
+/// Reads a module's `Exploit` class without importing it, so the legacy
+/// module dependencies don't need to be installed
+const EXTRACT_INFO: &str = r#"
+import ast
+import json
+
+def extract(source):
+    tree = ast.parse(source)
+    for node in tree.body:
+        if not (isinstance(node, ast.ClassDef) and node.name == "Exploit"):
+            continue
+        info = {}
+        for stmt in node.body:
+            if isinstance(stmt, ast.Assign) and any(
+                isinstance(t, ast.Name) and t.id == "__info__" for t in stmt.targets
+            ):
+                info = ast.literal_eval(stmt.value)
+        return json.dumps({"bases": [ast.unparse(b) for b in node.bases], "info": info})
+    return None
+"#;
+
+/// A module discovered in the `routersploit.modules` package
+#[derive(Debug, Clone)]
+pub struct LegacyModule {
+    /// Dotted import path, e.g. `routersploit.modules.exploits.routers.dlink.hnap_rce`
+    pub python_path: String,
+    pub metadata: ExploitMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedClass {
+    bases: Vec<String>,
+    info: HashMap<String, serde_json::Value>,
+}
+
+/// Dotted package name of `dir`, found by walking up through `__init__.py` parents
+fn package_name(dir: &Path) -> String {
+    let mut parts = Vec::new();
+    let mut current = Some(dir);
+    while let Some(path) = current {
+        if !path.join("__init__.py").is_file() {
+            break;
+        }
+        match path.file_name() {
+            Some(name) => parts.push(name.to_string_lossy().to_string()),
+            None => break,
+        }
+        current = path.parent();
+    }
+    parts.reverse();
+    parts.join(".")
+}
+
+fn collect_sources(dir: &Path, sources: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(&path, sources)?;
+        } else if path.extension().is_some_and(|ext| ext == "py")
+            && path.file_name().is_some_and(|name| name != "__init__.py")
+        {
+            sources.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Guess the protocol from the module's client base class (HTTPClient, SSHClient, ...)
+fn protocol_from_bases(bases: &[String]) -> Protocol {
+    for base in bases {
+        let name = base.to_uppercase();
+        let protocol = if name.contains("SSH") {
+            Protocol::SSH
+        } else if name.contains("TELNET") {
+            Protocol::Telnet
+        } else if name.contains("FTP") {
+            Protocol::FTP
+        } else if name.contains("SNMP") {
+            Protocol::SNMP
+        } else if name.contains("HTTP") {
+            Protocol::HTTP
+        } else if name.contains("UDP") {
+            Protocol::UDP
+        } else if name.contains("TCP") {
+            Protocol::TCP
+        } else {
+            continue;
+        };
+        return protocol;
+    }
+    Protocol::Custom(bases.first().cloned().unwrap_or_else(|| "python".to_string()))
+}
+
+fn string_list(info: &HashMap<String, serde_json::Value>, key: &str) -> Vec<String> {
+    match info.get(key) {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        Some(serde_json::Value::String(s)) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
+fn to_metadata(kind: &str, category: String, stem: &str, class: ExtractedClass) -> ExploitMetadata {
+    let text = |key: &str| class.info.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+    ExploitMetadata {
+        id: Uuid::new_v4(),
+        name: text("name").unwrap_or_else(|| stem.to_string()),
+        description: text("description").unwrap_or_default(),
+        authors: string_list(&class.info, "authors"),
+        references: string_list(&class.info, "references"),
+        devices: string_list(&class.info, "devices"),
+        category,
+        protocol: protocol_from_bases(&class.bases),
+        severity: match kind {
+            "exploits" => Severity::High,
+            "creds" => Severity::Medium,
+            _ => Severity::Info,
+        },
+        verified: false,
+        cryptex_id: None,
+        cvss_vector: None,
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+/// Walk a `routersploit/modules` directory and extract metadata for every
+/// exploit, scanner and creds module. Modules that fail to parse are skipped.
+pub fn discover_modules(modules_dir: &Path) -> Result<Vec<LegacyModule>> {
+    if !modules_dir.is_dir() {
+        return Err(anyhow!("Module directory not found: {}", modules_dir.display()));
+    }
+    let package = package_name(modules_dir);
+
+    let mut sources = Vec::new();
+    for kind in MODULE_KINDS {
+        let dir = modules_dir.join(kind);
+        if dir.is_dir() {
+            collect_sources(&dir, &mut sources)?;
+        }
+    }
+    sources.sort();
+
+    Python::with_gil(|py| {
+        let extract = PyModule::from_code(py, EXTRACT_INFO, "module_loader.py", "module_loader")
+            .and_then(|module| module.getattr("extract"))
+            .map_err(|e| py_error(py, e))?;
+
+        let mut modules = Vec::new();
+        for path in sources {
+            let relative = path.strip_prefix(modules_dir)?.with_extension("");
+            let parts: Vec<String> = relative
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect();
+            let (stem, dirs) = parts.split_last().ok_or_else(|| anyhow!("Empty module path"))?;
+
+            let source = std::fs::read_to_string(&path)?;
+            let extracted: Option<String> = match extract.call1((source,)).and_then(|r| r.extract()) {
+                Ok(extracted) => extracted,
+                Err(e) => {
+                    tracing::warn!("Skipping {}: {}", path.display(), py_error(py, e));
+                    continue;
+                }
+            };
+            let Some(extracted) = extracted else {
+                tracing::debug!("Skipping {}: no Exploit class", path.display());
+                continue;
+            };
+            let class: ExtractedClass = serde_json::from_str(&extracted)?;
+
+            let python_path = if package.is_empty() {
+                parts.join(".")
+            } else {
+                format!("{}.{}", package, parts.join("."))
+            };
+            modules.push(LegacyModule {
+                python_path,
+                metadata: to_metadata(&dirs[0], dirs.join("/"), stem, class),
+            });
+        }
+        Ok(modules)
+    })
+}
+
+/// Metadata for every legacy module under `modules_dir`
+pub fn load_exploit_metadata(modules_dir: &Path) -> Result<Vec<ExploitMetadata>> {
+    Ok(discover_modules(modules_dir)?
+        .into_iter()
+        .map(|module| module.metadata)
+        .collect())
+}
+
+#[cfg(all(test, feature = "python-tests"))]
+mod tests {
+    use super::*;
+
+    const HNAP_RCE: &str = r#"
+from routersploit.core.exploit import *
+from routersploit.core.http.http_client import HTTPClient
+
+
+class Exploit(HTTPClient):
+    __info__ = {
+        "name": "D-Link HNAP RCE",
+        "description": "Module exploits HNAP SOAPAction command injection.",
+        "authors": (
+            "Marcin Bury <marcin[at]threat9.com>",  # routersploit module
+        ),
+        "references": (
+            "https://www.exploit-db.com/exploits/37171/",
+        ),
+        "devices": (
+            "D-Link DIR-645",
+            "D-Link DIR-815",
+        ),
+    }
+
+    target = OptIP("", "Target IPv4 or IPv6 address")
+"#;
+
+    const SSH_DEFAULT: &str = r#"
+class Exploit(SSHBruteforce):
+    __info__ = {
+        "name": "Acme Router Default SSH Creds",
+        "devices": ("Acme R1",),
+    }
+"#;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn fixture_package() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("pyrsf_modules_{}", Uuid::new_v4().simple()));
+        let modules = root.join("routersploit").join("modules");
+
+        for package in [
+            "routersploit",
+            "routersploit/modules",
+            "routersploit/modules/exploits",
+            "routersploit/modules/exploits/routers",
+            "routersploit/modules/exploits/routers/dlink",
+            "routersploit/modules/creds",
+            "routersploit/modules/payloads",
+        ] {
+            write(&root.join(package).join("__init__.py"), "");
+        }
+        write(&modules.join("exploits/routers/dlink/hnap_rce.py"), HNAP_RCE);
+        write(&modules.join("exploits/routers/dlink/broken.py"), "class Exploit(:\n");
+        write(&modules.join("creds/acme_ssh_default_creds.py"), SSH_DEFAULT);
+        // Payloads are not imported
+        write(&modules.join("payloads/reverse_tcp.py"), SSH_DEFAULT);
+
+        modules
+    }
+
+    #[test]
+    fn test_discover_fixture_modules() {
+        let modules = discover_modules(&fixture_package()).unwrap();
+        assert_eq!(modules.len(), 2);
+
+        let creds = &modules[0];
+        assert_eq!(creds.python_path, "routersploit.modules.creds.acme_ssh_default_creds");
+        assert_eq!(creds.metadata.category, "creds");
+        assert!(matches!(creds.metadata.protocol, Protocol::SSH));
+
+        let rce = &modules[1];
+        assert_eq!(rce.python_path, "routersploit.modules.exploits.routers.dlink.hnap_rce");
+        assert_eq!(rce.metadata.name, "D-Link HNAP RCE");
+        assert_eq!(rce.metadata.category, "exploits/routers/dlink");
+        assert_eq!(rce.metadata.devices, vec!["D-Link DIR-645", "D-Link DIR-815"]);
+        assert_eq!(rce.metadata.references.len(), 1);
+        assert!(matches!(rce.metadata.protocol, Protocol::HTTP));
+        assert_eq!(rce.metadata.severity, Severity::High);
+    }
+}
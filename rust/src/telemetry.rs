@@ -0,0 +1,73 @@
+// Prometheus metrics for the API, scanner and MCP server
+
+use lazy_static::lazy_static;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::time::Duration;
+
+pub const HTTP_REQUESTS_TOTAL: &str = "pyro_http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "pyro_http_request_duration_seconds";
+pub const SCANS_TOTAL: &str = "pyro_scans_total";
+pub const SCAN_DURATION: &str = "pyro_scan_duration_seconds";
+pub const MCP_TOOL_CALLS_TOTAL: &str = "pyro_mcp_tool_calls_total";
+pub const MCP_TOOL_CALL_DURATION: &str = "pyro_mcp_tool_call_duration_seconds";
+pub const MCP_TOOL_CACHE_TOTAL: &str = "pyro_mcp_tool_cache_total";
+
+lazy_static! {
+    static ref PROMETHEUS: PrometheusHandle = install();
+}
+
+/// Install the global recorder. If the embedder already installed its own,
+/// ours is kept detached so `render` still works but stays empty.
+fn install() -> PrometheusHandle {
+    let recorder = PrometheusBuilder::new().build_recorder();
+    let handle = recorder.handle();
+    if let Err(e) = metrics::set_global_recorder(recorder) {
+        tracing::warn!("Metrics recorder not installed: {}", e);
+    }
+    handle
+}
+
+/// Install the recorder. Safe to call more than once.
+pub fn init() {
+    lazy_static::initialize(&PROMETHEUS);
+}
+
+/// Current metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    PROMETHEUS.render()
+}
+
+/// `status` is the HTTP status code, so error rates come from `status=~"4..|5.."`
+pub fn record_http_request(method: &str, path: &str, status: u16, elapsed: Duration) {
+    init();
+    let labels = [
+        ("method", method.to_string()),
+        ("path", path.to_string()),
+        ("status", status.to_string()),
+    ];
+    metrics::counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    metrics::histogram!(HTTP_REQUEST_DURATION, &labels[..2]).record(elapsed.as_secs_f64());
+}
+
+pub fn record_scan(scan_type: &str, status: &str, elapsed: Duration) {
+    init();
+    let labels = [("scan_type", scan_type.to_string()), ("status", status.to_string())];
+    metrics::counter!(SCANS_TOTAL, &labels).increment(1);
+    metrics::histogram!(SCAN_DURATION, &labels[..1]).record(elapsed.as_secs_f64());
+}
+
+pub fn record_tool_call(tool: &str, success: bool, elapsed: Duration) {
+    init();
+    let outcome = if success { "success" } else { "error" };
+    let labels = [("tool", tool.to_string()), ("outcome", outcome.to_string())];
+    metrics::counter!(MCP_TOOL_CALLS_TOTAL, &labels).increment(1);
+    metrics::histogram!(MCP_TOOL_CALL_DURATION, &labels[..1]).record(elapsed.as_secs_f64());
+}
+
+/// `result` is `hit` or `miss`
+pub fn record_tool_cache(tool: &str, hit: bool) {
+    init();
+    let result = if hit { "hit" } else { "miss" };
+    let labels = [("tool", tool.to_string()), ("result", result.to_string())];
+    metrics::counter!(MCP_TOOL_CACHE_TOTAL, &labels).increment(1);
+}
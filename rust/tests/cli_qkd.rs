@@ -0,0 +1,44 @@
+// CLI round trip: qkd encrypt, then qkd decrypt with the saved session
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn run(db_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pyroutersploit"))
+        .env("PYROUTERSPLOIT_DB_PATH", db_path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Value after `label` on the first stdout line containing it
+fn field(output: &Output, label: &str) -> String {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.split_once(label).map(|(_, value)| value.trim().to_string()))
+        .unwrap_or_else(|| panic!("no {:?} in output: {:?}", label, output))
+}
+
+#[test]
+fn test_qkd_encrypt_then_decrypt() {
+    let dir = std::env::temp_dir().join(format!("pyroutersploit-cli-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("cli.redb");
+
+    let encrypted = run(&db_path, &["qkd", "encrypt", "--data", "attack at dawn"]);
+    assert!(encrypted.status.success(), "{:?}", encrypted);
+    let ciphertext = field(&encrypted, "Encrypted (hex):");
+    let session_id = field(&encrypted, "Session ID:");
+
+    let decrypted = run(&db_path, &["qkd", "decrypt", "--ciphertext", &ciphertext, "--session-id", &session_id]);
+    assert!(decrypted.status.success(), "{:?}", decrypted);
+    assert_eq!(field(&decrypted, "Decrypted:"), "attack at dawn");
+
+    // An unknown session fails with a clear error
+    let unknown = uuid::Uuid::new_v4().to_string();
+    let missing = run(&db_path, &["qkd", "decrypt", "--ciphertext", &ciphertext, "--session-id", &unknown]);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("Session not found"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}